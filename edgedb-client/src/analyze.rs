@@ -0,0 +1,146 @@
+//! Query plans returned by the `ANALYZE` statement
+//!
+//! See [`Connection::analyze_query`](crate::client::Connection::analyze_query)
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use serde::Deserialize;
+use serde_json::Value;
+
+
+/// Single node of the query plan
+///
+/// Field names follow the PostgreSQL plan format, which is what the server
+/// reports. Costs are in the planner's arbitrary units, timings are in
+/// milliseconds. Timings and row counts are only known if the query was
+/// actually executed. Fields not listed here are kept in `extra`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[non_exhaustive]
+pub struct PlanNode {
+    #[serde(alias="Node Type")]
+    pub node_type: String,
+    #[serde(default, alias="Startup Cost")]
+    pub startup_cost: Option<f64>,
+    #[serde(default, alias="Total Cost")]
+    pub total_cost: Option<f64>,
+    #[serde(default, alias="Plan Rows")]
+    pub plan_rows: Option<f64>,
+    #[serde(default, alias="Actual Startup Time")]
+    pub actual_startup_time: Option<f64>,
+    #[serde(default, alias="Actual Total Time")]
+    pub actual_total_time: Option<f64>,
+    #[serde(default, alias="Actual Rows")]
+    pub actual_rows: Option<f64>,
+    #[serde(default, alias="Actual Loops")]
+    pub actual_loops: Option<f64>,
+    #[serde(default, alias="Shared Hit Blocks")]
+    pub shared_hit_blocks: Option<u64>,
+    #[serde(default, alias="Shared Read Blocks")]
+    pub shared_read_blocks: Option<u64>,
+    #[serde(default, alias="Plans")]
+    pub plans: Vec<PlanNode>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+/// Parsed output of the `ANALYZE` statement
+#[derive(Debug, Clone)]
+pub struct QueryAnalysis {
+    plan: PlanNode,
+    raw: Value,
+}
+
+impl PlanNode {
+    /// All nodes of the subtree in depth-first order, starting with this one
+    pub fn nodes(&self) -> Vec<&PlanNode> {
+        let mut result = Vec::new();
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            result.push(node);
+            stack.extend(node.plans.iter().rev());
+        }
+        result
+    }
+}
+
+impl QueryAnalysis {
+    /// Parse the JSON document returned by the server
+    ///
+    /// Both the server's own format (plan tree in `fine_grained`) and the
+    /// plain PostgreSQL `EXPLAIN (FORMAT JSON)` output are accepted.
+    pub fn from_json(data: &str) -> anyhow::Result<QueryAnalysis> {
+        let raw: Value = serde_json::from_str(data)
+            .context("analysis output is not valid JSON")?;
+        let root = if let Some(tree) = raw.get("fine_grained") {
+            tree
+        } else if let Some(tree) = raw.get(0).and_then(|x| x.get("Plan")) {
+            tree
+        } else {
+            &raw
+        };
+        let plan = PlanNode::deserialize(root)
+            .context("unexpected structure of the query plan")?;
+        Ok(QueryAnalysis { plan, raw })
+    }
+    /// Root node of the plan
+    pub fn plan(&self) -> &PlanNode {
+        &self.plan
+    }
+    /// Whole document as returned by the server
+    pub fn raw(&self) -> &Value {
+        &self.raw
+    }
+}
+
+#[test]
+fn server_format() {
+    let analysis = QueryAnalysis::from_json(r#"{
+        "buffers": [],
+        "fine_grained": {
+            "node_type": "Nested Loop",
+            "startup_cost": 0.5, "total_cost": 10.25,
+            "plan_rows": 3, "actual_rows": 2,
+            "actual_total_time": 0.125,
+            "plans": [
+                {"node_type": "Index Scan", "index_name": "User_name_idx"},
+                {"node_type": "Seq Scan", "plans": [
+                    {"node_type": "Result"}
+                ]}
+            ]
+        }
+    }"#).unwrap();
+    let plan = analysis.plan();
+    assert_eq!(plan.node_type, "Nested Loop");
+    assert_eq!(plan.total_cost, Some(10.25));
+    assert_eq!(plan.actual_rows, Some(2.0));
+    assert_eq!(plan.actual_loops, None);
+    assert_eq!(plan.plans[0].extra["index_name"], "User_name_idx");
+    assert_eq!(plan.nodes().iter().map(|n| &n.node_type[..])
+               .collect::<Vec<_>>(),
+               vec!["Nested Loop", "Index Scan", "Seq Scan", "Result"]);
+    assert!(analysis.raw().get("buffers").is_some());
+}
+
+#[test]
+fn postgres_format() {
+    let analysis = QueryAnalysis::from_json(r#"[{
+        "Plan": {
+            "Node Type": "Seq Scan",
+            "Startup Cost": 0.0, "Total Cost": 1.5,
+            "Actual Loops": 1, "Shared Hit Blocks": 4
+        },
+        "Execution Time": 0.02
+    }]"#).unwrap();
+    let plan = analysis.plan();
+    assert_eq!(plan.node_type, "Seq Scan");
+    assert_eq!(plan.total_cost, Some(1.5));
+    assert_eq!(plan.actual_loops, Some(1.0));
+    assert_eq!(plan.shared_hit_blocks, Some(4));
+    assert!(plan.plans.is_empty());
+}
+
+#[test]
+fn bad_plan() {
+    assert!(QueryAnalysis::from_json("{").is_err());
+    assert!(QueryAnalysis::from_json(r#"{"costs": 1}"#).is_err());
+}
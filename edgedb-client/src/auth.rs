@@ -0,0 +1,145 @@
+//! Authentication methods used in the connection handshake
+//!
+//! The server lists SASL mechanisms it accepts for the user. By default the
+//! client uses the built-in [`Scram`] if a password is configured. Other
+//! mechanisms can be plugged in using [`Builder::authentication`].
+//!
+//! Note: authenticating with a secret key (e.g. for cloud instances) is not
+//! a SASL mechanism, the key is sent in the handshake, see
+//! [`Builder::secret_key`].
+//!
+//! [`Builder::authentication`]: crate::Builder::authentication
+//! [`Builder::secret_key`]: crate::Builder::secret_key
+use std::fmt;
+use std::mem;
+use std::str;
+
+use bytes::Bytes;
+use scram::ScramClient;
+use scram::client::{ServerFirst, ServerFinal};
+
+
+/// SASL authentication mechanism
+pub trait Authentication: fmt::Debug + Send + Sync {
+    /// Name of the mechanism as listed by the server, e.g. `SCRAM-SHA-256`
+    fn method(&self) -> &str;
+    /// Start authentication of the `user`
+    fn start<'a>(&'a self, user: &'a str)
+        -> anyhow::Result<Box<dyn AuthExchange + 'a>>;
+}
+
+/// State of a single authentication attempt
+///
+/// Methods are called in order: `initial_response`, then `handle_continue`
+/// for every `AuthenticationSASLContinue` message received, and
+/// `handle_final` when server completes the exchange.
+pub trait AuthExchange: Send {
+    /// Data of the `AuthenticationSASLInitialResponse` message
+    fn initial_response(&mut self) -> anyhow::Result<Bytes>;
+    /// Process challenge, returns data of the `AuthenticationSASLResponse`
+    fn handle_continue(&mut self, data: &[u8]) -> anyhow::Result<Bytes>;
+    /// Verify data of the `AuthenticationSASLFinal` message
+    fn handle_final(&mut self, data: &[u8]) -> anyhow::Result<()>;
+}
+
+/// Built-in `SCRAM-SHA-256` password authentication
+#[derive(Clone)]
+pub struct Scram {
+    password: String,
+}
+
+enum ScramState<'a> {
+    Start(ScramClient<'a>),
+    ServerFirst(ServerFirst<'a>),
+    ServerFinal(ServerFinal),
+    Done,
+}
+
+struct ScramExchange<'a> {
+    state: ScramState<'a>,
+}
+
+impl Scram {
+    pub fn new(password: impl Into<String>) -> Scram {
+        Scram { password: password.into() }
+    }
+}
+
+impl fmt::Debug for Scram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Scram")
+            .field("password", &"<hidden>")
+            .finish()
+    }
+}
+
+impl Authentication for Scram {
+    fn method(&self) -> &str {
+        "SCRAM-SHA-256"
+    }
+    fn start<'a>(&'a self, user: &'a str)
+        -> anyhow::Result<Box<dyn AuthExchange + 'a>>
+    {
+        let client = ScramClient::new(user, &self.password, None);
+        Ok(Box::new(ScramExchange { state: ScramState::Start(client) }))
+    }
+}
+
+fn utf8(data: &[u8]) -> anyhow::Result<&str> {
+    str::from_utf8(data)
+        .map_err(|_| anyhow::anyhow!("invalid utf-8 in SCRAM-SHA-256 auth"))
+}
+
+impl AuthExchange for ScramExchange<'_> {
+    fn initial_response(&mut self) -> anyhow::Result<Bytes> {
+        match mem::replace(&mut self.state, ScramState::Done) {
+            ScramState::Start(client) => {
+                let (next, first) = client.client_first();
+                self.state = ScramState::ServerFirst(next);
+                Ok(first.into())
+            }
+            _ => anyhow::bail!("SCRAM exchange is already started"),
+        }
+    }
+    fn handle_continue(&mut self, data: &[u8]) -> anyhow::Result<Bytes> {
+        match mem::replace(&mut self.state, ScramState::Done) {
+            ScramState::ServerFirst(server_first) => {
+                let client_final = server_first
+                    .handle_server_first(utf8(data)?)
+                    .map_err(|e| {
+                        anyhow::anyhow!("Authentication error: {}", e)
+                    })?;
+                let (next, data) = client_final.client_final();
+                self.state = ScramState::ServerFinal(next);
+                Ok(data.into())
+            }
+            _ => anyhow::bail!("unexpected SCRAM-SHA-256 challenge"),
+        }
+    }
+    fn handle_final(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        match mem::replace(&mut self.state, ScramState::Done) {
+            ScramState::ServerFinal(server_final) => {
+                server_final.handle_server_final(utf8(data)?)
+                    .map_err(|e| {
+                        anyhow::anyhow!("Authentication error: {}", e)
+                    })
+            }
+            _ => anyhow::bail!("unexpected end of SCRAM-SHA-256 exchange"),
+        }
+    }
+}
+
+#[test]
+fn scram_order() {
+    let scram = Scram::new("secret");
+    assert_eq!(scram.method(), "SCRAM-SHA-256");
+    assert!(!format!("{:?}", scram).contains("secret"));
+    let mut exchange = scram.start("edgedb").unwrap();
+    assert!(exchange.handle_final(b"v=xxx").is_err());
+
+    let mut exchange = scram.start("edgedb").unwrap();
+    let first = exchange.initial_response().unwrap();
+    assert!(first.starts_with(b"n,,n=edgedb,r="));
+    assert!(exchange.initial_response().is_err());
+    assert!(exchange.handle_continue(b"garbage").is_err());
+}
@@ -0,0 +1,162 @@
+//! Synchronous client for applications that are not async
+//!
+//! This is a thin wrapper around [`Connection`] that runs every request to
+//! completion on the internal async runtime. It's useful for CLI tools,
+//! build scripts and applications that don't use async code otherwise.
+//! Methods must not be called from inside of an async task, as they block
+//! the thread.
+//!
+//! ```rust,ignore
+//! let mut client = blocking::Client::connect(&Builder::from_dsn(dsn)?)?;
+//! let names = client.query::<String>("SELECT User.name",
+//!                                    &Value::empty_tuple())?;
+//! client.transaction(|tx| {
+//!     tx.execute("INSERT User { name := 'John' }")?;
+//!     Ok(())
+//! })?;
+//! ```
+use std::ops::{Deref, DerefMut};
+
+use async_std::prelude::StreamExt;
+use async_std::task::block_on;
+
+use edgedb_protocol::queryable::Queryable;
+use edgedb_protocol::value::Value;
+
+use crate::builder::Builder;
+use crate::client::Connection;
+use crate::status::CommandStatus;
+use crate::transaction;
+
+
+/// A blocking connection to the EdgeDB
+pub struct Client {
+    conn: Connection,
+}
+
+/// A blocking client in the transaction block
+///
+/// This is passed to the body of [`Client::transaction`]. All the methods
+/// of the [`Client`] are available via `Deref`.
+pub struct Transaction<'a> {
+    client: &'a mut Client,
+}
+
+impl Client {
+    /// Connect using the builder
+    ///
+    /// Waits until the database is available, the same way
+    /// [`Builder::connect`] does.
+    pub fn connect(builder: &Builder) -> anyhow::Result<Client> {
+        Ok(Client { conn: block_on(builder.connect())? })
+    }
+    /// Wrap an existing async connection
+    pub fn from_async(conn: Connection) -> Client {
+        Client { conn }
+    }
+    /// Returns the underlying async connection
+    pub fn into_async(self) -> Connection {
+        self.conn
+    }
+    pub fn is_consistent(&self) -> bool {
+        self.conn.is_consistent()
+    }
+    pub fn execute<S>(&mut self, request: S)
+        -> anyhow::Result<CommandStatus>
+        where S: ToString,
+    {
+        block_on(self.conn.execute(request))
+    }
+    /// Run the query and return all the rows
+    pub fn query<R>(&mut self, request: &str, arguments: &Value)
+        -> anyhow::Result<Vec<R>>
+        where R: Queryable,
+    {
+        block_on(async {
+            let mut query = self.conn.query(request, arguments).await?;
+            let mut rows = Vec::new();
+            while let Some(row) = query.next().await {
+                rows.push(row?);
+            }
+            Ok(rows)
+        })
+    }
+    pub fn query_row<R>(&mut self, request: &str, arguments: &Value)
+        -> anyhow::Result<R>
+        where R: Queryable,
+    {
+        block_on(self.conn.query_row(request, arguments))
+    }
+    pub fn query_row_opt<R>(&mut self, request: &str, arguments: &Value)
+        -> anyhow::Result<Option<R>>
+        where R: Queryable,
+    {
+        block_on(self.conn.query_row_opt(request, arguments))
+    }
+    /// Run the query and return the result as a JSON array
+    pub fn query_json(&mut self, request: &str, arguments: &Value)
+        -> anyhow::Result<String>
+    {
+        block_on(async {
+            let mut query = self.conn.query_json(request, arguments).await?;
+            let mut result = String::new();
+            while let Some(chunk) = query.next().await {
+                result.push_str(&chunk?);
+            }
+            Ok(result)
+        })
+    }
+    /// Run `body` in a transaction
+    ///
+    /// Transaction is committed if `body` returns `Ok` and rolled back
+    /// otherwise. See [`Connection::transaction`] for details.
+    pub fn transaction<T, F>(&mut self, mut body: F) -> anyhow::Result<T>
+        where F: FnMut(&mut Transaction<'_>) -> anyhow::Result<T>,
+    {
+        let options = self.conn.transaction_options.clone();
+        block_on(self.conn.start_transaction(&options))?;
+        let result = body(&mut Transaction { client: self });
+        block_on(self.conn.finish_transaction(result))
+    }
+}
+
+impl Transaction<'_> {
+    /// Declare a savepoint, see [`transaction::Transaction::savepoint`]
+    pub fn savepoint(&mut self, name: &str) -> anyhow::Result<()> {
+        let conn = &mut self.client.conn;
+        block_on(transaction::Transaction::new(conn).savepoint(name))
+    }
+    /// Roll back to the savepoint previously declared with
+    /// [`savepoint`](Transaction::savepoint)
+    pub fn rollback_to(&mut self, name: &str) -> anyhow::Result<()> {
+        let conn = &mut self.client.conn;
+        block_on(transaction::Transaction::new(conn).rollback_to(name))
+    }
+    /// Release the savepoint, keeping changes made since it was declared
+    pub fn release(&mut self, name: &str) -> anyhow::Result<()> {
+        let conn = &mut self.client.conn;
+        block_on(transaction::Transaction::new(conn).release(name))
+    }
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            // state of the transaction is unknown, don't reuse connection
+            self.client.conn.dirty = true;
+        }
+    }
+}
+
+impl Deref for Transaction<'_> {
+    type Target = Client;
+    fn deref(&self) -> &Client {
+        self.client
+    }
+}
+
+impl DerefMut for Transaction<'_> {
+    fn deref_mut(&mut self) -> &mut Client {
+        self.client
+    }
+}
@@ -0,0 +1,305 @@
+//! Step-by-step diagnostics of the connection
+//!
+//! [`Builder::diagnose`] runs every stage of establishing a connection
+//! separately and reports which one fails, along with a hint on how to fix
+//! it. This is what `edgedb doctor`-like tools should print:
+//!
+//! ```rust,ignore
+//! let diagnosis = Builder::from_dsn(dsn)?.diagnose().await;
+//! println!("{}", diagnosis);
+//! ```
+//!
+//! Note: this version of the protocol doesn't use TLS, so there is no
+//! separate TLS stage.
+use std::fmt;
+use std::io;
+
+use crate::builder::{Builder, Addr, AddrImpl, Severity, timeout};
+use crate::errors::PasswordRequired;
+use crate::reader::ReadError;
+use crate::transport::Transport;
+
+
+/// Stage of establishing a connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Validation of the builder, see [`Builder::validate`]
+    Config,
+    /// Resolving the host name
+    Resolve,
+    /// Opening a TCP or Unix socket connection
+    Connect,
+    /// Protocol handshake and authentication
+    Handshake,
+}
+
+/// Result of a single stage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Failed(String),
+    /// Stage isn't run because a previous one has failed, or it isn't
+    /// applicable (e.g. resolving host name for a Unix socket)
+    Skipped,
+}
+
+/// Result of a single stage with an optional remediation hint
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub stage: Stage,
+    pub status: Status,
+    pub hint: Option<&'static str>,
+}
+
+/// Report returned by [`Builder::diagnose`]
+///
+/// `Display` implementation prints connection parameters (secrets are
+/// masked) and results of all the stages.
+#[derive(Debug, Clone)]
+pub struct Diagnosis {
+    pub params: Vec<(&'static str, String)>,
+    pub checks: Vec<Check>,
+}
+
+impl Diagnosis {
+    pub fn is_ok(&self) -> bool {
+        self.failed_stage().is_none()
+    }
+    /// Returns the first stage that has failed
+    pub fn failed_stage(&self) -> Option<Stage> {
+        self.checks.iter()
+            .find(|c| matches!(c.status, Status::Failed(_)))
+            .map(|c| c.stage)
+    }
+    fn check(&mut self, stage: Stage, result: Result<(), String>,
+             hint: Option<&'static str>)
+    {
+        let status = match result {
+            Ok(()) => Status::Ok,
+            Err(e) => Status::Failed(e),
+        };
+        self.checks.push(Check { stage, status, hint });
+    }
+    fn skip(&mut self, stage: Stage) {
+        self.checks.push(Check { stage, status: Status::Skipped, hint: None });
+    }
+    fn skip_rest(mut self, stages: &[Stage]) -> Diagnosis {
+        for stage in stages {
+            self.skip(*stage);
+        }
+        self
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Stage::Config => "configuration",
+            Stage::Resolve => "name resolution",
+            Stage::Connect => "connection",
+            Stage::Handshake => "handshake and authentication",
+        })
+    }
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Connection parameters:")?;
+        for (name, value) in &self.params {
+            writeln!(f, "  {}: {}", name, value)?;
+        }
+        writeln!(f, "Checks:")?;
+        for check in &self.checks {
+            match &check.status {
+                Status::Ok => writeln!(f, "  [ok] {}", check.stage)?,
+                Status::Skipped => {
+                    writeln!(f, "  [skipped] {}", check.stage)?
+                }
+                Status::Failed(e) => {
+                    writeln!(f, "  [FAILED] {}: {}", check.stage, e)?;
+                    if let Some(hint) = check.hint {
+                        writeln!(f, "    hint: {}", hint)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn io_kind(e: &anyhow::Error) -> Option<io::ErrorKind> {
+    match e.downcast_ref::<ReadError>() {
+        Some(ReadError::Io { source, .. }) => Some(source.kind()),
+        _ => e.downcast_ref::<io::Error>().map(|e| e.kind()),
+    }
+}
+
+fn connect_hint(e: &anyhow::Error) -> &'static str {
+    use io::ErrorKind::*;
+
+    match io_kind(e) {
+        Some(ConnectionRefused) | Some(NotFound) => {
+            "server is not running or listens on a different address; \
+             check that the instance is started and the port is correct"
+        }
+        Some(TimedOut) => {
+            "server is unreachable; check firewall rules and network \
+             connectivity, or increase connect timeout"
+        }
+        Some(PermissionDenied) => {
+            "no permission to access the socket; check file permissions \
+             of the Unix socket or the runtime directory"
+        }
+        _ => "check that host and port point to the EdgeDB instance",
+    }
+}
+
+fn handshake_hint(e: &anyhow::Error) -> &'static str {
+    if e.is::<PasswordRequired>() {
+        return "server requires a password for this user; \
+                set password in the DSN or credentials file";
+    }
+    match io_kind(e) {
+        Some(_) => {
+            "connection was closed during the handshake; make sure the \
+             port belongs to EdgeDB and not to another service"
+        }
+        None if e.downcast_ref::<ReadError>().is_some() => {
+            "server replied with an unexpected data; make sure the port \
+             belongs to EdgeDB and the server version is supported"
+        }
+        None => {
+            "check user name, password (or secret key) and database name"
+        }
+    }
+}
+
+impl Builder {
+    /// Run all the stages of connecting and report which one fails
+    ///
+    /// Unlike [`connect`](Builder::connect), this never waits until the
+    /// server is available and doesn't retry. Each stage is limited by
+    /// [`connect_timeout`](Builder::connect_timeout). See
+    /// [`doctor`](crate::doctor) module.
+    pub async fn diagnose(&self) -> Diagnosis {
+        use Stage::*;
+
+        let mut diag = Diagnosis {
+            params: self.masked_params(),
+            checks: Vec::new(),
+        };
+
+        let errors = self.validate().into_iter()
+            .filter(|p| p.severity == Severity::Error)
+            .map(|p| p.message)
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            diag.check(Config, Err(errors.join("; ")),
+                Some("fix connection options, DSN or credentials file"));
+            return diag.skip_rest(&[Resolve, Connect, Handshake]);
+        }
+        diag.check(Config, Ok(()), None);
+
+        let transport = match &self.addr {
+            Addr(AddrImpl::Tcp(host, port)) => {
+                let resolved = timeout(self.connect_timeout, async {
+                    Ok(self.addr_cache.resolve(host, *port,
+                                               self.dns_ttl).await?)
+                }).await;
+                let addrs = match resolved {
+                    Ok(addrs) if addrs.is_empty() => {
+                        diag.check(Resolve,
+                            Err(format!("{:?} has no addresses", host)),
+                            Some("check the host name"));
+                        return diag.skip_rest(&[Connect, Handshake]);
+                    }
+                    Ok(addrs) => addrs,
+                    Err(e) => {
+                        diag.check(Resolve, Err(format!("{:#}", e)),
+                            Some("check the host name and DNS settings"));
+                        return diag.skip_rest(&[Connect, Handshake]);
+                    }
+                };
+                diag.check(Resolve, Ok(()), None);
+                timeout(self.connect_timeout, async {
+                    use async_std::net::TcpStream;
+
+                    let conn = TcpStream::connect(&addrs[..]).await?;
+                    self.tcp.apply(&conn)?;
+                    Ok(Transport::from(conn))
+                }).await
+            }
+            Addr(AddrImpl::Unix(_)) => {
+                diag.skip(Resolve);
+                timeout(self.connect_timeout, self.open_unix()).await
+            }
+        };
+        let transport = match transport {
+            Ok(transport) => transport,
+            Err(e) => {
+                self.addr_cache.invalidate();
+                diag.check(Connect, Err(format!("{:#}", e)),
+                           Some(connect_hint(&e)));
+                return diag.skip_rest(&[Handshake]);
+            }
+        };
+        diag.check(Connect, Ok(()), None);
+
+        let conn = timeout(self.connect_timeout,
+                           self.connect_transport(transport)).await;
+        match conn {
+            Ok(_) => diag.check(Handshake, Ok(()), None),
+            Err(e) => {
+                diag.check(Handshake, Err(format!("{:#}", e)),
+                           Some(handshake_hint(&e)));
+            }
+        }
+        diag
+    }
+    fn masked_params(&self) -> Vec<(&'static str, String)> {
+        fn mask(value: &Option<String>) -> String {
+            value.as_ref().map(|_| "<hidden>").unwrap_or("<none>").into()
+        }
+        vec![
+            ("address", self.addr.to_string()),
+            ("user", self.user.clone()),
+            ("password", mask(&self.password)),
+            ("secret_key", mask(&self.secret_key)),
+            ("database", self.database.clone()),
+            ("connect_timeout", format!("{:?}", self.connect_timeout)),
+        ]
+    }
+}
+
+#[test]
+fn invalid_config() {
+    use async_std::task::block_on;
+
+    let mut builder = Builder::new();
+    builder.tcp_addr("", 5656).password("secret");
+    let diag = block_on(builder.diagnose());
+    assert!(!diag.is_ok());
+    assert_eq!(diag.failed_stage(), Some(Stage::Config));
+    assert_eq!(diag.checks.len(), 4);
+    assert_eq!(diag.checks[3].status, Status::Skipped);
+    let text = diag.to_string();
+    assert!(text.contains("password: <hidden>"));
+    assert!(!text.contains("secret\n"));
+    assert!(text.contains("[FAILED] configuration"));
+}
+
+#[test]
+fn connection_refused() {
+    use async_std::task::block_on;
+
+    // bind and drop listener to find a port nobody listens on
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap()
+        .local_addr().unwrap().port();
+    let mut builder = Builder::new();
+    builder.tcp_addr("127.0.0.1", port);
+    let diag = block_on(builder.diagnose());
+    assert_eq!(diag.failed_stage(), Some(Stage::Connect));
+    assert_eq!(diag.checks[1].status, Status::Ok);
+    assert!(diag.checks[2].hint.unwrap().contains("not running"));
+    assert_eq!(diag.checks[3].status, Status::Skipped);
+}
@@ -0,0 +1,94 @@
+//! Cancellation of the requests running on a connection
+//!
+//! ```rust,ignore
+//! let handle = conn.cancel_handle();
+//! task::spawn(async move {
+//!     task::sleep(Duration::from_secs(5)).await;
+//!     handle.cancel();
+//! });
+//! let result = conn.query_row::<i64>(long_query, &args).await;
+//! ```
+//!
+//! This version of the protocol has no message to cancel a query, so
+//! cancellation shuts the connection down, which makes the server abort
+//! the query, and fails the request in progress with
+//! [`ReadError::Cancelled`]. The connection can't be used after that
+//! (connections from the pool are discarded automatically).
+//!
+//! Custom transports should provide
+//! [`Transport::with_shutdown`], otherwise the query keeps running on the
+//! server until the connection is dropped.
+//!
+//! [`ReadError::Cancelled`]: crate::reader::ReadError::Cancelled
+//! [`Transport::with_shutdown`]: crate::transport::Transport::with_shutdown
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Waker};
+
+
+/// A handle that cancels requests on the connection it's created for
+///
+/// Obtained with [`Connection::cancel_handle`]. Handle is cheap to clone
+/// and can be sent to another task. Cancellation is permanent: once
+/// cancelled, the connection can't be used anymore.
+///
+/// [`Connection::cancel_handle`]: crate::client::Connection::cancel_handle
+#[derive(Debug, Clone, Default)]
+pub struct CancelHandle {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl CancelHandle {
+    /// Cancel the request in progress and all subsequent requests
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        if let Some(waker) = self.inner.waker.lock().expect("lock").take() {
+            waker.wake();
+        }
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+    /// Check for cancellation, registering the task to be woken up on
+    /// `cancel()` otherwise
+    pub(crate) fn poll_cancelled(&self, cx: &mut Context) -> bool {
+        if self.is_cancelled() {
+            return true;
+        }
+        *self.inner.waker.lock().expect("lock") = Some(cx.waker().clone());
+        // cancel() might be called before waker is stored
+        self.is_cancelled()
+    }
+}
+
+#[test]
+fn cancel() {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::Poll;
+
+    struct Wait(CancelHandle);
+    impl Future for Wait {
+        type Output = ();
+        fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+            if self.0.poll_cancelled(cx) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        }
+    }
+
+    let handle = CancelHandle::default();
+    let wait = async_std::task::spawn(Wait(handle.clone()));
+    assert!(!handle.is_cancelled());
+    handle.clone().cancel();
+    async_std::task::block_on(wait);
+    assert!(handle.is_cancelled());
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::default::Default;
 use std::fmt;
+use std::pin::Pin;
 use std::str;
 use std::sync::Arc;
 use std::time::Duration;
@@ -10,37 +11,132 @@ use async_std::prelude::StreamExt;
 use async_std::future::{timeout, pending};
 use async_std::io::prelude::WriteExt;
 use async_std::io::ReadExt;
-use async_listen::ByteStream;
 use bytes::{Bytes, BytesMut};
 use typemap::TypeMap;
 
 use edgedb_protocol::client_message::ClientMessage;
-use edgedb_protocol::client_message::{Prepare, IoFormat, Cardinality};
+use edgedb_protocol::client_message::{Prepare, Cardinality};
 use edgedb_protocol::client_message::{DescribeStatement, DescribeAspect};
 use edgedb_protocol::client_message::{Execute, ExecuteScript};
+use edgedb_protocol::client_message::OptimisticExecute;
+use edgedb_protocol::client_message::{Capabilities, HEADER_ALLOW_CAPABILITIES};
 use edgedb_protocol::codec::Codec;
-use edgedb_protocol::server_message::ServerMessage;
-use edgedb_protocol::server_message::{TransactionState};
-use edgedb_protocol::queryable::{Queryable, Decoder};
+use edgedb_protocol::server_message::{ServerMessage, CommandDataDescription};
+use edgedb_protocol::server_message::{TransactionState, LogMessage};
+use edgedb_protocol::server_message::ErrorResponse;
+use edgedb_protocol::queryable::{Queryable, Decoder, DescriptorMismatch};
+use edgedb_protocol::descriptors::TypePos;
 use edgedb_protocol::value::Value;
 use edgedb_protocol::descriptors::OutputTypedesc;
 
-use crate::server_params::ServerParam;
-use crate::reader::{self, QueryableDecoder, QueryResponse, Reader};
-use crate::errors::NoResultExpected;
+use crate::Builder;
+use crate::analyze::QueryAnalysis;
+use crate::server_info::ServerInfo;
+use crate::status::CommandStatus;
+use crate::server_params::{ServerParam, ParamWatch, ParameterUpdate};
+use crate::cancel::CancelHandle;
+use crate::messages::MessageWatch;
+use crate::reader::{self, QueryableDecoder, QueryResponse, QueryResult};
+use crate::reader::{Reader, InputBuffer};
+use crate::errors::{NoResultExpected, ExecuteManyError, QuerySource};
+use crate::errors::TransactionControlStatement;
+use crate::statement::is_transaction_control;
+use crate::retry::RetryOptions;
+use crate::transaction::TransactionOptions;
+use crate::transport::{ReadHalf, WriteHalf, Shutdown};
+
+use codecs::CodecCache;
+use statements::{StatementCache, StatementKey};
+use reconnect::SessionState;
 
 pub use crate::features::ProtocolVersion;
+pub use edgedb_protocol::client_message::IoFormat;
+pub use pool::{Pool, PoolOptions, PoolConnection, CloseStats};
+pub use options::QueryOptions;
+pub use pipeline::{Pipeline, PipelineResult};
+pub use csv::CSV_SCALAR_COLUMN;
+#[cfg(feature="arrow")]
+pub use arrow::ARROW_SCALAR_COLUMN;
+
+mod aliases;
+#[cfg(feature="arrow")]
+mod arrow;
+mod codecs;
+mod config;
+mod csv;
+mod globals;
+mod options;
+#[cfg(feature="rayon")]
+mod parallel;
+mod pipeline;
+mod pool;
+mod reconnect;
+pub(crate) mod statements;
+
+/// Capabilities that are not allowed for read-only connections
+pub const READ_ONLY_FORBIDDEN: Capabilities = Capabilities::MODIFICATIONS
+    .union(Capabilities::DDL)
+    .union(Capabilities::PERSISTENT_CONFIG);
+
+/// Maximum number of statements sent in a single pipeline by `insert_many`
+pub const INSERT_BATCH_SIZE: usize = 100;
+
+/// Maximum length of the query tag in bytes
+pub const MAX_QUERY_TAG_LENGTH: usize = 128;
+
+fn validate_query_tag(tag: &str) -> anyhow::Result<()> {
+    if tag.len() > MAX_QUERY_TAG_LENGTH {
+        anyhow::bail!("query tag is longer than {} bytes",
+                      MAX_QUERY_TAG_LENGTH);
+    }
+    if tag.contains(|c: char| c.is_control()) {
+        anyhow::bail!("query tag must not contain control characters");
+    }
+    if tag.starts_with("edgedb/") {
+        anyhow::bail!("query tags starting with \"edgedb/\" are reserved");
+    }
+    Ok(())
+}
 
+/// Prefix statement text with the tag comment
+fn tag_statement(tag: Option<&str>, text: &str) -> String {
+    match tag {
+        Some(tag) => format!("# tag: {}\n{}", tag, text),
+        None => text.to_owned(),
+    }
+}
+
+/// Number of characters `tag_statement` prepends to the text
+fn tag_length(tag: Option<&str>) -> usize {
+    tag.map(|tag| "# tag: \n".len() + tag.chars().count()).unwrap_or(0)
+}
 
 /// A single connection to the EdgeDB
 pub struct Connection {
-    pub(crate) stream: ByteStream,
-    pub(crate) input_buf: BytesMut,
+    pub(crate) read: ReadHalf,
+    pub(crate) write: WriteHalf,
+    pub(crate) shutdown: Option<Shutdown>,
+    pub(crate) input_buf: InputBuffer,
     pub(crate) output_buf: BytesMut,
     pub(crate) version: ProtocolVersion,
     pub(crate) params: TypeMap<dyn typemap::DebugAny + Send + Sync>,
+    pub(crate) param_watch: ParamWatch,
+    pub(crate) message_watch: MessageWatch,
+    pub(crate) cancel: CancelHandle,
     pub(crate) transaction_state: TransactionState,
     pub(crate) dirty: bool,
+    pub(crate) transaction_options: TransactionOptions,
+    pub(crate) retry_options: RetryOptions,
+    pub(crate) read_only: bool,
+    pub(crate) allow_capabilities: Capabilities,
+    pub(crate) query_tag: Option<String>,
+    pub(crate) query_options: QueryOptions,
+    pub(crate) query_source_limit: Option<usize>,
+    pub(crate) query_timeout: Option<Duration>,
+    pub(crate) codecs: Arc<CodecCache>,
+    pub(crate) statements: StatementCache,
+    pub(crate) reconnect: Option<Box<Builder>>,
+    pub(crate) session: SessionState,
 }
 
 pub struct Sequence<'a> {
@@ -49,26 +145,35 @@ pub struct Sequence<'a> {
     pub(crate) active: bool,
     dirty: &'a mut bool,
     proto: &'a ProtocolVersion,
+    allow_capabilities: Capabilities,
+    query_tag: Option<&'a str>,
+    query_options: &'a QueryOptions,
+    query_source_limit: Option<usize>,
+    codecs: &'a CodecCache,
+    statements: &'a mut StatementCache,
 }
 
 
 pub struct Writer<'a> {
-    stream: &'a ByteStream,
+    stream: &'a mut WriteHalf,
     outbuf: &'a mut BytesMut,
 }
 
 
 impl<'a> Sequence<'a> {
 
-    pub fn response<D: reader::Decode>(self, decoder: D)
+    pub fn response<D: reader::Decode>(self, decoder: D, request: &str)
         -> QueryResponse<'a, D>
     {
         assert!(self.active);  // TODO(tailhook) maybe debug_assert
         reader::QueryResponse {
+            // only needed to attach query source to errors
+            request: self.query_source_limit.map(|_| request.to_owned()),
             seq: self,
             buffer: Vec::new(),
             error: None,
             complete: false,
+            done: false,
             decoder,
         }
     }
@@ -78,11 +183,52 @@ impl<'a> Sequence<'a> {
         *self.dirty = false;
     }
 
+    /// Headers for the `Prepare` and `ExecuteScript` messages
+    ///
+    /// The `extra` capabilities are allowed in addition to the ones
+    /// allowed for the connection. This is used for statements issued by
+    /// the library itself (e.g. `START TRANSACTION`).
+    fn headers(&self, extra: Capabilities) -> HashMap<u16, Bytes> {
+        let mut headers = HashMap::new();
+        let allow = self.allow_capabilities | extra;
+        if allow != Capabilities::ALL {
+            let caps = allow.bits().to_be_bytes();
+            headers.insert(HEADER_ALLOW_CAPABILITIES,
+                           Bytes::copy_from_slice(&caps[..]));
+        }
+        headers
+    }
+
+    /// Headers for the `Prepare` and `OptimisticExecute` messages
+    fn prepare_headers(&self) -> HashMap<u16, Bytes> {
+        let mut headers = self.headers(Capabilities::NONE);
+        self.query_options.add_headers(&mut headers);
+        headers
+    }
+
     fn decoder(&self) -> Decoder {
         let mut dec = Decoder::default();
-        dec.has_implicit_tid = self.proto.has_implicit_tid();
+        dec.has_implicit_tid = self.has_implicit_tid();
+        dec.has_implicit_tname = self.query_options.implicit_typenames();
+        dec.has_implicit_id = self.query_options.implicit_ids();
         return dec;
     }
+
+    fn has_implicit_tid(&self) -> bool {
+        self.proto.has_implicit_tid() || self.query_options.implicit_typeids()
+    }
+
+    /// Check that descriptor matches the type decoded by `T`
+    fn check_descriptor<T: Queryable>(&self,
+        desc: &OutputTypedesc, root_pos: TypePos)
+        -> Result<(), DescriptorMismatch>
+    {
+        let mut ctx = desc.as_queryable_context();
+        ctx.has_implicit_tid = self.has_implicit_tid();
+        ctx.has_implicit_tname = self.query_options.implicit_typenames();
+        ctx.has_implicit_id = self.query_options.implicit_ids();
+        T::check_descriptor(&ctx, root_pos)
+    }
 }
 
 impl Connection {
@@ -91,7 +237,7 @@ impl Connection {
     }
     pub async fn passive_wait<T>(&mut self) -> T {
         let mut buf = [0u8; 1];
-        self.stream.read(&mut buf[..]).await.ok();
+        self.read.read(&mut buf[..]).await.ok();
         // any erroneous or successful read (even 0) means need reconnect
         self.dirty = true;
         pending::<()>().await;
@@ -112,19 +258,40 @@ impl Connection {
     pub async fn start_sequence<'x>(&'x mut self)
         -> anyhow::Result<Sequence<'x>>
     {
+        if self.cancel.is_cancelled() {
+            self.dirty = true;
+            Err(reader::ReadError::Cancelled)?;
+        }
+        if self.should_reconnect() {
+            self.reconnect().await?;
+        }
         if self.dirty {
             anyhow::bail!("Connection is inconsistent state. \
                 Please reconnect.");
         }
         self.dirty = true;
+        self.message_watch.clear_warnings();
         let reader = Reader {
             buf: &mut self.input_buf,
-            stream: &self.stream,
+            stream: &mut self.read,
             transaction_state: &mut self.transaction_state,
+            params: &mut self.param_watch,
+            messages: &mut self.message_watch,
+            cancel: &self.cancel,
+            shutdown: self.shutdown.as_deref(),
+            peeked: None,
+            timeout: self.query_timeout.map(|dur| {
+                Box::pin(async_std::task::sleep(dur)) as Pin<Box<_>>
+            }),
         };
         let writer = Writer {
             outbuf: &mut self.output_buf,
-            stream: &self.stream,
+            stream: &mut self.write,
+        };
+        let allow_capabilities = if self.read_only {
+            self.allow_capabilities - READ_ONLY_FORBIDDEN
+        } else {
+            self.allow_capabilities
         };
         Ok(Sequence {
             writer,
@@ -132,18 +299,212 @@ impl Connection {
             active: true,
             dirty: &mut self.dirty,
             proto: &self.version,
+            allow_capabilities,
+            query_tag: self.query_tag.as_deref(),
+            query_options: &self.query_options,
+            query_source_limit: self.query_source_limit,
+            codecs: &self.codecs,
+            statements: &mut self.statements,
         })
     }
 
+    /// Value of the server parameter received on handshake
+    ///
+    /// Use [`server_param`](Connection::server_param) to get the latest
+    /// value, if parameter might change during the session.
     pub fn get_param<T: ServerParam>(&self)
         -> Option<&<T as typemap::Key>::Value>
         where <T as typemap::Key>::Value: fmt::Debug + Send + Sync
     {
         self.params.get::<T>()
     }
+    /// Latest value of the server parameter
+    ///
+    /// Returns `None` if server hasn't sent the parameter and an error if
+    /// the value can't be decoded.
+    ///
+    /// ```rust,ignore
+    /// let config = conn.server_param::<SystemConfig>()?;
+    /// ```
+    pub fn server_param<T: ServerParam>(&self) -> anyhow::Result<Option<T>> {
+        self.param_watch.get(T::NAME).map(T::decode).transpose()
+    }
+    /// Latest raw value of the server parameter
+    pub fn get_raw_param(&self, name: &str) -> Option<&Bytes> {
+        self.param_watch.get(name)
+    }
+    /// Latest raw values of all parameters sent by the server
+    pub fn raw_params(&self) -> &HashMap<String, Bytes> {
+        self.param_watch.values()
+    }
+    /// Number of concurrent connections suggested by the server
+    pub fn suggested_pool_concurrency(&self) -> Option<usize> {
+        let value = self.get_raw_param("suggested_pool_concurrency")?;
+        str::from_utf8(value).ok()?.trim().parse().ok()
+    }
+    /// Subscribe to the updates of server parameters
+    ///
+    /// Every `ParameterStatus` message received after this call is sent to
+    /// the returned channel. Note that messages are only received while
+    /// the connection is used for queries (or waits in
+    /// [`passive_wait`](Connection::passive_wait)), as there is no
+    /// background task reading the connection. Dropping the receiver
+    /// unsubscribes.
+    pub fn subscribe_params(&mut self)
+        -> async_std::channel::Receiver<ParameterUpdate>
+    {
+        self.param_watch.subscribe()
+    }
+    /// Register a handler for log messages sent by the server
+    ///
+    /// Handler is called for every `LogMessage` (e.g. a deprecation
+    /// warning) received by this connection. When there are no handlers
+    /// and subscribers, messages are written to the log instead. See
+    /// [`messages`](crate::messages) module.
+    pub fn on_log_message<F>(&mut self, handler: F)
+        where F: Fn(&LogMessage) + Send + Sync + 'static,
+    {
+        self.message_watch.add_handler(Box::new(handler));
+    }
+    /// Subscribe to log messages sent by the server
+    ///
+    /// Same as [`on_log_message`](Connection::on_log_message) but returns
+    /// a channel. Like with [`subscribe_params`], messages are only
+    /// received while the connection is in use. Dropping the receiver
+    /// unsubscribes.
+    ///
+    /// [`subscribe_params`]: Connection::subscribe_params
+    pub fn subscribe_log_messages(&mut self)
+        -> async_std::channel::Receiver<LogMessage>
+    {
+        self.message_watch.subscribe()
+    }
+    /// Returns a handle that cancels requests on this connection
+    ///
+    /// See [`cancel`](crate::cancel) module for details.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
     pub fn transaction_state(&self) -> TransactionState {
         self.transaction_state
     }
+    /// Returns connection with overridden transaction options
+    ///
+    /// By default options are inherited from the [`Builder`].
+    ///
+    /// [`Builder`]: crate::Builder
+    pub fn with_transaction_options(mut self, options: TransactionOptions)
+        -> Connection
+    {
+        self.transaction_options = options;
+        self
+    }
+    /// Returns connection with overridden retry options
+    ///
+    /// By default options are inherited from the [`Builder`].
+    ///
+    /// [`Builder`]: crate::Builder
+    pub fn with_retry_options(mut self, options: RetryOptions) -> Connection {
+        self.retry_options = options;
+        self
+    }
+    /// Returns connection with read-only mode enabled or disabled
+    ///
+    /// In read-only mode queries that modify data, schema or persistent
+    /// configuration are rejected by the server and transactions are
+    /// started as `READ ONLY`. By default the mode is inherited from the
+    /// [`Builder`].
+    ///
+    /// [`Builder`]: crate::Builder
+    pub fn with_read_only(mut self, read_only: bool) -> Connection {
+        self.read_only = read_only;
+        self
+    }
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+    /// Returns connection with overridden query timeout
+    ///
+    /// By default the timeout is inherited from the [`Builder`], see
+    /// [`Builder::query_timeout`] for details.
+    ///
+    /// [`Builder`]: crate::Builder
+    /// [`Builder::query_timeout`]: crate::Builder::query_timeout
+    pub fn with_query_timeout(mut self, timeout: Option<Duration>)
+        -> Connection
+    {
+        self.query_timeout = timeout;
+        self
+    }
+    pub fn query_timeout(&self) -> Option<Duration> {
+        self.query_timeout
+    }
+    /// Restrict what subsequent queries are allowed to do
+    ///
+    /// This sets the `ALLOW_CAPABILITIES` header for every query and
+    /// statement executed on this connection until changed again. Server
+    /// rejects a command that requires a capability not in the set. For
+    /// example, this forbids schema and configuration changes, as well as
+    /// transaction control statements, but allows modifying data:
+    ///
+    /// ```rust,ignore
+    /// conn.allow_capabilities(Capabilities::MODIFICATIONS);
+    /// let rows = conn.query::<String>(&user_supplied_query, &args).await?;
+    /// conn.allow_capabilities(Capabilities::ALL);
+    /// ```
+    ///
+    /// Transaction API still works when `TRANSACTION` is not allowed, as
+    /// the library is allowed to issue transaction control statements
+    /// itself. Read-only mode restrictions apply on top of this setting.
+    pub fn allow_capabilities(&mut self, capabilities: Capabilities)
+        -> &mut Self
+    {
+        self.allow_capabilities = capabilities;
+        self
+    }
+    pub fn allowed_capabilities(&self) -> Capabilities {
+        self.allow_capabilities
+    }
+    /// Returns connection that tags all subsequent queries with `tag`
+    ///
+    /// Tag is an application-defined string, such as a service name or
+    /// a request id, that is visible in server-side logs and monitoring
+    /// to attribute load to call sites. As this protocol version has no
+    /// headers for annotations, the tag is sent as a `# tag: ...` comment
+    /// in front of the statement text.
+    ///
+    /// Tag must be at most [`MAX_QUERY_TAG_LENGTH`] bytes long, must not
+    /// contain control characters and must not start with `edgedb/`.
+    pub fn with_query_tag(mut self, tag: Option<&str>)
+        -> anyhow::Result<Connection>
+    {
+        if let Some(tag) = tag {
+            validate_query_tag(tag)?;
+        }
+        self.query_tag = tag.map(|t| t.to_owned());
+        Ok(self)
+    }
+    pub fn query_tag(&self) -> Option<&str> {
+        self.query_tag.as_deref()
+    }
+    /// Returns connection that uses `options` for all subsequent queries
+    ///
+    /// See [`QueryOptions`] for the list of options.
+    pub fn with_query_options(mut self, options: QueryOptions)
+        -> Connection
+    {
+        self.query_options = options;
+        self
+    }
+    pub fn query_options(&self) -> &QueryOptions {
+        &self.query_options
+    }
+    pub fn transaction_options(&self) -> &TransactionOptions {
+        &self.transaction_options
+    }
+    pub fn retry_options(&self) -> &RetryOptions {
+        &self.retry_options
+    }
 }
 
 impl<'a> Writer<'a> {
@@ -192,7 +553,31 @@ impl<'a> Sequence<'a> {
         Ok(())
     }
 
-    pub async fn _process_exec(&mut self) -> anyhow::Result<Bytes> {
+    /// Convert error response to an error, attaching query source if
+    /// enabled (see [`Builder::query_source_in_errors`])
+    ///
+    /// [`Builder::query_source_in_errors`]:
+    ///     crate::Builder::query_source_in_errors
+    fn request_error(&self, request: &str, err: ErrorResponse)
+        -> anyhow::Error
+    {
+        match self.query_source(request, &err) {
+            Some(source) => anyhow::Error::new(err).context(source),
+            None => anyhow::anyhow!(err),
+        }
+    }
+
+    pub(crate) fn query_source(&self, request: &str, err: &ErrorResponse)
+        -> Option<QuerySource>
+    {
+        self.query_source_limit.map(|limit| {
+            QuerySource::new(request, err, limit, tag_length(self.query_tag))
+        })
+    }
+
+    pub async fn _process_exec(&mut self, request: &str)
+        -> anyhow::Result<Bytes>
+    {
         assert!(self.active);  // TODO(tailhook) maybe debug_assert
         let status = loop {
             match self.reader.message().await? {
@@ -204,7 +589,7 @@ impl<'a> Sequence<'a> {
                 ServerMessage::ErrorResponse(err) => {
                     self.reader.wait_ready().await?;
                     self.end_clean();
-                    return Err(anyhow::anyhow!(err));
+                    return Err(self.request_error(request, err));
                 }
                 ServerMessage::Data(_) => { }
                 msg => {
@@ -215,20 +600,28 @@ impl<'a> Sequence<'a> {
         Ok(status)
     }
 
-    async fn _query(&mut self, request: &str, arguments: &Value,
-        io_format: IoFormat)
-        -> Result<OutputTypedesc, anyhow::Error >
+    async fn _prepare(&mut self, request: &str, io_format: IoFormat)
+        -> Result<CommandDataDescription, anyhow::Error>
     {
         assert!(self.active);  // TODO(tailhook) maybe debug_assert
+        if is_transaction_control(request) {
+            // nothing is sent yet, so connection is still consistent
+            self.end_clean();
+            Err(TransactionControlStatement)?;
+        }
         let statement_name = Bytes::from_static(b"");
 
+        let headers = self.prepare_headers();
+        let command_text = tag_statement(self.query_tag, request);
+        let key = StatementKey::new(&command_text, io_format,
+                                    Cardinality::Many, &headers);
         self.send_messages(&[
             ClientMessage::Prepare(Prepare {
-                headers: HashMap::new(),
+                headers,
                 io_format,
                 expected_cardinality: Cardinality::Many,
                 statement_name: statement_name.clone(),
-                command_text: String::from(request),
+                command_text,
             }),
             ClientMessage::Flush,
         ]).await?;
@@ -241,7 +634,7 @@ impl<'a> Sequence<'a> {
                 }
                 ServerMessage::ErrorResponse(err) => {
                     self.err_sync().await?;
-                    return Err(anyhow::anyhow!(err));
+                    return Err(self.request_error(request, err));
                 }
                 _ => {
                     return Err(anyhow::anyhow!(
@@ -263,11 +656,12 @@ impl<'a> Sequence<'a> {
             let msg = self.reader.message().await?;
             match msg {
                 ServerMessage::CommandDataDescription(data_desc) => {
+                    self.statements.insert(key, data_desc.clone());
                     break data_desc;
                 }
                 ServerMessage::ErrorResponse(err) => {
                     self.err_sync().await?;
-                    return Err(anyhow::anyhow!(err));
+                    return Err(self.request_error(request, err));
                 }
                 _ => {
                     return Err(anyhow::anyhow!(
@@ -275,8 +669,22 @@ impl<'a> Sequence<'a> {
                 }
             }
         };
+        Ok(data_description)
+    }
+
+    async fn _query(&mut self, request: &str, arguments: &Value,
+        io_format: IoFormat)
+        -> Result<OutputTypedesc, anyhow::Error >
+    {
+        assert!(self.active);  // TODO(tailhook) maybe debug_assert
+        let cached = self._optimistic_query(request, arguments, io_format)
+            .await?;
+        if let Some(desc) = cached {
+            return Ok(desc);
+        }
+        let data_description = self._prepare(request, io_format).await?;
         let desc = data_description.output()?;
-        let incodec = data_description.input()?.build_codec()?;
+        let incodec = self.codecs.input(&data_description.input()?)?;
 
         let mut arg_buf = BytesMut::with_capacity(8);
         incodec.encode(&mut arg_buf, &arguments)?;
@@ -284,25 +692,227 @@ impl<'a> Sequence<'a> {
         self.send_messages(&[
             ClientMessage::Execute(Execute {
                 headers: HashMap::new(),
-                statement_name: statement_name.clone(),
+                statement_name: Bytes::from_static(b""),
                 arguments: arg_buf.freeze(),
             }),
             ClientMessage::Sync,
         ]).await?;
         Ok(desc)
     }
+
+    /// Executes statement using the cached type descriptors
+    ///
+    /// This skips separate `Prepare` and `DescribeStatement` round trips.
+    /// Returns `None` if the statement isn't cached or its type has
+    /// changed. Nothing is executed in this case, so the statement should
+    /// be prepared as usual.
+    async fn _optimistic_query(&mut self, request: &str, arguments: &Value,
+        io_format: IoFormat)
+        -> Result<Option<OutputTypedesc>, anyhow::Error>
+    {
+        if is_transaction_control(request) {
+            return Ok(None);
+        }
+        let headers = self.prepare_headers();
+        let command_text = tag_statement(self.query_tag, request);
+        let key = StatementKey::new(&command_text, io_format,
+                                    Cardinality::Many, &headers);
+        let data_description = match self.statements.get(&key) {
+            Some(data_description) => data_description,
+            None => return Ok(None),
+        };
+        let desc = data_description.output()?;
+        let incodec = self.codecs.input(&data_description.input()?)?;
+        let mut arg_buf = BytesMut::with_capacity(8);
+        if incodec.encode(&mut arg_buf, arguments).is_err() {
+            // arguments might match the updated type of the statement
+            self.statements.remove(&key);
+            return Ok(None);
+        }
+        self.send_messages(&[
+            ClientMessage::OptimisticExecute(OptimisticExecute {
+                headers,
+                io_format,
+                expected_cardinality: Cardinality::Many,
+                command_text,
+                input_typedesc_id: data_description.input_typedesc_id,
+                output_typedesc_id: data_description.output_typedesc_id,
+                arguments: arg_buf.freeze(),
+            }),
+            ClientMessage::Sync,
+        ]).await?;
+        match self.reader.message().await? {
+            ServerMessage::CommandDataDescription(_) => {
+                self.statements.remove(&key);
+                self.reader.wait_ready().await?;
+                Ok(None)
+            }
+            msg => {
+                if matches!(msg, ServerMessage::ErrorResponse(_)) {
+                    self.statements.remove(&key);
+                }
+                self.reader.unread(msg);
+                Ok(Some(desc))
+            }
+        }
+    }
+
+    /// Prepares statement once and executes it for every set of arguments
+    ///
+    /// All `Execute` messages are sent in a single write followed by a
+    /// single `Sync`, so the whole batch is a single network roundtrip.
+    /// If `transaction` is true, `START TRANSACTION` is executed in a
+    /// separate roundtrip first and `COMMIT` is sent at the end of the
+    /// pipeline. Returns completion status of every statement.
+    async fn _execute_pipeline(&mut self, request: &str, arguments: &[Value],
+        transaction: bool)
+        -> Result<Vec<Bytes>, anyhow::Error>
+    {
+        assert!(self.active);  // TODO(tailhook) maybe debug_assert
+        let data_description = self._prepare(request, IoFormat::Binary).await?;
+        let incodec = self.codecs.input(&data_description.input()?)?;
+
+        let mut messages = Vec::with_capacity(arguments.len() + 2);
+        for args in arguments {
+            let mut arg_buf = BytesMut::with_capacity(8);
+            if let Err(e) = incodec.encode(&mut arg_buf, args) {
+                // statement is prepared so we need to get ReadyForCommand
+                self.err_sync().await?;
+                return Err(e.into());
+            }
+            messages.push(ClientMessage::Execute(Execute {
+                headers: HashMap::new(),
+                statement_name: Bytes::from_static(b""),
+                arguments: arg_buf.freeze(),
+            }));
+        }
+        if transaction {
+            // `ExecuteScript` replies with its own `ReadyForCommand`, so
+            // it's not pipelined to keep replies easy to match
+            self.send_messages(&[
+                ClientMessage::ExecuteScript(ExecuteScript {
+                    headers: self.headers(Capabilities::TRANSACTION),
+                    script_text: "START TRANSACTION".into(),
+                }),
+            ]).await?;
+            loop {
+                match self.reader.message().await? {
+                    ServerMessage::CommandComplete(_) => {
+                        self.reader.wait_ready().await?;
+                        break;
+                    }
+                    ServerMessage::ErrorResponse(err) => {
+                        self.reader.wait_ready().await?;
+                        self.end_clean();
+                        return Err(err.into());
+                    }
+                    msg => {
+                        log::warn!("unsolicited message {:?}", msg);
+                    }
+                }
+            }
+            messages.push(ClientMessage::ExecuteScript(ExecuteScript {
+                headers: self.headers(Capabilities::TRANSACTION),
+                script_text: "COMMIT".into(),
+            }));
+        }
+        messages.push(ClientMessage::Sync);
+        self.send_messages(&messages).await?;
+
+        // one `ReadyForCommand` for `COMMIT` and one for `Sync`, but after
+        // a failed `Execute` server skips everything up to the `Sync`
+        let mut ready_left = if transaction { 2 } else { 1 };
+        let mut statuses = Vec::with_capacity(arguments.len() + 1);
+        let mut error = None;
+        loop {
+            match self.reader.message().await? {
+                ServerMessage::CommandComplete(c) => {
+                    statuses.push(c.status_data);
+                }
+                ServerMessage::ErrorResponse(err) => {
+                    if statuses.len() < arguments.len() {
+                        ready_left = 1;
+                    }
+                    error = Some(err);
+                }
+                ServerMessage::ReadyForCommand(ready) => {
+                    self.reader.consume_ready(ready);
+                    ready_left -= 1;
+                    if ready_left == 0 {
+                        self.end_clean();
+                        break;
+                    }
+                }
+                ServerMessage::Data(_) => {}
+                msg => {
+                    log::warn!("unsolicited message {:?}", msg);
+                }
+            }
+        }
+        if let Some(error) = error {
+            Err(ExecuteManyError { index: statuses.len(), error })?;
+        }
+        if transaction {
+            statuses.pop();  // COMMIT
+        }
+        Ok(statuses)
+    }
 }
 
 impl Connection {
     pub async fn execute<S>(&mut self, request: S)
-        -> Result<Bytes, anyhow::Error>
+        -> Result<CommandStatus, anyhow::Error>
         where S: ToString,
+    {
+        self._execute(request.to_string(), Capabilities::NONE).await
+            .map(CommandStatus::new)
+    }
+
+    /// Execute transaction control statement issued by the library itself
+    ///
+    /// Unlike `execute` it works even if `TRANSACTION` capability is not
+    /// allowed for the connection.
+    pub(crate) async fn execute_control(&mut self, request: String)
+        -> Result<Bytes, anyhow::Error>
+    {
+        self._execute(request, Capabilities::TRANSACTION).await
+    }
+
+    /// Reset session state to defaults
+    ///
+    /// This resets the default module and all aliases set via `SET MODULE`
+    /// and `SET ALIAS`, as well as configuration and globals changed by
+    /// [`set_config`](Connection::set_config) and
+    /// [`set_globals`](Connection::set_globals). Useful to reuse a
+    /// connection between unrelated tasks (e.g. in test fixtures or
+    /// connection pools). Options of the connection itself (read-only
+    /// mode, allowed capabilities, transaction and retry options) are not
+    /// changed.
+    pub async fn reset_session(&mut self) -> anyhow::Result<()> {
+        let script = format!("RESET MODULE; RESET ALIAS *;\n{}",
+                             self.session.reset_script());
+        self._execute(script, Capabilities::SESSION_CONFIG).await?;
+        self.session = Default::default();
+        Ok(())
+    }
+
+    /// Returns the same connection with session state reset to defaults
+    ///
+    /// See [`reset_session`](Connection::reset_session) for details.
+    pub async fn with_default_state(mut self) -> anyhow::Result<Connection> {
+        self.reset_session().await?;
+        Ok(self)
+    }
+
+    async fn _execute(&mut self, request: String, extra: Capabilities)
+        -> Result<Bytes, anyhow::Error>
     {
         let mut seq = self.start_sequence().await?;
+        let headers = seq.headers(extra);
         seq.send_messages(&[
             ClientMessage::ExecuteScript(ExecuteScript {
-                headers: HashMap::new(),
-                script_text: request.to_string(),
+                headers,
+                script_text: tag_statement(seq.query_tag, &request),
             }),
         ]).await?;
         let status = loop {
@@ -313,7 +923,7 @@ impl Connection {
                 }
                 ServerMessage::ErrorResponse(err) => {
                     seq.expect_ready().await?;
-                    return Err(anyhow::anyhow!(err));
+                    return Err(seq.request_error(&request, err));
                 }
                 msg => {
                     eprintln!("WARNING: unsolicited message {:?}", msg);
@@ -323,27 +933,75 @@ impl Connection {
         Ok(status)
     }
 
+    /// Describe input and output types of the query without executing it
+    ///
+    /// Returned description can be inspected with its `input()` and
+    /// `output()` methods and stored for offline use (e.g. by code
+    /// generators) with `to_bytes()`.
+    pub async fn query_describe(&mut self, request: &str)
+        -> anyhow::Result<CommandDataDescription>
+    {
+        let mut seq = self.start_sequence().await?;
+        let description = seq._prepare(request, IoFormat::Binary).await?;
+        seq.send_messages(&[ClientMessage::Sync]).await?;
+        seq.expect_ready().await?;
+        Ok(description)
+    }
+
+    /// Run a query and return the stream of rows
+    ///
+    /// Rows are decoded as `Data` messages arrive, so memory use doesn't
+    /// depend on the size of the result set. Collect the stream if all the
+    /// rows are needed at once. If the stream is dropped before it's
+    /// exhausted, the connection becomes inconsistent and has to be
+    /// reestablished.
     pub async fn query<R>(&mut self, request: &str, arguments: &Value)
         -> anyhow::Result<QueryResponse<'_, QueryableDecoder<R>>>
         where R: Queryable,
+    {
+        self.query_with_format(request, arguments, IoFormat::Binary).await
+    }
+
+    /// Run a query returning data in the specified `io_format`
+    ///
+    /// This is what `query`, `query_json` and `query_json_els` use under
+    /// the hood. Type `R` must match the output format: JSON formats
+    /// return a `String` per result (a single one for `IoFormat::Json`).
+    pub async fn query_with_format<R>(&mut self, request: &str,
+        arguments: &Value, io_format: IoFormat)
+        -> anyhow::Result<QueryResponse<'_, QueryableDecoder<R>>>
+        where R: Queryable,
     {
         let mut seq = self.start_sequence().await?;
-        let desc = seq._query(request, arguments, IoFormat::Binary).await?;
+        let desc = seq._query(request, arguments, io_format).await?;
         match desc.root_pos() {
             Some(root_pos) => {
-                let mut ctx = desc.as_queryable_context();
-                ctx.has_implicit_tid = seq.proto.has_implicit_tid();
-                R::check_descriptor(&ctx, root_pos)?;
+                seq.check_descriptor::<R>(&desc, root_pos)?;
                 let decoder = seq.decoder();
-                Ok(seq.response(QueryableDecoder::new(decoder)))
+                Ok(seq.response(QueryableDecoder::new(decoder), request))
             }
             None => {
-                let completion_message = seq._process_exec().await?;
+                let completion_message = seq._process_exec(request).await?;
                 Err(NoResultExpected { completion_message })?
             }
         }
     }
 
+    /// Run the query and return all the rows along with server warnings
+    pub async fn query_with_warnings<R>(&mut self, request: &str,
+        arguments: &Value)
+        -> anyhow::Result<QueryResult<R>>
+        where R: Queryable,
+    {
+        let mut query = self.query(request, arguments).await?;
+        let mut rows = Vec::new();
+        while let Some(row) = query.next().await.transpose()? {
+            rows.push(row);
+        }
+        let warnings = query.warnings().to_vec();
+        Ok(QueryResult { rows, warnings })
+    }
+
     pub async fn query_row<R>(&mut self, request: &str, arguments: &Value)
         -> anyhow::Result<R>
         where R: Queryable,
@@ -378,21 +1036,7 @@ impl Connection {
     pub async fn query_json(&mut self, request: &str, arguments: &Value)
         -> anyhow::Result<QueryResponse<'_, QueryableDecoder<String>>>
     {
-        let mut seq = self.start_sequence().await?;
-        let desc = seq._query(request, arguments, IoFormat::Json).await?;
-        match desc.root_pos() {
-            Some(root_pos) => {
-                let mut ctx = desc.as_queryable_context();
-                ctx.has_implicit_tid = seq.proto.has_implicit_tid();
-                String::check_descriptor(&ctx, root_pos)?;
-                let decoder = seq.decoder();
-                Ok(seq.response(QueryableDecoder::new(decoder)))
-            }
-            None => {
-                let completion_message = seq._process_exec().await?;
-                Err(NoResultExpected { completion_message })?
-            }
-        }
+        self.query_with_format(request, arguments, IoFormat::Json).await
     }
 
     pub async fn query_json_els(&mut self, request: &str, arguments: &Value)
@@ -401,22 +1045,42 @@ impl Connection {
             anyhow::Error
         >
     {
-        let mut seq = self.start_sequence().await?;
-        let desc = seq._query(request, arguments,
-            IoFormat::JsonElements).await?;
-        match desc.root_pos() {
-            Some(root_pos) => {
-                let mut ctx = desc.as_queryable_context();
-                ctx.has_implicit_tid = seq.proto.has_implicit_tid();
-                String::check_descriptor(&ctx, root_pos)?;
-                let decoder = seq.decoder();
-                Ok(seq.response(QueryableDecoder::new(decoder)))
-            }
-            None => {
-                let completion_message = seq._process_exec().await?;
-                Err(NoResultExpected { completion_message })?
+        self.query_with_format(request, arguments, IoFormat::JsonElements)
+            .await
+    }
+
+    /// Run the query and return the single result as JSON text
+    ///
+    /// Returns `"null"` if the query returns no results and fails if it
+    /// returns more than one, so the output is always a valid JSON
+    /// document that can be passed to a web API as is.
+    pub async fn query_single_json(&mut self, request: &str,
+        arguments: &Value)
+        -> anyhow::Result<String>
+    {
+        let mut query = self.query_json_els(request, arguments).await?;
+        if let Some(result) = query.next().await.transpose()? {
+            if query.next().await.transpose()?.is_some() {
+                query.skip_remaining().await?;
+                anyhow::bail!("extra row returned for query_single_json");
             }
+            Ok(result)
+        } else {
+            Ok("null".into())
+        }
+    }
+
+    /// Run the query and return all the results parsed as a JSON array
+    pub async fn query_json_value(&mut self, request: &str,
+        arguments: &Value)
+        -> anyhow::Result<serde_json::Value>
+    {
+        let mut query = self.query_json(request, arguments).await?;
+        let mut result = String::new();
+        while let Some(chunk) = query.next().await {
+            result.push_str(&chunk?);
         }
+        Ok(serde_json::from_str(&result)?)
     }
 
     pub async fn query_dynamic(&mut self, request: &str, arguments: &Value)
@@ -424,18 +1088,153 @@ impl Connection {
     {
         let mut seq = self.start_sequence().await?;
         let desc = seq._query(request, arguments, IoFormat::Binary).await?;
-        let codec = desc.build_codec()?;
-        Ok(seq.response(codec))
+        let codec = seq.codecs.output(&desc)?;
+        Ok(seq.response(codec, request))
+    }
+
+
+    /// Execute the same statement for each set of arguments in `arguments`
+    ///
+    /// Arguments are sent in chunks of up to `INSERT_BATCH_SIZE` items
+    /// using a pipeline of `Execute` messages. This is dramatically faster
+    /// than running a query per row as it only requires a few network
+    /// roundtrips per chunk. Each chunk is committed in its own
+    /// transaction, so if a chunk fails the previous ones are kept. (If
+    /// connection is already in a transaction block, everything becomes a
+    /// part of that transaction.)
+    ///
+    /// Returns number of executed statements. If one of the statements
+    /// fails [`ExecuteManyError`] is returned, with `index` counted from the
+    /// start of the `arguments`.
+    ///
+    /// ```rust,ignore
+    /// conn.insert_many(
+    ///     "INSERT User { name := <str>$0 }",
+    ///     names.iter().map(|n| Value::Tuple(vec![Value::Str(n.clone())])),
+    /// ).await?;
+    /// ```
+    pub async fn insert_many<I>(&mut self, request: &str, arguments: I)
+        -> anyhow::Result<u64>
+        where I: IntoIterator<Item=Value>,
+    {
+        let mut arguments = arguments.into_iter();
+        let mut total = 0;
+        loop {
+            let chunk = arguments.by_ref()
+                .take(INSERT_BATCH_SIZE)
+                .collect::<Vec<_>>();
+            if chunk.is_empty() {
+                break;
+            }
+            let wrap = self.transaction_state
+                == TransactionState::NotInTransaction;
+            let mut seq = self.start_sequence().await?;
+            let result = seq._execute_pipeline(request, &chunk, wrap).await;
+            let statuses = match result {
+                Ok(statuses) => statuses,
+                Err(e) => match e.downcast::<ExecuteManyError>() {
+                    Ok(mut e) => {
+                        if wrap && self.transaction_state
+                            != TransactionState::NotInTransaction
+                        {
+                            self.execute_control("ROLLBACK".into()).await?;
+                        }
+                        e.index += total as usize;
+                        return Err(e.into());
+                    }
+                    Err(e) => return Err(e),
+                },
+            };
+            total += statuses.len() as u64;
+        }
+        Ok(total)
+    }
+
+    /// Execute the same statement for each set of arguments
+    ///
+    /// Unlike [`insert_many`](Connection::insert_many) all statements are
+    /// sent in a single pipeline and run in a single transaction: either
+    /// all of them are committed or none. (If connection is already in a
+    /// transaction block, statements become a part of that transaction.)
+    ///
+    /// Returns a completion status for each statement, or
+    /// [`ExecuteManyError`] that contains the index of the first failed
+    /// statement. Index equal to the number of arguments means that the
+    /// final `COMMIT` has failed.
+    pub async fn execute_many<I>(&mut self, request: &str, arguments: I)
+        -> anyhow::Result<Vec<CommandStatus>>
+        where I: IntoIterator<Item=Value>,
+    {
+        let arguments = arguments.into_iter().collect::<Vec<_>>();
+        if arguments.is_empty() {
+            return Ok(Vec::new());
+        }
+        let wrap = self.transaction_state
+            == TransactionState::NotInTransaction;
+        let mut seq = self.start_sequence().await?;
+        let result = seq._execute_pipeline(request, &arguments, wrap).await;
+        if wrap && self.transaction_state != TransactionState::NotInTransaction
+        {
+            self.execute_control("ROLLBACK".into()).await?;
+        }
+        Ok(result?.into_iter().map(CommandStatus::new).collect())
     }
 
+    /// Run DML statement and return number of objects it affected
+    ///
+    /// Statement is wrapped into `SELECT count((...))`, so this works
+    /// regardless of whether the server reports counts in the
+    /// [`CommandStatus`]. Only a single statement is allowed.
+    ///
+    /// ```rust,ignore
+    /// let updated = conn.query_affected(
+    ///     "UPDATE User FILTER .name = <str>$0 SET { active := true }",
+    ///     &Value::Tuple(vec![Value::Str(name)]),
+    /// ).await?;
+    /// if updated == 0 {
+    ///     // no such user
+    /// }
+    /// ```
+    pub async fn query_affected(&mut self, request: &str, arguments: &Value)
+        -> anyhow::Result<u64>
+    {
+        let request = request.trim().trim_end_matches(';');
+        let count: i64 = self.query_row(
+            &format!("SELECT count((\n{}\n))", request),
+            arguments,
+        ).await?;
+        Ok(count as u64)
+    }
+
+    /// Run the query with `ANALYZE` and return the parsed plan
+    ///
+    /// Note: the query is actually executed (that's how actual timings
+    /// are obtained), so use it inside a transaction which is rolled back
+    /// if the query has side effects. Requires a server supporting the
+    /// `ANALYZE` statement.
+    pub async fn analyze_query(&mut self, request: &str, arguments: &Value)
+        -> anyhow::Result<QueryAnalysis>
+    {
+        let request = request.trim().trim_end_matches(';');
+        let data: String = self.query_row(
+            &format!("ANALYZE {}", request),
+            arguments,
+        ).await?;
+        QueryAnalysis::from_json(&data)
+    }
 
-    #[allow(dead_code)]
     pub async fn execute_args(&mut self, request: &str, arguments: &Value)
-        -> Result<Bytes, anyhow::Error>
+        -> Result<CommandStatus, anyhow::Error>
     {
         let mut seq = self.start_sequence().await?;
         seq._query(request, arguments, IoFormat::Binary).await?;
-        return seq._process_exec().await;
+        return seq._process_exec(request).await.map(CommandStatus::new);
+    }
+
+    /// Fetch server version and return it along with protocol features
+    pub async fn server_info(&mut self) -> anyhow::Result<ServerInfo> {
+        let version = self.get_version().await?.parse()?;
+        Ok(ServerInfo::new(version, self.version.clone()))
     }
 
     pub async fn get_version(&mut self) -> Result<String, anyhow::Error> {
@@ -448,3 +1247,17 @@ impl Connection {
 }
 
 
+
+#[test]
+fn query_tag() {
+    assert!(validate_query_tag("billing-service").is_ok());
+    assert!(validate_query_tag(&"x".repeat(MAX_QUERY_TAG_LENGTH)).is_ok());
+    assert!(validate_query_tag(&"x".repeat(MAX_QUERY_TAG_LENGTH + 1))
+        .is_err());
+    assert!(validate_query_tag("a\nSELECT 1").is_err());
+    assert!(validate_query_tag("edgedb/cli").is_err());
+    assert_eq!(tag_statement(Some("svc"), "SELECT 1"), "# tag: svc\nSELECT 1");
+    assert_eq!(tag_statement(None, "SELECT 1"), "SELECT 1");
+    assert_eq!(tag_length(Some("svc")), "# tag: svc\n".len());
+    assert_eq!(tag_length(None), 0);
+}
@@ -0,0 +1,72 @@
+use edgedb_protocol::client_message::Capabilities;
+
+use crate::client::Connection;
+use crate::client::globals::quote_global;
+
+
+/// Script that sets module aliases, empty alias sets the default module
+pub(crate) fn alias_script<A, M>(aliases: &[(A, M)]) -> String
+    where A: AsRef<str>, M: AsRef<str>,
+{
+    let mut script = String::new();
+    for (alias, module) in aliases {
+        let (alias, module) = (alias.as_ref(), module.as_ref());
+        if alias.is_empty() {
+            script.push_str(&format!("SET MODULE {};\n",
+                                     quote_global(module)));
+        } else {
+            script.push_str(&format!("SET ALIAS {} AS MODULE {};\n",
+                                     quote_global(alias),
+                                     quote_global(module)));
+        }
+    }
+    script
+}
+
+impl Connection {
+    /// Set module aliases for the session
+    ///
+    /// Each pair is an alias and a module name it refers to. Empty alias
+    /// changes the default module, so unqualified names in subsequent
+    /// queries resolve to that module:
+    ///
+    /// ```rust,ignore
+    /// conn.set_module_aliases(&[("", "app"), ("cfg", "cfg")]).await?;
+    /// ```
+    ///
+    /// Aliases set before are kept unless overridden. Use
+    /// [`reset_session`](Connection::reset_session) to drop all of them.
+    pub async fn set_module_aliases<A, M>(&mut self, aliases: &[(A, M)])
+        -> anyhow::Result<()>
+        where A: AsRef<str>, M: AsRef<str>,
+    {
+        if aliases.is_empty() {
+            return Ok(());
+        }
+        self._execute(alias_script(aliases),
+                      Capabilities::SESSION_CONFIG).await?;
+        for (alias, module) in aliases {
+            self.session.record_alias(alias.as_ref(), module.as_ref());
+        }
+        Ok(())
+    }
+    /// Returns the same connection with module aliases set
+    ///
+    /// See [`set_module_aliases`](Connection::set_module_aliases) for
+    /// details.
+    pub async fn with_module_aliases<A, M>(mut self, aliases: &[(A, M)])
+        -> anyhow::Result<Connection>
+        where A: AsRef<str>, M: AsRef<str>,
+    {
+        self.set_module_aliases(aliases).await?;
+        Ok(self)
+    }
+}
+
+#[test]
+fn script() {
+    assert_eq!(alias_script(&[("", "app"), ("m", "app::models")]),
+        "SET MODULE `app`;\n\
+         SET ALIAS `m` AS MODULE `app`::`models`;\n");
+    assert_eq!(alias_script::<&str, &str>(&[]), "");
+}
@@ -0,0 +1,430 @@
+//! Export of query results into Apache Arrow record batches
+//!
+//! Only available with the `arrow` feature.
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_array::builder::{BinaryBuilder, BooleanBuilder};
+use arrow_array::builder::{Date32Builder, DurationMicrosecondBuilder};
+use arrow_array::builder::{FixedSizeBinaryBuilder, Float32Builder};
+use arrow_array::builder::{Float64Builder, Int16Builder, Int32Builder};
+use arrow_array::builder::{Int64Builder, StringBuilder};
+use arrow_array::builder::{Time64MicrosecondBuilder};
+use arrow_array::builder::{TimestampMicrosecondBuilder};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use async_std::prelude::StreamExt;
+
+use edgedb_protocol::client_message::IoFormat;
+use edgedb_protocol::codec;
+use edgedb_protocol::descriptors::{Descriptor, TypePos};
+use edgedb_protocol::model::{Datetime, LocalDate};
+use edgedb_protocol::value::Value;
+
+use crate::client::Connection;
+use crate::errors::{NoResultExpected, UnsupportedColumn};
+
+
+/// Name of the column if query returns scalars rather than objects
+pub const ARROW_SCALAR_COLUMN: &str = "value";
+
+enum Column {
+    Str(StringBuilder),
+    Bytes(BinaryBuilder),
+    Int16(Int16Builder),
+    Int32(Int32Builder),
+    Int64(Int64Builder),
+    Float32(Float32Builder),
+    Float64(Float64Builder),
+    Bool(BooleanBuilder),
+    Uuid(FixedSizeBinaryBuilder),
+    Timestamp(TimestampMicrosecondBuilder),
+    Date(Date32Builder),
+    Time(Time64MicrosecondBuilder),
+    Duration(DurationMicrosecondBuilder),
+}
+
+enum Layout {
+    Scalar,
+    /// Indexes of the exported fields of an object or a named tuple
+    Fields(Vec<usize>),
+}
+
+/// Accumulates rows of a uniform shape into Arrow columns
+pub(crate) struct BatchBuilder {
+    schema: Arc<Schema>,
+    layout: Layout,
+    columns: Vec<Column>,
+}
+
+fn column(descriptors: &[Descriptor], pos: TypePos, name: &str)
+    -> Result<(Field, Column), UnsupportedColumn>
+{
+    use Descriptor as D;
+
+    let unsupported = |type_name: &str| UnsupportedColumn {
+        column: name.into(),
+        type_name: type_name.into(),
+    };
+    let id = match descriptors.get(pos.0 as usize) {
+        Some(D::BaseScalar(base)) => base.id,
+        Some(D::Scalar(scalar)) => {
+            return column(descriptors, scalar.base_type_pos, name);
+        }
+        Some(D::Enumeration(_)) => codec::STD_STR,
+        Some(D::ObjectShape(_)) => return Err(unsupported("object")),
+        Some(D::Set(_)) => return Err(unsupported("set")),
+        Some(D::Array(_)) => return Err(unsupported("array")),
+        Some(D::Range(_)) | Some(D::MultiRange(_)) => {
+            return Err(unsupported("range"));
+        }
+        Some(D::Tuple(_)) | Some(D::NamedTuple(_)) => {
+            return Err(unsupported("tuple"));
+        }
+        Some(D::TypeAnnotation(_)) | None => {
+            return Err(unsupported("unknown"));
+        }
+    };
+    let (data_type, column) = match id {
+        codec::STD_STR | codec::STD_JSON | codec::STD_BIGINT => {
+            (DataType::Utf8, Column::Str(StringBuilder::new()))
+        }
+        codec::STD_BYTES => {
+            (DataType::Binary, Column::Bytes(BinaryBuilder::new()))
+        }
+        codec::STD_INT16 => {
+            (DataType::Int16, Column::Int16(Int16Builder::new()))
+        }
+        codec::STD_INT32 => {
+            (DataType::Int32, Column::Int32(Int32Builder::new()))
+        }
+        codec::STD_INT64 => {
+            (DataType::Int64, Column::Int64(Int64Builder::new()))
+        }
+        codec::STD_FLOAT32 => {
+            (DataType::Float32, Column::Float32(Float32Builder::new()))
+        }
+        codec::STD_FLOAT64 => {
+            (DataType::Float64, Column::Float64(Float64Builder::new()))
+        }
+        codec::STD_BOOL => {
+            (DataType::Boolean, Column::Bool(BooleanBuilder::new()))
+        }
+        codec::STD_UUID => {
+            (DataType::FixedSizeBinary(16),
+             Column::Uuid(FixedSizeBinaryBuilder::new(16)))
+        }
+        codec::STD_DATETIME => {
+            let typ = DataType::Timestamp(TimeUnit::Microsecond,
+                                          Some("UTC".into()));
+            let builder = TimestampMicrosecondBuilder::new()
+                .with_data_type(typ.clone());
+            (typ, Column::Timestamp(builder))
+        }
+        codec::CAL_LOCAL_DATETIME => {
+            (DataType::Timestamp(TimeUnit::Microsecond, None),
+             Column::Timestamp(TimestampMicrosecondBuilder::new()))
+        }
+        codec::CAL_LOCAL_DATE => {
+            (DataType::Date32, Column::Date(Date32Builder::new()))
+        }
+        codec::CAL_LOCAL_TIME => {
+            (DataType::Time64(TimeUnit::Microsecond),
+             Column::Time(Time64MicrosecondBuilder::new()))
+        }
+        codec::STD_DURATION => {
+            (DataType::Duration(TimeUnit::Microsecond),
+             Column::Duration(DurationMicrosecondBuilder::new()))
+        }
+        _ => return Err(unsupported(&id.to_string())),
+    };
+    Ok((Field::new(name, data_type, true), column))
+}
+
+impl Column {
+    fn push(&mut self, value: Option<&Value>) -> anyhow::Result<()> {
+        use Column as C;
+        use Value as V;
+
+        // EdgeDB epoch is 2000-01-01, Arrow's is 1970-01-01
+        let unix_micros = Datetime::UNIX_EPOCH.to_micros();
+        match (self, value) {
+            (C::Str(b), Some(V::Str(v))) => b.append_value(v),
+            (C::Str(b), Some(V::Json(v))) => b.append_value(v),
+            (C::Str(b), Some(V::Enum(v))) => b.append_value(&**v),
+            (C::Str(b), Some(V::BigInt(v))) => b.append_value(v.to_string()),
+            (C::Bytes(b), Some(V::Bytes(v))) => b.append_value(v),
+            (C::Int16(b), Some(V::Int16(v))) => b.append_value(*v),
+            (C::Int32(b), Some(V::Int32(v))) => b.append_value(*v),
+            (C::Int64(b), Some(V::Int64(v))) => b.append_value(*v),
+            (C::Float32(b), Some(V::Float32(v))) => b.append_value(*v),
+            (C::Float64(b), Some(V::Float64(v))) => b.append_value(*v),
+            (C::Bool(b), Some(V::Bool(v))) => b.append_value(*v),
+            (C::Uuid(b), Some(V::Uuid(v))) => b.append_value(v.as_bytes())?,
+            (C::Timestamp(b), Some(V::Datetime(v))) => {
+                b.append_value(v.to_micros() - unix_micros)
+            }
+            (C::Timestamp(b), Some(V::LocalDatetime(v))) => {
+                b.append_value(v.to_micros() - unix_micros)
+            }
+            (C::Date(b), Some(V::LocalDate(v))) => {
+                b.append_value(v.to_days() - LocalDate::UNIX_EPOCH.to_days())
+            }
+            (C::Time(b), Some(V::LocalTime(v))) => {
+                b.append_value(v.to_micros() as i64)
+            }
+            (C::Duration(b), Some(V::Duration(v))) => {
+                b.append_value(v.to_micros())
+            }
+            (c, None) | (c, Some(V::Nothing)) => c.push_null(),
+            (_, Some(v)) => {
+                anyhow::bail!("unexpected value of kind {} in a column",
+                              v.kind());
+            }
+        }
+        Ok(())
+    }
+    fn push_null(&mut self) {
+        match self {
+            Column::Str(b) => b.append_null(),
+            Column::Bytes(b) => b.append_null(),
+            Column::Int16(b) => b.append_null(),
+            Column::Int32(b) => b.append_null(),
+            Column::Int64(b) => b.append_null(),
+            Column::Float32(b) => b.append_null(),
+            Column::Float64(b) => b.append_null(),
+            Column::Bool(b) => b.append_null(),
+            Column::Uuid(b) => b.append_null(),
+            Column::Timestamp(b) => b.append_null(),
+            Column::Date(b) => b.append_null(),
+            Column::Time(b) => b.append_null(),
+            Column::Duration(b) => b.append_null(),
+        }
+    }
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Column::Str(b) => Arc::new(b.finish()),
+            Column::Bytes(b) => Arc::new(b.finish()),
+            Column::Int16(b) => Arc::new(b.finish()),
+            Column::Int32(b) => Arc::new(b.finish()),
+            Column::Int64(b) => Arc::new(b.finish()),
+            Column::Float32(b) => Arc::new(b.finish()),
+            Column::Float64(b) => Arc::new(b.finish()),
+            Column::Bool(b) => Arc::new(b.finish()),
+            Column::Uuid(b) => Arc::new(b.finish()),
+            Column::Timestamp(b) => Arc::new(b.finish()),
+            Column::Date(b) => Arc::new(b.finish()),
+            Column::Time(b) => Arc::new(b.finish()),
+            Column::Duration(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+impl BatchBuilder {
+    pub fn new(descriptors: &[Descriptor], root_pos: TypePos)
+        -> Result<BatchBuilder, UnsupportedColumn>
+    {
+        let mut fields = Vec::new();
+        let mut columns = Vec::new();
+        let layout = match descriptors.get(root_pos.0 as usize) {
+            Some(Descriptor::ObjectShape(shape)) => {
+                let mut indexes = Vec::new();
+                for (idx, el) in shape.elements.iter().enumerate() {
+                    if el.flag_implicit {
+                        continue;
+                    }
+                    let (field, col) = column(descriptors,
+                                              el.type_pos, &el.name)?;
+                    fields.push(field);
+                    columns.push(col);
+                    indexes.push(idx);
+                }
+                Layout::Fields(indexes)
+            }
+            Some(Descriptor::NamedTuple(tuple)) => {
+                for el in &tuple.elements {
+                    let (field, col) = column(descriptors,
+                                              el.type_pos, &el.name)?;
+                    fields.push(field);
+                    columns.push(col);
+                }
+                Layout::Fields((0..tuple.elements.len()).collect())
+            }
+            _ => {
+                let (field, col) = column(descriptors,
+                                          root_pos, ARROW_SCALAR_COLUMN)?;
+                fields.push(field);
+                columns.push(col);
+                Layout::Scalar
+            }
+        };
+        Ok(BatchBuilder {
+            schema: Arc::new(Schema::new(fields)),
+            layout,
+            columns,
+        })
+    }
+    pub fn push(&mut self, row: &Value) -> anyhow::Result<()> {
+        match (&self.layout, row) {
+            (Layout::Scalar, _) => self.columns[0].push(Some(row))?,
+            (Layout::Fields(indexes), Value::Object { fields, .. }) => {
+                for (col, idx) in self.columns.iter_mut().zip(indexes) {
+                    col.push(fields.get(*idx).and_then(|f| f.as_ref()))?;
+                }
+            }
+            (Layout::Fields(indexes), Value::NamedTuple { fields, .. }) => {
+                for (col, idx) in self.columns.iter_mut().zip(indexes) {
+                    col.push(fields.get(*idx))?;
+                }
+            }
+            (_, row) => {
+                anyhow::bail!("unexpected row of kind {}", row.kind());
+            }
+        }
+        Ok(())
+    }
+    pub fn finish(mut self) -> anyhow::Result<RecordBatch> {
+        let arrays = self.columns.iter_mut().map(|c| c.finish()).collect();
+        Ok(RecordBatch::try_new(self.schema, arrays)?)
+    }
+}
+
+impl Connection {
+    /// Run the query and return results as an Arrow record batch
+    ///
+    /// Query must return objects or named tuples with scalar fields (each
+    /// field becomes a column; implicit fields like `id` are only exported
+    /// if selected explicitly), or just scalars, which are returned as a
+    /// single column named [`ARROW_SCALAR_COLUMN`]. Rows are appended to the
+    /// column builders as they are received, without collecting them
+    /// first.
+    ///
+    /// Supported types are all the scalars, except `decimal`. `bigint`,
+    /// `json` and enums are exported as strings, `uuid` as a 16-byte
+    /// fixed-size binary.
+    pub async fn query_arrow(&mut self, request: &str, arguments: &Value)
+        -> anyhow::Result<RecordBatch>
+    {
+        let mut seq = self.start_sequence().await?;
+        let desc = seq._query(request, arguments, IoFormat::Binary).await?;
+        let root_pos = match desc.root_pos() {
+            Some(root_pos) => root_pos,
+            None => {
+                let completion_message = seq._process_exec(request).await?;
+                Err(NoResultExpected { completion_message })?
+            }
+        };
+        let codec = seq.codecs.output(&desc)?;
+        let builder = BatchBuilder::new(desc.descriptors(), root_pos);
+        let mut rows = seq.response(codec, request);
+        let mut builder = match builder {
+            Ok(builder) => builder,
+            Err(e) => {
+                rows.skip_remaining().await?;
+                return Err(e.into());
+            }
+        };
+        while let Some(row) = rows.next().await.transpose()? {
+            builder.push(&row)?;
+        }
+        builder.finish()
+    }
+}
+
+#[test]
+fn object_batch() {
+    use arrow_array::{Array, Int64Array, StringArray, Date32Array};
+    use edgedb_protocol::codec::{ObjectShape, ShapeElement};
+    use edgedb_protocol::descriptors as d;
+
+    let el = |name: &str, implicit, type_pos| d::ShapeElement {
+        flag_implicit: implicit,
+        flag_link_property: false,
+        flag_link: false,
+        name: name.into(),
+        type_pos: TypePos(type_pos),
+    };
+    let descriptors = vec![
+        Descriptor::BaseScalar(d::BaseScalarTypeDescriptor {
+            id: codec::STD_UUID,
+        }),
+        Descriptor::BaseScalar(d::BaseScalarTypeDescriptor {
+            id: codec::STD_STR,
+        }),
+        Descriptor::BaseScalar(d::BaseScalarTypeDescriptor {
+            id: codec::STD_INT64,
+        }),
+        Descriptor::BaseScalar(d::BaseScalarTypeDescriptor {
+            id: codec::CAL_LOCAL_DATE,
+        }),
+        Descriptor::ObjectShape(d::ObjectShapeDescriptor {
+            id: Default::default(),
+            elements: vec![
+                el("id", true, 0),
+                el("name", false, 1),
+                el("age", false, 2),
+                el("born", false, 3),
+            ],
+        }),
+    ];
+    let mut builder = BatchBuilder::new(&descriptors, TypePos(4)).unwrap();
+    let shape = ObjectShape::new(
+        ["id", "name", "age", "born"].iter().map(|n| ShapeElement {
+            flag_implicit: *n == "id",
+            flag_link_property: false,
+            flag_link: false,
+            name: n.to_string(),
+        }).collect());
+    builder.push(&Value::Object {
+        shape: shape.clone(),
+        fields: vec![
+            Some(Value::Uuid(Default::default())),
+            Some(Value::Str("John".into())),
+            Some(Value::Int64(33)),
+            Some(Value::LocalDate(LocalDate::from_ymd(1970, 1, 2))),
+        ],
+    }).unwrap();
+    builder.push(&Value::Object {
+        shape,
+        fields: vec![
+            Some(Value::Uuid(Default::default())),
+            Some(Value::Str("Jane".into())),
+            None,
+            None,
+        ],
+    }).unwrap();
+    let batch = builder.finish().unwrap();
+    assert_eq!(batch.num_rows(), 2);
+    let names = batch.schema().fields().iter()
+        .map(|f| f.name().clone())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["name", "age", "born"]);
+    let name = batch.column(0).as_any()
+        .downcast_ref::<StringArray>().unwrap();
+    assert_eq!(name.value(1), "Jane");
+    let age = batch.column(1).as_any()
+        .downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(age.value(0), 33);
+    assert!(age.is_null(1));
+    let born = batch.column(2).as_any()
+        .downcast_ref::<Date32Array>().unwrap();
+    assert_eq!(born.value(0), 1);
+}
+
+#[test]
+fn unsupported() {
+    use edgedb_protocol::descriptors as d;
+
+    let descriptors = vec![
+        Descriptor::BaseScalar(d::BaseScalarTypeDescriptor {
+            id: codec::STD_INT64,
+        }),
+        Descriptor::Array(d::ArrayTypeDescriptor {
+            id: Default::default(),
+            type_pos: TypePos(0),
+            dimensions: vec![None],
+        }),
+    ];
+    let err = BatchBuilder::new(&descriptors, TypePos(1)).err().unwrap();
+    assert_eq!(err.column, ARROW_SCALAR_COLUMN);
+    assert_eq!(err.type_name, "array");
+}
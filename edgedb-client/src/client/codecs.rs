@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use edgedb_protocol::codec::Codec;
+use edgedb_protocol::descriptors::{InputTypedesc, OutputTypedesc};
+use edgedb_protocol::errors::CodecError;
+use edgedb_protocol::model::Uuid;
+
+
+/// Cache is cleared when it grows larger than this
+///
+/// Type descriptor ids are derived from the type, so the number of distinct
+/// codecs is bounded by the number of distinct shapes used by the
+/// application. The limit only guards against unbounded growth when
+/// queries are generated dynamically.
+const MAX_CODECS: usize = 1024;
+
+/// Built codecs keyed by type descriptor id
+///
+/// Every connection has one. Connections of a [`Pool`] to the same database
+/// share it, so a newly established connection doesn't rebuild codecs.
+///
+/// [`Pool`]: crate::client::Pool
+#[derive(Debug, Default)]
+pub(crate) struct CodecCache {
+    codecs: Mutex<HashMap<Uuid, Arc<dyn Codec>>>,
+}
+
+impl CodecCache {
+    pub fn input(&self, desc: &InputTypedesc)
+        -> Result<Arc<dyn Codec>, CodecError>
+    {
+        self.get_or_build(desc.id(), || desc.build_codec())
+    }
+    pub fn output(&self, desc: &OutputTypedesc)
+        -> Result<Arc<dyn Codec>, CodecError>
+    {
+        self.get_or_build(desc.id(), || desc.build_codec())
+    }
+    fn get_or_build<F>(&self, id: &Uuid, build: F)
+        -> Result<Arc<dyn Codec>, CodecError>
+        where F: FnOnce() -> Result<Arc<dyn Codec>, CodecError>,
+    {
+        if let Some(codec) = self.codecs.lock()
+            .expect("codec cache lock").get(id)
+        {
+            return Ok(codec.clone());
+        }
+        // build without holding the lock, in the worst case
+        // the same codec is built twice
+        let codec = build()?;
+        let mut codecs = self.codecs.lock().expect("codec cache lock");
+        if codecs.len() >= MAX_CODECS {
+            codecs.clear();
+        }
+        codecs.insert(*id, codec.clone());
+        Ok(codec)
+    }
+}
+
+#[test]
+fn reuse() {
+    use bytes::Bytes;
+    use edgedb_protocol::value::Value;
+    use edgedb_protocol::server_message::CommandDataDescription;
+    use edgedb_protocol::client_message::Cardinality;
+
+    let description = CommandDataDescription {
+        headers: HashMap::new(),
+        result_cardinality: Cardinality::One,
+        input_typedesc_id: Uuid::from_u128(0xFF),
+        input_typedesc: Bytes::from_static(
+            b"\x04\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\xff\0\0"),
+        output_typedesc_id: Uuid::from_u128(0x105),
+        output_typedesc: Bytes::from_static(
+            b"\x02\0\0\0\0\0\0\0\0\0\0\0\0\0\0\x01\x05"),
+    };
+    let cache = CodecCache::default();
+    let out1 = cache.output(&description.output().unwrap()).unwrap();
+    let out2 = cache.output(&description.output().unwrap()).unwrap();
+    assert!(Arc::ptr_eq(&out1, &out2));
+    assert_eq!(out1.decode(&[0, 0, 0, 0, 0, 0, 0, 7]).unwrap(),
+               Value::Int64(7));
+    cache.input(&description.input().unwrap()).unwrap();
+    assert_eq!(cache.codecs.lock().unwrap().len(), 2);
+}
@@ -0,0 +1,75 @@
+use edgedb_protocol::client_message::Capabilities;
+use edgedb_protocol::state::{ConfigDelta, ConfigModifier};
+
+use crate::client::Connection;
+
+
+pub(crate) fn configure_script(config: &ConfigModifier) -> String {
+    let mut script = String::new();
+    for (name, value) in config.values() {
+        let name = format!("`{}`", name.replace('`', "``"));
+        match value {
+            Some(value) => {
+                script.push_str(&format!("CONFIGURE SESSION SET {} := {};\n",
+                                         name, value.to_edgeql()));
+            }
+            None => {
+                script.push_str(&format!("CONFIGURE SESSION RESET {};\n",
+                                         name));
+            }
+        }
+    }
+    script
+}
+
+impl Connection {
+    /// Change session configuration
+    ///
+    /// Only settings changed by `config` are affected, for example:
+    ///
+    /// ```rust,ignore
+    /// conn.set_config(&SessionConfig::new()
+    ///     .with_query_execution_timeout(Duration::from_secs(10))
+    /// ).await?;
+    /// ```
+    pub async fn set_config<C: ConfigDelta>(&mut self, config: &C)
+        -> anyhow::Result<()>
+    {
+        let mut modifier = ConfigModifier::new();
+        config.apply(&mut modifier);
+        if modifier.values().is_empty() {
+            return Ok(());
+        }
+        self._execute(configure_script(&modifier),
+                      Capabilities::SESSION_CONFIG).await?;
+        self.session.record_config(&modifier);
+        Ok(())
+    }
+    /// Returns the same connection with session configuration changed
+    ///
+    /// See [`set_config`](Connection::set_config) for details.
+    pub async fn with_config<C: ConfigDelta>(mut self, config: &C)
+        -> anyhow::Result<Connection>
+    {
+        self.set_config(config).await?;
+        Ok(self)
+    }
+}
+
+#[test]
+fn script() {
+    use std::time::Duration;
+    use edgedb_protocol::state::SessionConfig;
+
+    let config = SessionConfig::new()
+        .with_apply_access_policies(false)
+        .with_query_execution_timeout(Duration::from_millis(1500))
+        .reset_allow_bare_ddl();
+    let mut modifier = ConfigModifier::new();
+    config.apply(&mut modifier);
+    assert_eq!(configure_script(&modifier),
+        "CONFIGURE SESSION SET `apply_access_policies` := false;\n\
+         CONFIGURE SESSION SET `query_execution_timeout` := \
+            <std::duration>'1500000 microseconds';\n\
+         CONFIGURE SESSION RESET `allow_bare_ddl`;\n");
+}
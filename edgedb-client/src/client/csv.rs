@@ -0,0 +1,202 @@
+use async_std::io::prelude::WriteExt;
+use async_std::io::Write;
+use async_std::prelude::StreamExt;
+
+use edgedb_protocol::client_message::IoFormat;
+use edgedb_protocol::descriptors::{Descriptor, TypePos};
+use edgedb_protocol::value::Value;
+
+use crate::client::Connection;
+use crate::errors::{NoResultExpected, UnsupportedColumn};
+
+
+/// Name of the CSV column if query returns scalars rather than objects
+pub const CSV_SCALAR_COLUMN: &str = "value";
+
+enum Layout {
+    Scalar,
+    /// Indexes of the exported fields of an object or a named tuple
+    Fields(Vec<usize>),
+}
+
+fn layout(descriptors: &[Descriptor], root_pos: TypePos)
+    -> (Layout, Vec<String>)
+{
+    match descriptors.get(root_pos.0 as usize) {
+        Some(Descriptor::ObjectShape(shape)) => {
+            let (indexes, names) = shape.elements.iter().enumerate()
+                .filter(|(_, el)| !el.flag_implicit)
+                .map(|(idx, el)| (idx, el.name.clone()))
+                .unzip();
+            (Layout::Fields(indexes), names)
+        }
+        Some(Descriptor::NamedTuple(tuple)) => {
+            let names = tuple.elements.iter()
+                .map(|el| el.name.clone())
+                .collect();
+            (Layout::Fields((0..tuple.elements.len()).collect()), names)
+        }
+        _ => (Layout::Scalar, vec![CSV_SCALAR_COLUMN.into()]),
+    }
+}
+
+/// Append a field quoting it if needed (RFC 4180)
+fn write_field(buf: &mut String, value: &str) {
+    if value.contains(&[',', '"', '\r', '\n'][..]) {
+        buf.push('"');
+        buf.push_str(&value.replace('"', "\"\""));
+        buf.push('"');
+    } else {
+        buf.push_str(value);
+    }
+}
+
+fn format_value(column: &str, value: Option<&Value>)
+    -> Result<String, UnsupportedColumn>
+{
+    use Value::*;
+
+    let text = match value {
+        None | Some(Nothing) => String::new(),
+        Some(Str(v)) | Some(Json(v)) => v.clone(),
+        Some(Enum(v)) => v.to_string(),
+        Some(Uuid(v)) => v.to_string(),
+        Some(Int16(v)) => v.to_string(),
+        Some(Int32(v)) => v.to_string(),
+        Some(Int64(v)) => v.to_string(),
+        Some(Float32(v)) => v.to_string(),
+        Some(Float64(v)) => v.to_string(),
+        Some(BigInt(v)) => v.to_string(),
+        Some(Bool(v)) => v.to_string(),
+        // debug format is ISO 8601
+        Some(Datetime(v)) => format!("{:?}", v),
+        Some(LocalDatetime(v)) => format!("{:?}", v),
+        Some(LocalDate(v)) => v.to_string(),
+        Some(LocalTime(v)) => v.to_string(),
+        Some(Duration(v)) => v.to_string(),
+        Some(v) => {
+            return Err(UnsupportedColumn {
+                column: column.into(),
+                type_name: v.kind().into(),
+            });
+        }
+    };
+    Ok(text)
+}
+
+fn format_row(buf: &mut String, layout: &Layout, names: &[String],
+              row: &Value)
+    -> anyhow::Result<()>
+{
+    let mut fields = Vec::with_capacity(names.len());
+    match (layout, row) {
+        (Layout::Scalar, _) => {
+            fields.push(format_value(&names[0], Some(row))?);
+        }
+        (Layout::Fields(indexes), Value::Object { fields: values, .. }) => {
+            for (idx, name) in indexes.iter().zip(names) {
+                let value = values.get(*idx).and_then(|v| v.as_ref());
+                fields.push(format_value(name, value)?);
+            }
+        }
+        (Layout::Fields(indexes), Value::NamedTuple { fields: values, .. })
+        => {
+            for (idx, name) in indexes.iter().zip(names) {
+                fields.push(format_value(name, values.get(*idx))?);
+            }
+        }
+        (_, row) => anyhow::bail!("unexpected row of kind {}", row.kind()),
+    }
+    write_row(buf, fields.iter().map(|f| &f[..]));
+    Ok(())
+}
+
+fn write_row<'a>(buf: &mut String, fields: impl Iterator<Item=&'a str>) {
+    for (idx, field) in fields.enumerate() {
+        if idx > 0 {
+            buf.push(',');
+        }
+        write_field(buf, field);
+    }
+    buf.push_str("\r\n");
+}
+
+impl Connection {
+    /// Run the query and write results into `writer` as CSV
+    ///
+    /// Query must return objects or named tuples with scalar fields (each
+    /// field becomes a column; implicit fields like `id` are only exported
+    /// if selected explicitly), or just scalars, which are written as a
+    /// single column named [`CSV_SCALAR_COLUMN`]. The first line contains
+    /// column names. Missing values are written as empty fields.
+    ///
+    /// Rows are written as they are received, so memory use doesn't
+    /// depend on the size of the result. Writes are not buffered, wrap
+    /// `writer` into a `BufWriter` if needed. Returns number of rows
+    /// written (not including the header).
+    pub async fn copy_csv<W>(&mut self, request: &str, arguments: &Value,
+        writer: &mut W)
+        -> anyhow::Result<u64>
+        where W: Write + Unpin,
+    {
+        let mut seq = self.start_sequence().await?;
+        let desc = seq._query(request, arguments, IoFormat::Binary).await?;
+        let root_pos = match desc.root_pos() {
+            Some(root_pos) => root_pos,
+            None => {
+                let completion_message = seq._process_exec(request).await?;
+                Err(NoResultExpected { completion_message })?
+            }
+        };
+        let codec = seq.codecs.output(&desc)?;
+        let (layout, names) = layout(desc.descriptors(), root_pos);
+        let mut rows = seq.response(codec, request);
+        let mut buf = String::new();
+        write_row(&mut buf, names.iter().map(|n| &n[..]));
+        writer.write_all(buf.as_bytes()).await?;
+        let mut count = 0;
+        while let Some(row) = rows.next().await.transpose()? {
+            buf.clear();
+            format_row(&mut buf, &layout, &names, &row)?;
+            writer.write_all(buf.as_bytes()).await?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+#[test]
+fn quoting() {
+    let mut buf = String::new();
+    write_row(&mut buf, vec!["plain", "a,b", "say \"hi\"", "two\nlines", ""]
+        .into_iter());
+    assert_eq!(buf,
+        "plain,\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",\r\n");
+}
+
+#[test]
+fn rows() {
+    use edgedb_protocol::codec::{NamedTupleShape, TupleElement};
+
+    let shape = NamedTupleShape::new(vec![
+        TupleElement { name: "name".into() },
+        TupleElement { name: "age".into() },
+    ]);
+    let names = vec![String::from("name"), String::from("age")];
+    let layout = Layout::Fields(vec![0, 1]);
+    let mut buf = String::new();
+    format_row(&mut buf, &layout, &names, &Value::NamedTuple {
+        shape: shape.clone(),
+        fields: vec![Value::Str("Doe, John".into()), Value::Int64(33)],
+    }).unwrap();
+    format_row(&mut buf, &layout, &names, &Value::NamedTuple {
+        shape: shape.clone(),
+        fields: vec![Value::Str("Jane".into()), Value::Nothing],
+    }).unwrap();
+    assert_eq!(buf, "\"Doe, John\",33\r\nJane,\r\n");
+    let err = format_row(&mut buf, &layout, &names, &Value::NamedTuple {
+        shape,
+        fields: vec![Value::Str("Jane".into()), Value::Array(vec![])],
+    }).unwrap_err();
+    assert_eq!(err.to_string(), "cannot export column \"age\" of type array");
+}
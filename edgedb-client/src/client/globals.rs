@@ -0,0 +1,87 @@
+use edgedb_protocol::client_message::{Capabilities, IoFormat};
+use edgedb_protocol::state::{GlobalsDelta, GlobalsModifier};
+use edgedb_protocol::value::Value;
+
+use crate::client::Connection;
+
+
+pub(super) fn quote_global(name: &str) -> String {
+    name.split("::")
+        .map(|part| format!("`{}`", part.replace('`', "``")))
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+fn cast_type(name: &str, value: &Value) -> anyhow::Result<&'static str> {
+    use Value::*;
+    let cast = match value {
+        Str(..) => "str",
+        Uuid(..) | Bytes(..) | Int16(..) | Int32(..) | Int64(..) |
+        Float32(..) | Float64(..) | BigInt(..) | Decimal(..) | Bool(..) |
+        Datetime(..) | LocalDatetime(..) | LocalDate(..) | LocalTime(..) |
+        Duration(..) | Json(..)
+        => value.kind(),
+        _ => anyhow::bail!("unsupported value of kind {} for global {}",
+                           value.kind(), name),
+    };
+    Ok(cast)
+}
+
+impl Connection {
+    /// Set values of global variables for the session
+    ///
+    /// Globals set to [`Value::Nothing`] (e.g. `None` fields of a struct
+    /// deriving `GlobalsDelta`) are reset to their defaults. Other globals
+    /// of the session are not changed.
+    pub async fn set_globals<G: GlobalsDelta>(&mut self, globals: &G)
+        -> anyhow::Result<()>
+    {
+        let mut modifier = GlobalsModifier::new();
+        globals.apply(&mut modifier);
+        self._set_globals(modifier.into_values()).await
+    }
+    pub(crate) async fn _set_globals(&mut self, values: Vec<(String, Value)>)
+        -> anyhow::Result<()>
+    {
+        for (name, value) in values {
+            if value == Value::Nothing {
+                self._execute(format!("RESET GLOBAL {}", quote_global(&name)),
+                              Capabilities::SESSION_CONFIG).await?;
+                self.session.record_global(&name, &value);
+                continue;
+            }
+            let statement = format!("SET GLOBAL {} := <{}>$0",
+                quote_global(&name), cast_type(&name, &value)?);
+            let mut seq = self.start_sequence().await?;
+            seq.allow_capabilities = seq.allow_capabilities
+                | Capabilities::SESSION_CONFIG;
+            seq._query(&statement, &Value::Tuple(vec![value.clone()]),
+                       IoFormat::Binary).await?;
+            seq._process_exec(&statement).await?;
+            self.session.record_global(&name, &value);
+        }
+        Ok(())
+    }
+    /// Returns the same connection with global variables set
+    ///
+    /// See [`set_globals`](Connection::set_globals) for details.
+    pub async fn with_globals<G: GlobalsDelta>(mut self, globals: &G)
+        -> anyhow::Result<Connection>
+    {
+        self.set_globals(globals).await?;
+        Ok(self)
+    }
+}
+
+#[test]
+fn quote() {
+    assert_eq!(quote_global("default::user_id"), "`default`::`user_id`");
+    assert_eq!(quote_global("a`b::c"), "`a``b`::`c`");
+}
+
+#[test]
+fn cast() {
+    assert_eq!(cast_type("x", &Value::Str("a".into())).unwrap(), "str");
+    assert_eq!(cast_type("x", &Value::Int64(1)).unwrap(), "int64");
+    assert!(cast_type("x", &Value::empty_tuple()).is_err());
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+
+use edgedb_protocol::client_message::{HEADER_IMPLICIT_TYPENAMES};
+use edgedb_protocol::client_message::{HEADER_IMPLICIT_TYPEIDS};
+use edgedb_protocol::client_message::{HEADER_EXPLICIT_OBJECTIDS};
+
+
+/// Options controlling fields that server adds to the query results
+///
+/// By default the server adds an implicit `id` to every object shape, and
+/// no type ids or type names. Type names are needed to decode polymorphic
+/// results, while queries sensitive to the result size might want to turn
+/// off implicit ids.
+///
+/// Derived [`Queryable`](edgedb_protocol::queryable::Queryable) types
+/// account for these options automatically.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryOptions {
+    implicit_ids: bool,
+    implicit_typeids: bool,
+    implicit_typenames: bool,
+}
+
+impl Default for QueryOptions {
+    fn default() -> QueryOptions {
+        QueryOptions {
+            implicit_ids: true,
+            implicit_typeids: false,
+            implicit_typenames: false,
+        }
+    }
+}
+
+impl QueryOptions {
+    /// Add implicit `id` to object shapes (enabled by default)
+    pub fn with_implicit_ids(mut self, value: bool) -> Self {
+        self.implicit_ids = value;
+        self
+    }
+    /// Add implicit `__tid__` (type id) to object shapes
+    pub fn with_implicit_typeids(mut self, value: bool) -> Self {
+        self.implicit_typeids = value;
+        self
+    }
+    /// Add implicit `__tname__` (type name) to object shapes
+    pub fn with_implicit_typenames(mut self, value: bool) -> Self {
+        self.implicit_typenames = value;
+        self
+    }
+    pub fn implicit_ids(&self) -> bool {
+        self.implicit_ids
+    }
+    pub fn implicit_typeids(&self) -> bool {
+        self.implicit_typeids
+    }
+    pub fn implicit_typenames(&self) -> bool {
+        self.implicit_typenames
+    }
+    /// Add headers of the `Prepare` message
+    pub(crate) fn add_headers(&self, headers: &mut HashMap<u16, Bytes>) {
+        let on = Bytes::from_static(b"true");
+        if !self.implicit_ids {
+            headers.insert(HEADER_EXPLICIT_OBJECTIDS, on.clone());
+        }
+        if self.implicit_typeids {
+            headers.insert(HEADER_IMPLICIT_TYPEIDS, on.clone());
+        }
+        if self.implicit_typenames {
+            headers.insert(HEADER_IMPLICIT_TYPENAMES, on);
+        }
+    }
+}
+
+#[test]
+fn headers() {
+    let mut headers = HashMap::new();
+    QueryOptions::default().add_headers(&mut headers);
+    assert!(headers.is_empty());
+    QueryOptions::default()
+        .with_implicit_ids(false)
+        .with_implicit_typenames(true)
+        .add_headers(&mut headers);
+    assert_eq!(headers.len(), 2);
+    assert_eq!(headers[&HEADER_EXPLICIT_OBJECTIDS], "true");
+    assert_eq!(headers[&HEADER_IMPLICIT_TYPENAMES], "true");
+}
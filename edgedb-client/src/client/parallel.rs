@@ -0,0 +1,76 @@
+use async_std::prelude::StreamExt;
+use async_std::task::spawn_blocking;
+use bytes::Bytes;
+use rayon::prelude::*;
+
+use edgedb_protocol::client_message::IoFormat;
+use edgedb_protocol::errors::DecodeError;
+use edgedb_protocol::queryable::{Queryable, Decoder};
+use edgedb_protocol::value::Value;
+
+use crate::client::Connection;
+use crate::errors::NoResultExpected;
+use crate::reader::Decode;
+
+
+/// Passes row data through, so it can be decoded later
+struct RawDecoder;
+
+impl Decode for RawDecoder {
+    type Output = Bytes;
+    fn decode(&self, msg: Bytes) -> Result<Bytes, DecodeError> {
+        Ok(msg)
+    }
+}
+
+fn decode_rows<R>(decoder: &Decoder, rows: Vec<Bytes>)
+    -> Result<Vec<R>, DecodeError>
+    where R: Queryable + Send,
+{
+    rows.into_par_iter()
+        .map(|row| R::decode(decoder, &row))
+        .collect()
+}
+
+impl Connection {
+    /// Run the query and decode rows on the rayon thread pool
+    ///
+    /// Unlike [`query`](Connection::query), all rows are received into
+    /// memory first and only then decoded, in parallel. This pays off for
+    /// large results of wide shapes, where decoding takes more time than
+    /// reading from the network. Decoding doesn't block the async
+    /// executor, it's run via `spawn_blocking`. Order of rows is preserved.
+    pub async fn query_parallel<R>(&mut self, request: &str,
+        arguments: &Value)
+        -> anyhow::Result<Vec<R>>
+        where R: Queryable + Send + 'static,
+    {
+        let mut seq = self.start_sequence().await?;
+        let desc = seq._query(request, arguments, IoFormat::Binary).await?;
+        let root_pos = match desc.root_pos() {
+            Some(root_pos) => root_pos,
+            None => {
+                let completion_message = seq._process_exec(request).await?;
+                Err(NoResultExpected { completion_message })?
+            }
+        };
+        seq.check_descriptor::<R>(&desc, root_pos)?;
+        let decoder = seq.decoder();
+        let mut response = seq.response(RawDecoder, request);
+        let mut rows = Vec::new();
+        while let Some(row) = response.next().await.transpose()? {
+            rows.push(row);
+        }
+        let result = spawn_blocking(move || decode_rows(&decoder, rows));
+        Ok(result.await?)
+    }
+}
+
+#[test]
+fn decode_in_order() {
+    let rows = (0..1000i64)
+        .map(|i| Bytes::copy_from_slice(&i.to_be_bytes()))
+        .collect();
+    let values: Vec<i64> = decode_rows(&Decoder::default(), rows).unwrap();
+    assert_eq!(values, (0..1000).collect::<Vec<_>>());
+}
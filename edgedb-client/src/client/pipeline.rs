@@ -0,0 +1,293 @@
+use std::sync::Arc;
+
+use bytes::{Bytes, BytesMut};
+
+use edgedb_protocol::client_message::{ClientMessage, Prepare, Cardinality};
+use edgedb_protocol::client_message::{DescribeStatement, DescribeAspect};
+use edgedb_protocol::client_message::{IoFormat, OptimisticExecute};
+use edgedb_protocol::codec::Codec;
+use edgedb_protocol::descriptors::OutputTypedesc;
+use edgedb_protocol::queryable::{Queryable, Decoder};
+use edgedb_protocol::server_message::{ServerMessage, CommandDataDescription};
+use edgedb_protocol::value::Value;
+
+use crate::client::{Connection, Sequence, tag_statement};
+use crate::client::statements::StatementKey;
+use crate::errors::{ExecuteManyError, NoResultExpected};
+use crate::errors::TransactionControlStatement;
+use crate::statement::is_transaction_control;
+use crate::status::CommandStatus;
+
+
+/// A batch of queries sent to the server without waiting for responses
+///
+/// Created by [`Connection::pipeline`].
+#[must_use = "pipeline does nothing unless `run()` is called"]
+pub struct Pipeline<'a> {
+    conn: &'a mut Connection,
+    queries: Vec<(String, Value)>,
+}
+
+/// Result of a single query of the [`Pipeline`]
+pub struct PipelineResult {
+    desc: OutputTypedesc,
+    codec: Arc<dyn Codec>,
+    decoder: Decoder,
+    data: Vec<Bytes>,
+    status: CommandStatus,
+}
+
+impl Connection {
+    /// Start a pipeline of queries
+    ///
+    /// All queries of the pipeline are written to the connection before
+    /// reading any responses, so the whole batch takes at most two network
+    /// round trips (one to describe statements that are not in the
+    /// statement cache, and one to execute everything).
+    ///
+    /// ```rust,ignore
+    /// let results = conn.pipeline()
+    ///     .add("SELECT User { name }", Value::empty_tuple())
+    ///     .add("SELECT count(Post)", Value::empty_tuple())
+    ///     .run().await?;
+    /// let users = results[0].rows::<Value>()?;
+    /// ```
+    pub fn pipeline(&mut self) -> Pipeline<'_> {
+        Pipeline {
+            conn: self,
+            queries: Vec::new(),
+        }
+    }
+}
+
+impl Pipeline<'_> {
+    /// Add a query to the pipeline
+    pub fn add(mut self, request: impl Into<String>, arguments: Value)
+        -> Self
+    {
+        self.queries.push((request.into(), arguments));
+        self
+    }
+    /// Number of queries in the pipeline
+    pub fn len(&self) -> usize {
+        self.queries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+    /// Execute all the queries and return their results in order
+    ///
+    /// Every query is committed on its own (unless the connection is in a
+    /// transaction block). If one of the queries fails, server skips the
+    /// rest of them and [`ExecuteManyError`] is returned, with `index` of
+    /// the failed query. Queries before it have been executed, unless the
+    /// error is reported when compiling queries, in which case nothing is
+    /// executed.
+    pub async fn run(self) -> anyhow::Result<Vec<PipelineResult>> {
+        if self.queries.is_empty() {
+            return Ok(Vec::new());
+        }
+        if self.queries.iter().any(|(q, _)| is_transaction_control(q)) {
+            Err(TransactionControlStatement)?;
+        }
+        let mut seq = self.conn.start_sequence().await?;
+        let descriptions = describe(&mut seq, &self.queries).await?;
+        execute(&mut seq, &self.queries, descriptions).await
+    }
+}
+
+impl PipelineResult {
+    /// Rows returned by the query
+    pub fn rows<R: Queryable>(&self) -> anyhow::Result<Vec<R>> {
+        let root_pos = match self.desc.root_pos() {
+            Some(root_pos) => root_pos,
+            None => Err(NoResultExpected {
+                completion_message: self.status.status_data().clone(),
+            })?,
+        };
+        let mut ctx = self.desc.as_queryable_context();
+        ctx.has_implicit_tid = self.decoder.has_implicit_tid;
+        ctx.has_implicit_tname = self.decoder.has_implicit_tname;
+        ctx.has_implicit_id = self.decoder.has_implicit_id;
+        R::check_descriptor(&ctx, root_pos)?;
+        let rows = self.data.iter()
+            .map(|row| R::decode(&self.decoder, row))
+            .collect::<Result<_, _>>()?;
+        Ok(rows)
+    }
+    /// Rows returned by the query, decoded into dynamic values
+    pub fn values(&self) -> anyhow::Result<Vec<Value>> {
+        let rows = self.data.iter()
+            .map(|row| self.codec.decode(row))
+            .collect::<Result<_, _>>()?;
+        Ok(rows)
+    }
+    /// Completion status of the query
+    pub fn status(&self) -> &CommandStatus {
+        &self.status
+    }
+}
+
+fn statement_key(seq: &Sequence<'_>, request: &str) -> StatementKey {
+    StatementKey::new(&tag_statement(seq.query_tag, request),
+                      IoFormat::Binary, Cardinality::Many,
+                      &seq.prepare_headers())
+}
+
+/// Fetch descriptors of the statements that are not in the cache
+async fn describe(seq: &mut Sequence<'_>, queries: &[(String, Value)])
+    -> anyhow::Result<Vec<CommandDataDescription>>
+{
+    let mut descriptions = Vec::with_capacity(queries.len());
+    let mut missing = Vec::new();
+    for (index, (request, _)) in queries.iter().enumerate() {
+        let desc = seq.statements.get(&statement_key(seq, request));
+        if desc.is_none() {
+            missing.push(index);
+        }
+        descriptions.push(desc);
+    }
+    if !missing.is_empty() {
+        let mut messages = Vec::with_capacity(missing.len()*2 + 1);
+        for &index in &missing {
+            messages.push(ClientMessage::Prepare(Prepare {
+                headers: seq.prepare_headers(),
+                io_format: IoFormat::Binary,
+                expected_cardinality: Cardinality::Many,
+                statement_name: Bytes::from_static(b""),
+                command_text: tag_statement(seq.query_tag,
+                                            &queries[index].0),
+            }));
+            messages.push(ClientMessage::DescribeStatement(
+                DescribeStatement {
+                    headers: Default::default(),
+                    aspect: DescribeAspect::DataDescription,
+                    statement_name: Bytes::from_static(b""),
+                }));
+        }
+        messages.push(ClientMessage::Sync);
+        seq.send_messages(&messages).await?;
+
+        let mut described = missing.iter();
+        let mut error = None;
+        loop {
+            match seq.reader.message().await? {
+                ServerMessage::PrepareComplete(..) => {}
+                ServerMessage::CommandDataDescription(data_desc) => {
+                    let index = *described.next().ok_or_else(|| {
+                        anyhow::anyhow!("unexpected data description")
+                    })?;
+                    let key = statement_key(seq, &queries[index].0);
+                    seq.statements.insert(key, data_desc.clone());
+                    descriptions[index] = Some(data_desc);
+                }
+                ServerMessage::ErrorResponse(err) => {
+                    let index = missing.len() - described.len();
+                    error = Some(ExecuteManyError {
+                        index: missing[index],
+                        error: err,
+                    });
+                }
+                ServerMessage::ReadyForCommand(ready) => {
+                    seq.reader.consume_ready(ready);
+                    break;
+                }
+                msg => {
+                    log::warn!("unsolicited message {:?}", msg);
+                }
+            }
+        }
+        if let Some(error) = error {
+            seq.end_clean();
+            Err(error)?;
+        }
+    }
+    descriptions.into_iter()
+        .map(|desc| desc.ok_or_else(|| {
+            anyhow::anyhow!("statement is not described by the server")
+        }))
+        .collect()
+}
+
+async fn execute(seq: &mut Sequence<'_>, queries: &[(String, Value)],
+                 descriptions: Vec<CommandDataDescription>)
+    -> anyhow::Result<Vec<PipelineResult>>
+{
+    let mut messages = Vec::with_capacity(queries.len() + 1);
+    let mut results = Vec::with_capacity(queries.len());
+    for ((request, arguments), data_desc) in queries.iter()
+        .zip(&descriptions)
+    {
+        let incodec = seq.codecs.input(&data_desc.input()?)?;
+        let desc = data_desc.output()?;
+        let codec = seq.codecs.output(&desc)?;
+        let mut arg_buf = BytesMut::with_capacity(8);
+        if let Err(e) = incodec.encode(&mut arg_buf, arguments) {
+            // nothing is sent yet
+            seq.end_clean();
+            return Err(e.into());
+        }
+        messages.push(ClientMessage::OptimisticExecute(OptimisticExecute {
+            headers: seq.prepare_headers(),
+            io_format: IoFormat::Binary,
+            expected_cardinality: Cardinality::Many,
+            command_text: tag_statement(seq.query_tag, request),
+            input_typedesc_id: data_desc.input_typedesc_id,
+            output_typedesc_id: data_desc.output_typedesc_id,
+            arguments: arg_buf.freeze(),
+        }));
+        results.push(PipelineResult {
+            desc,
+            codec,
+            decoder: seq.decoder(),
+            data: Vec::new(),
+            status: CommandStatus::new(Bytes::new()),
+        });
+    }
+    messages.push(ClientMessage::Sync);
+    seq.send_messages(&messages).await?;
+
+    let mut done = 0;
+    let mut error = None;
+    loop {
+        match seq.reader.message().await? {
+            ServerMessage::Data(data) if done < results.len() => {
+                results[done].data.extend(data.data);
+            }
+            ServerMessage::CommandComplete(c) if done < results.len() => {
+                results[done].status = CommandStatus::new(c.status_data);
+                done += 1;
+            }
+            ServerMessage::CommandDataDescription(_)
+            if done < results.len() && error.is_none()
+            => {
+                // schema changed after statements were described, server
+                // doesn't execute the statement in this case
+                let key = statement_key(seq, &queries[done].0);
+                seq.statements.remove(&key);
+                error = Some(anyhow::anyhow!(
+                    "type of the statement #{} changed during \
+                     pipeline execution", done));
+                done += 1;
+            }
+            ServerMessage::ErrorResponse(err) => {
+                error = Some(ExecuteManyError {
+                    index: done,
+                    error: err,
+                }.into());
+            }
+            ServerMessage::ReadyForCommand(ready) => {
+                seq.reader.consume_ready(ready);
+                seq.end_clean();
+                break;
+            }
+            msg => {
+                log::warn!("unsolicited message {:?}", msg);
+            }
+        }
+    }
+    if let Some(error) = error {
+        return Err(error);
+    }
+    Ok(results)
+}
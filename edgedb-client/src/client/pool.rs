@@ -0,0 +1,611 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_std::channel::{bounded, Receiver, Sender};
+use async_std::future::timeout;
+use async_std::prelude::StreamExt;
+
+use edgedb_protocol::queryable::Queryable;
+use edgedb_protocol::value::Value;
+
+use crate::builder::Builder;
+use crate::client::Connection;
+use crate::client::codecs::CodecCache;
+use crate::errors::{PoolClosed, AcquireTimeout};
+use crate::status::CommandStatus;
+
+
+/// Default maximum number of connections per database
+pub const DEFAULT_MAX_SIZE: usize = 10;
+
+/// Options of the connection [`Pool`]
+#[derive(Debug, Clone)]
+pub struct PoolOptions {
+    max_size: usize,
+    min_size: usize,
+    acquire_timeout: Option<Duration>,
+    idle_timeout: Option<Duration>,
+}
+
+/// A pool of connections
+///
+/// Connections are established lazily on [`acquire`](Pool::acquire) (or
+/// up front with [`warm_up`](Pool::warm_up)) and returned to the pool when
+/// [`PoolConnection`] is dropped. Connections that are in inconsistent
+/// state (e.g. a request was interrupted) or in a transaction are closed
+/// instead.
+///
+/// For simple cases queries can be run on the pool directly, e.g.
+/// [`query`](Pool::query), which acquires a connection for the duration
+/// of a single query.
+///
+/// Pool is cheap to clone, all clones share the same connections.
+///
+/// A single pool can serve multiple databases of the same instance:
+/// [`database`](Pool::database) returns a handle that acquires connections
+/// to a different database, with a separate set of connections (and a
+/// separate size limit) for each database. This is useful for multi-tenant
+/// applications that have a database per tenant.
+#[derive(Clone)]
+pub struct Pool {
+    inner: Arc<Inner>,
+    database: String,
+}
+
+struct Inner {
+    builder: Builder,
+    options: PoolOptions,
+    databases: Mutex<HashMap<String, Arc<SubPool>>>,
+    closed: AtomicBool,
+}
+
+/// Connections to a single database
+struct SubPool {
+    builder: Builder,
+    max_size: usize,
+    idle: Mutex<Idle>,
+    tokens: Receiver<()>,
+    release: Sender<()>,
+    /// Shared by all connections to this database
+    codecs: Arc<CodecCache>,
+}
+
+struct Idle {
+    /// Connections along with the time they were returned, oldest first
+    connections: Vec<(Connection, Instant)>,
+    /// Set when pool is closed, connections are not returned anymore
+    closed: bool,
+}
+
+/// Result of the [`Pool::close`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CloseStats {
+    /// Connections closed gracefully
+    pub terminated: usize,
+    /// Connections that were still in use when timeout expired
+    ///
+    /// These are closed (without `Terminate` message) when dropped.
+    pub force_closed: usize,
+}
+
+/// A connection acquired from the [`Pool`]
+///
+/// All the methods of the [`Connection`] are available via `Deref`.
+/// Connection is returned to the pool when dropped.
+pub struct PoolConnection {
+    conn: Option<Connection>,
+    slot: Slot,
+}
+
+/// An occupied slot of the pool, freed on drop
+///
+/// This also frees the slot if `acquire()` is cancelled while connecting.
+struct Slot(Arc<SubPool>);
+
+impl Default for PoolOptions {
+    fn default() -> PoolOptions {
+        PoolOptions {
+            max_size: DEFAULT_MAX_SIZE,
+            min_size: 0,
+            acquire_timeout: None,
+            idle_timeout: None,
+        }
+    }
+}
+
+impl PoolOptions {
+    /// Set maximum number of connections per database
+    ///
+    /// Default is 10. Values less than 1 are treated as 1.
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size.max(1);
+        self
+    }
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+    /// Set number of connections kept open when idle
+    ///
+    /// These connections are established by [`Pool::warm_up`] and are not
+    /// closed by the idle timeout. Default is 0. Values larger than
+    /// `max_size` are treated as `max_size`.
+    pub fn with_min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+    pub fn min_size(&self) -> usize {
+        self.min_size.min(self.max_size)
+    }
+    /// Set maximum time to wait for a free slot in [`Pool::acquire`]
+    ///
+    /// When expired, `acquire()` fails with [`AcquireTimeout`]. This only
+    /// limits waiting for other connections to be returned, establishing
+    /// a new connection is limited by the `Builder`'s connect timeout.
+    /// Default is `None`: wait indefinitely.
+    pub fn with_acquire_timeout(mut self, timeout: Option<Duration>) -> Self
+    {
+        self.acquire_timeout = timeout;
+        self
+    }
+    pub fn acquire_timeout(&self) -> Option<Duration> {
+        self.acquire_timeout
+    }
+    /// Close connections that were idle for longer than `timeout`
+    ///
+    /// Stale connections are closed on the next `acquire()`, except ones
+    /// needed to keep [`min_size`](PoolOptions::with_min_size) idle
+    /// connections. Default is `None`: idle connections are kept open.
+    pub fn with_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+}
+
+/// Remove items idle longer than `idle_timeout`, keeping at least `keep`
+fn take_stale<T>(items: &mut Vec<(T, Instant)>, now: Instant,
+                 idle_timeout: Duration, keep: usize)
+    -> Vec<T>
+{
+    let stale = items.iter()
+        .take_while(|(_, since)| now.duration_since(*since) > idle_timeout)
+        .count()
+        .min(items.len().saturating_sub(keep));
+    items.drain(..stale).map(|(item, _)| item).collect()
+}
+
+impl Pool {
+    /// Create a pool with default options
+    ///
+    /// No connections are established until the first `acquire()`.
+    pub fn new(builder: &Builder) -> Pool {
+        Pool::with_options(builder, PoolOptions::default())
+    }
+    /// Create a pool with specified options
+    pub fn with_options(builder: &Builder, options: PoolOptions) -> Pool {
+        Pool {
+            database: builder.get_database().to_owned(),
+            inner: Arc::new(Inner {
+                builder: builder.clone(),
+                options,
+                databases: Mutex::new(HashMap::new()),
+                closed: AtomicBool::new(false),
+            }),
+        }
+    }
+    /// Returns a handle that acquires connections to another database
+    ///
+    /// The handle shares configuration with this pool, but connections to
+    /// each database are pooled separately. See also
+    /// [`Builder::with_database`] for use without a pool.
+    pub fn database(&self, name: &str) -> Pool {
+        Pool {
+            inner: self.inner.clone(),
+            database: name.to_owned(),
+        }
+    }
+    /// Name of the database connections are acquired for
+    pub fn get_database(&self) -> &str {
+        &self.database
+    }
+    pub fn options(&self) -> &PoolOptions {
+        &self.inner.options
+    }
+    fn sub_pool(&self) -> Arc<SubPool> {
+        let mut databases = self.inner.databases.lock()
+            .expect("pool lock");
+        databases.entry(self.database.clone())
+            .or_insert_with(|| {
+                let builder = self.inner.builder.with_database(&self.database);
+                Arc::new(SubPool::new(builder, self.inner.options.max_size))
+            })
+            .clone()
+    }
+    /// Acquire a connection
+    ///
+    /// Returns an idle connection if there is one, otherwise establishes
+    /// a new one. If maximum number of connections is reached, waits for
+    /// another connection to be returned to the pool.
+    ///
+    /// Session state set by the previous user of an idle connection with
+    /// [`set_globals`](Connection::set_globals),
+    /// [`set_config`](Connection::set_config) or
+    /// [`set_module_aliases`](Connection::set_module_aliases) is reset
+    /// before the connection is returned, so it's fine to set per-request
+    /// globals (e.g. for access policies) on a pooled connection:
+    ///
+    /// ```rust,ignore
+    /// let mut conn = pool.acquire().await?;
+    /// conn.set_globals(&Globals { user_id }).await?;
+    /// ```
+    ///
+    /// Fails with [`PoolClosed`] error after [`close`](Pool::close) is
+    /// called.
+    pub async fn acquire(&self) -> anyhow::Result<PoolConnection> {
+        if self.is_closed() {
+            Err(PoolClosed)?;
+        }
+        let pool = self.sub_pool();
+        let token = match self.inner.options.acquire_timeout {
+            Some(wait) => timeout(wait, pool.tokens.recv()).await
+                .map_err(|_| AcquireTimeout)?,
+            None => pool.tokens.recv().await,
+        };
+        token.map_err(|_| PoolClosed)?;
+        let slot = Slot(pool);
+        if self.is_closed() {
+            Err(PoolClosed)?;
+        }
+        let (idle, stale) = {
+            let mut idle = slot.0.idle.lock().expect("pool lock");
+            let stale = match self.inner.options.idle_timeout {
+                Some(idle_timeout) => take_stale(&mut idle.connections,
+                    Instant::now(), idle_timeout,
+                    self.inner.options.min_size()),
+                None => Vec::new(),
+            };
+            (idle.connections.pop().map(|(conn, _)| conn), stale)
+        };
+        if !stale.is_empty() {
+            log::debug!("Closing {} idle connections", stale.len());
+            async_std::task::spawn(async move {
+                for conn in stale {
+                    conn.terminate().await.ok();
+                }
+            });
+        }
+        let conn = match idle {
+            Some(mut conn) => {
+                // state set by the previous user, e.g. per-request globals
+                if conn.session.is_changed() {
+                    conn.reset_session().await?;
+                }
+                conn
+            }
+            None => {
+                let mut conn = slot.0.builder.connect().await?;
+                conn.codecs = slot.0.codecs.clone();
+                conn
+            }
+        };
+        Ok(PoolConnection {
+            conn: Some(conn),
+            slot,
+        })
+    }
+    /// Establish [`min_size`](PoolOptions::with_min_size) connections
+    ///
+    /// Useful at application startup to fail early if the database is
+    /// unavailable and to avoid connection latency on first requests.
+    pub async fn warm_up(&self) -> anyhow::Result<()> {
+        let mut connections = Vec::new();
+        for _ in 0..self.inner.options.min_size() {
+            connections.push(self.acquire().await?);
+        }
+        Ok(())
+    }
+    /// Run a query on a pooled connection and return all the rows
+    pub async fn query<R>(&self, request: &str, arguments: &Value)
+        -> anyhow::Result<Vec<R>>
+        where R: Queryable,
+    {
+        let mut conn = self.acquire().await?;
+        let mut response = conn.query(request, arguments).await?;
+        let mut rows = Vec::new();
+        while let Some(row) = response.next().await.transpose()? {
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+    /// Run a query returning a single row on a pooled connection
+    pub async fn query_row<R>(&self, request: &str, arguments: &Value)
+        -> anyhow::Result<R>
+        where R: Queryable,
+    {
+        self.acquire().await?.query_row(request, arguments).await
+    }
+    /// Run a query returning zero or one row on a pooled connection
+    pub async fn query_row_opt<R>(&self, request: &str, arguments: &Value)
+        -> anyhow::Result<Option<R>>
+        where R: Queryable,
+    {
+        self.acquire().await?.query_row_opt(request, arguments).await
+    }
+    /// Run a query on a pooled connection and return result as JSON
+    pub async fn query_json(&self, request: &str, arguments: &Value)
+        -> anyhow::Result<String>
+    {
+        let mut conn = self.acquire().await?;
+        let mut query = conn.query_json(request, arguments).await?;
+        let mut result = String::new();
+        while let Some(chunk) = query.next().await {
+            result.push_str(&chunk?);
+        }
+        Ok(result)
+    }
+    /// Execute a statement (or a script) on a pooled connection
+    pub async fn execute<S>(&self, request: S)
+        -> anyhow::Result<CommandStatus>
+        where S: ToString,
+    {
+        self.acquire().await?.execute(request).await
+    }
+    pub fn is_closed(&self) -> bool {
+        self.inner.closed.load(Ordering::SeqCst)
+    }
+    /// Close the pool gracefully
+    ///
+    /// Stops handing out connections (pending and subsequent `acquire()`
+    /// calls fail with [`PoolClosed`]), waits up to `timeout` for the
+    /// connections in use to be returned, then sends `Terminate` to every
+    /// connection. Closes connections to all the databases of the pool,
+    /// including ones acquired through [`database`](Pool::database)
+    /// handles.
+    pub async fn close(&self, timeout: Duration) -> CloseStats {
+        self.inner.closed.store(true, Ordering::SeqCst);
+        let deadline = Instant::now() + timeout;
+        let pools = self.inner.databases.lock().expect("pool lock")
+            .values().cloned().collect::<Vec<_>>();
+        let mut stats = CloseStats::default();
+        for pool in pools {
+            let result = pool.close(deadline).await;
+            stats.terminated += result.terminated;
+            stats.force_closed += result.force_closed;
+        }
+        if stats.force_closed > 0 {
+            log::warn!("{} connections were still in use when the pool \
+                        was closed", stats.force_closed);
+        }
+        stats
+    }
+    /// Close the pool when the process receives SIGINT or SIGTERM
+    ///
+    /// Installs a process-wide signal handler (only one can be installed
+    /// per process, so this fails if another handler is already set) and
+    /// returns a channel that receives the result of the
+    /// [`close`](Pool::close) with the `drain` timeout. Applications
+    /// usually stop accepting requests and wait on the channel before
+    /// exiting:
+    ///
+    /// ```rust,ignore
+    /// let closed = pool.close_on_signal(Duration::from_secs(10))?;
+    /// let stats = closed.recv().await?;
+    /// ```
+    ///
+    /// Only available with the `ctrlc` feature enabled.
+    #[cfg(feature="ctrlc")]
+    pub fn close_on_signal(&self, drain: Duration)
+        -> anyhow::Result<Receiver<CloseStats>>
+    {
+        let (signal_tx, signal_rx) = bounded(1);
+        ctrlc::set_handler(move || { signal_tx.try_send(()).ok(); })?;
+        let (tx, rx) = bounded(1);
+        let pool = self.clone();
+        async_std::task::spawn(async move {
+            if signal_rx.recv().await.is_ok() {
+                log::info!("Signal received, closing connection pool");
+                tx.send(pool.close(drain).await).await.ok();
+            }
+        });
+        Ok(rx)
+    }
+}
+
+impl SubPool {
+    fn new(builder: Builder, max_size: usize) -> SubPool {
+        let (release, tokens) = bounded(max_size);
+        for _ in 0..max_size {
+            release.try_send(()).expect("channel has capacity");
+        }
+        SubPool {
+            builder,
+            max_size,
+            idle: Mutex::new(Idle {
+                connections: Vec::with_capacity(max_size),
+                closed: false,
+            }),
+            tokens,
+            release,
+            codecs: Default::default(),
+        }
+    }
+    async fn close(&self, deadline: Instant) -> CloseStats {
+        // holding all the tokens means no connection is in use
+        let mut held = 0;
+        let wait = deadline.saturating_duration_since(Instant::now());
+        timeout(wait, async {
+            while held < self.max_size {
+                if self.tokens.recv().await.is_err() {
+                    break;
+                }
+                held += 1;
+            }
+        }).await.ok();
+        // wake up pending `acquire()` calls
+        self.release.close();
+        let connections = {
+            let mut idle = self.idle.lock().expect("pool lock");
+            idle.closed = true;
+            std::mem::take(&mut idle.connections)
+        };
+        let mut stats = CloseStats {
+            terminated: 0,
+            force_closed: self.max_size - held,
+        };
+        for (conn, _) in connections {
+            let wait = deadline.saturating_duration_since(Instant::now());
+            match timeout(wait, conn.terminate()).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => log::debug!("Error terminating: {:#}", e),
+                Err(_) => log::debug!("Timed out terminating connection"),
+            }
+            stats.terminated += 1;
+        }
+        stats
+    }
+}
+
+impl PoolConnection {
+    /// Remove connection from the pool
+    ///
+    /// The slot in the pool is freed, and the connection is not returned
+    /// to the pool.
+    pub fn detach(mut self) -> Connection {
+        self.conn.take().expect("connection is present")
+    }
+}
+
+impl Drop for PoolConnection {
+    fn drop(&mut self) {
+        use edgedb_protocol::server_message::TransactionState;
+
+        if let Some(conn) = self.conn.take() {
+            if std::thread::panicking() {
+                // user code might have left session in any state
+                log::debug!("Discarding connection dropped on panic");
+            } else if conn.is_consistent() && conn.transaction_state() ==
+                TransactionState::NotInTransaction
+            {
+                let mut idle = self.slot.0.idle.lock().expect("pool lock");
+                if !idle.closed {
+                    idle.connections.push((conn, Instant::now()));
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.release.try_send(()).ok();
+    }
+}
+
+impl Deref for PoolConnection {
+    type Target = Connection;
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection is present")
+    }
+}
+
+impl DerefMut for PoolConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("connection is present")
+    }
+}
+
+impl fmt::Debug for Pool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Pool")
+            .field("database", &self.database)
+            .field("options", &self.inner.options)
+            .finish()
+    }
+}
+
+#[test]
+fn databases() {
+    let mut builder = Builder::new();
+    builder.database("main");
+    let pool = Pool::with_options(&builder,
+        PoolOptions::default().with_max_size(0));
+    assert_eq!(pool.options().max_size(), 1);
+    assert_eq!(pool.options().min_size(), 0);
+    assert_eq!(pool.get_database(), "main");
+    let tenant = pool.database("tenant1");
+    assert_eq!(tenant.get_database(), "tenant1");
+    assert_eq!(tenant.sub_pool().builder.get_database(), "tenant1");
+    assert!(Arc::ptr_eq(&tenant.sub_pool(),
+                        &pool.database("tenant1").sub_pool()));
+    assert!(!Arc::ptr_eq(&tenant.sub_pool(), &pool.sub_pool()));
+}
+
+#[test]
+fn close() {
+    use async_std::task::block_on;
+
+    let pool = Pool::with_options(&Builder::new(),
+        PoolOptions::default().with_max_size(2));
+    let sub = pool.sub_pool();
+    // emulate a connection in use
+    let slot = block_on(sub.tokens.recv()).map(|()| Slot(sub.clone()));
+    let stats = block_on(pool.close(Duration::from_millis(10)));
+    assert_eq!(stats, CloseStats { terminated: 0, force_closed: 1 });
+    assert!(pool.is_closed());
+    assert!(sub.idle.lock().unwrap().closed);
+    let err = block_on(pool.database("other").acquire()).err()
+        .expect("pool is closed");
+    assert!(err.is::<PoolClosed>());
+    drop(slot);
+}
+
+#[test]
+fn options() {
+    let options = PoolOptions::default()
+        .with_max_size(3)
+        .with_min_size(5)
+        .with_acquire_timeout(Some(Duration::from_secs(1)))
+        .with_idle_timeout(Some(Duration::from_secs(60)));
+    assert_eq!(options.min_size(), 3);
+    assert_eq!(options.acquire_timeout(), Some(Duration::from_secs(1)));
+    assert_eq!(options.idle_timeout(), Some(Duration::from_secs(60)));
+    assert_eq!(PoolOptions::default().idle_timeout(), None);
+}
+
+#[test]
+fn acquire_timeout() {
+    use async_std::task::block_on;
+
+    let pool = Pool::with_options(&Builder::new(),
+        PoolOptions::default()
+        .with_max_size(1)
+        .with_acquire_timeout(Some(Duration::from_millis(10))));
+    let sub = pool.sub_pool();
+    // emulate a connection in use
+    let slot = block_on(sub.tokens.recv()).map(|()| Slot(sub.clone()));
+    let err = block_on(pool.acquire()).err().expect("no free slots");
+    assert!(err.is::<AcquireTimeout>());
+    drop(slot);
+}
+
+#[test]
+fn stale() {
+    let now = Instant::now();
+    let minute = Duration::from_secs(60);
+    let mut items = vec![
+        (1, now - minute * 3),
+        (2, now - minute * 2),
+        (3, now),
+    ];
+    assert_eq!(take_stale(&mut items, now, minute * 5, 0), Vec::<i32>::new());
+    assert_eq!(take_stale(&mut items, now, minute, 2), vec![1]);
+    assert_eq!(items.len(), 2);
+    assert_eq!(take_stale(&mut items, now, minute, 0), vec![2]);
+    assert_eq!(items.iter().map(|(x, _)| *x).collect::<Vec<_>>(), vec![3]);
+}
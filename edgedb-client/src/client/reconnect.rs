@@ -0,0 +1,158 @@
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+
+use edgedb_protocol::client_message::Capabilities;
+use edgedb_protocol::server_message::TransactionState;
+use edgedb_protocol::state::{ConfigModifier, GlobalsModifier};
+use edgedb_protocol::value::Value;
+
+use crate::client::Connection;
+use crate::client::aliases::alias_script;
+use crate::client::config::configure_script;
+use crate::client::globals::quote_global;
+
+
+/// Session state set by the application, replayed after reconnect
+#[derive(Debug, Default)]
+pub(crate) struct SessionState {
+    config: ConfigModifier,
+    globals: GlobalsModifier,
+    aliases: Vec<(String, String)>,
+}
+
+impl SessionState {
+    pub fn record_config(&mut self, config: &ConfigModifier) {
+        for (name, value) in config.values() {
+            match value {
+                Some(value) => self.config.set(name, value.clone()),
+                None => self.config.reset(name),
+            }
+        }
+    }
+    pub fn record_global(&mut self, name: &str, value: &Value) {
+        self.globals.set(name, value.clone());
+    }
+    pub fn record_alias(&mut self, alias: &str, module: &str) {
+        match self.aliases.iter_mut().find(|(a, _)| a == alias) {
+            Some((_, old)) => *old = module.to_owned(),
+            None => self.aliases.push((alias.to_owned(), module.to_owned())),
+        }
+    }
+    /// Whether anything was recorded since the session was reset
+    pub fn is_changed(&self) -> bool {
+        !self.config.values().is_empty()
+            || !self.globals.values().is_empty()
+            || !self.aliases.is_empty()
+    }
+    /// Script resetting recorded configuration and globals to defaults
+    pub fn reset_script(&self) -> String {
+        let mut config = ConfigModifier::new();
+        for (name, _) in self.config.values() {
+            config.reset(name);
+        }
+        let mut script = configure_script(&config);
+        for (name, _) in self.globals.values() {
+            script.push_str(&format!("RESET GLOBAL {};\n",
+                                     quote_global(name)));
+        }
+        script
+    }
+    fn globals(&self) -> Vec<(String, Value)> {
+        self.globals.values().iter()
+            // reset globals have default values in a new session anyway
+            .filter(|(_, value)| *value != Value::Nothing)
+            .cloned()
+            .collect()
+    }
+}
+
+impl Connection {
+    /// Whether broken connection should be reestablished on the next request
+    pub(crate) fn should_reconnect(&self) -> bool {
+        self.dirty && self.reconnect.is_some()
+            && !self.cancel.is_cancelled()
+            && self.transaction_state == TransactionState::NotInTransaction
+    }
+    /// Boxed, because replaying session state starts new sequences
+    pub(crate) fn reconnect(&mut self)
+        -> Pin<Box<dyn Future<Output=anyhow::Result<()>> + Send + '_>>
+    {
+        Box::pin(async move {
+            let builder = self.reconnect.as_ref()
+                .expect("reconnect is enabled");
+            log::info!("Connection is broken, reconnecting");
+            let new = builder.connect().await?;
+            self.read = new.read;
+            self.write = new.write;
+            self.shutdown = new.shutdown;
+            self.input_buf = new.input_buf;
+            self.output_buf = new.output_buf;
+            self.version = new.version;
+            self.params = new.params;
+            self.param_watch.merge(new.param_watch);
+            self.transaction_state = new.transaction_state;
+            self.dirty = false;
+
+            let config = configure_script(&self.session.config);
+            if !config.is_empty() {
+                self._execute(config, Capabilities::SESSION_CONFIG).await?;
+            }
+            let aliases = alias_script(&self.session.aliases);
+            if !aliases.is_empty() {
+                self._execute(aliases, Capabilities::SESSION_CONFIG).await?;
+            }
+            let globals = self.session.globals();
+            if !globals.is_empty() {
+                // recorded again by `_set_globals`
+                let session = mem::take(&mut self.session);
+                self.session.config = session.config;
+                self.session.aliases = session.aliases;
+                self._set_globals(globals).await?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[test]
+fn record() {
+    use edgedb_protocol::state::{ConfigDelta, SessionConfig};
+
+    let mut session = SessionState::default();
+    let mut config = ConfigModifier::new();
+    SessionConfig::new()
+        .with_apply_access_policies(false)
+        .reset_allow_bare_ddl()
+        .apply(&mut config);
+    session.record_config(&config);
+    let mut config = ConfigModifier::new();
+    SessionConfig::new()
+        .with_apply_access_policies(true)
+        .apply(&mut config);
+    session.record_config(&config);
+    assert_eq!(configure_script(&session.config),
+        "CONFIGURE SESSION SET `apply_access_policies` := true;\n\
+         CONFIGURE SESSION RESET `allow_bare_ddl`;\n");
+
+    session.record_global("default::user_id", &Value::Int64(1));
+    session.record_global("default::tenant", &Value::Str("x".into()));
+    session.record_global("default::tenant", &Value::Nothing);
+    assert_eq!(session.globals(),
+               vec![("default::user_id".to_owned(), Value::Int64(1))]);
+
+    session.record_alias("", "app");
+    session.record_alias("m", "app::models");
+    session.record_alias("", "default");
+    assert_eq!(session.aliases, vec![
+        ("".to_owned(), "default".to_owned()),
+        ("m".to_owned(), "app::models".to_owned()),
+    ]);
+    assert!(session.is_changed());
+    assert_eq!(session.reset_script(),
+        "CONFIGURE SESSION RESET `apply_access_policies`;\n\
+         CONFIGURE SESSION RESET `allow_bare_ddl`;\n\
+         RESET GLOBAL `default`::`user_id`;\n\
+         RESET GLOBAL `default`::`tenant`;\n");
+    assert!(!SessionState::default().is_changed());
+}
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+
+use edgedb_protocol::client_message::{IoFormat, Cardinality};
+use edgedb_protocol::server_message::CommandDataDescription;
+
+
+/// Default for [`Builder::max_prepared_statements`]
+///
+/// [`Builder::max_prepared_statements`]:
+///     crate::Builder::max_prepared_statements
+pub(crate) const DEFAULT_MAX_STATEMENTS: usize = 100;
+
+/// Everything that influences how server compiles the statement
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct StatementKey {
+    text: String,
+    io_format: IoFormat,
+    cardinality: Cardinality,
+    headers: Vec<(u16, Bytes)>,
+}
+
+/// Type descriptors of recently executed statements
+///
+/// Known descriptors allow sending `OptimisticExecute` instead of
+/// `Prepare`, `DescribeStatement` and `Execute` in separate round trips.
+/// Server checks descriptor ids, so a stale entry only costs an extra
+/// round trip. When the cache is full, least recently used entry is
+/// evicted.
+#[derive(Debug)]
+pub(crate) struct StatementCache {
+    capacity: usize,
+    clock: u64,
+    entries: HashMap<StatementKey, (CommandDataDescription, u64)>,
+}
+
+impl StatementKey {
+    pub fn new(text: &str, io_format: IoFormat, cardinality: Cardinality,
+               headers: &HashMap<u16, Bytes>)
+        -> StatementKey
+    {
+        let mut headers = headers.iter()
+            .map(|(&name, value)| (name, value.clone()))
+            .collect::<Vec<_>>();
+        headers.sort();
+        StatementKey {
+            text: text.to_owned(),
+            io_format,
+            cardinality,
+            headers,
+        }
+    }
+}
+
+impl StatementCache {
+    pub fn new(capacity: usize) -> StatementCache {
+        StatementCache {
+            capacity,
+            clock: 0,
+            entries: HashMap::new(),
+        }
+    }
+    pub fn get(&mut self, key: &StatementKey)
+        -> Option<CommandDataDescription>
+    {
+        self.clock += 1;
+        let (desc, used) = self.entries.get_mut(key)?;
+        *used = self.clock;
+        Some(desc.clone())
+    }
+    pub fn insert(&mut self, key: StatementKey, desc: CommandDataDescription)
+    {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity &&
+            !self.entries.contains_key(&key)
+        {
+            let oldest = self.entries.iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        self.entries.insert(key, (desc, self.clock));
+    }
+    pub fn remove(&mut self, key: &StatementKey) {
+        self.entries.remove(key);
+    }
+}
+
+impl Default for StatementCache {
+    fn default() -> StatementCache {
+        StatementCache::new(DEFAULT_MAX_STATEMENTS)
+    }
+}
+
+#[test]
+fn lru() {
+    use edgedb_protocol::model::Uuid;
+
+    let desc = CommandDataDescription {
+        headers: HashMap::new(),
+        result_cardinality: Cardinality::One,
+        input_typedesc_id: Uuid::from_u128(0xFF),
+        input_typedesc: Bytes::new(),
+        output_typedesc_id: Uuid::from_u128(0x105),
+        output_typedesc: Bytes::new(),
+    };
+    let key = |text: &str| StatementKey::new(text,
+        IoFormat::Binary, Cardinality::Many, &HashMap::new());
+    let mut cache = StatementCache::new(2);
+    cache.insert(key("SELECT 1"), desc.clone());
+    cache.insert(key("SELECT 2"), desc.clone());
+    assert!(cache.get(&key("SELECT 1")).is_some());
+    cache.insert(key("SELECT 3"), desc.clone());
+    assert!(cache.get(&key("SELECT 1")).is_some());
+    assert!(cache.get(&key("SELECT 2")).is_none());
+    assert!(cache.get(&key("SELECT 3")).is_some());
+
+    let json = StatementKey::new("SELECT 1",
+        IoFormat::Json, Cardinality::Many, &HashMap::new());
+    assert!(cache.get(&json).is_none());
+    cache.remove(&key("SELECT 1"));
+    assert!(cache.get(&key("SELECT 1")).is_none());
+
+    let mut disabled = StatementCache::new(0);
+    disabled.insert(key("SELECT 1"), desc);
+    assert!(disabled.get(&key("SELECT 1")).is_none());
+}
@@ -0,0 +1,91 @@
+//! EdgeDB Cloud instance names and endpoints
+use anyhow::Context;
+use serde::Deserialize;
+
+
+/// Claims of the secret key that are used to find the instance
+#[derive(Debug, Deserialize)]
+struct Claims {
+    #[serde(rename="iss")]
+    issuer: String,
+}
+
+/// Parse `org/instance` name, returns `(org, instance)`
+pub fn parse_instance_name(name: &str) -> anyhow::Result<(&str, &str)> {
+    let (org, inst) = name.split_once('/')
+        .with_context(|| format!("cloud instance name {:?} must be in \
+                                  the form `org/instance`", name))?;
+    for part in &[org, inst] {
+        if part.is_empty() || !part.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            anyhow::bail!("invalid cloud instance name {:?}: only \
+                           letters, digits and dashes are allowed", name);
+        }
+    }
+    Ok((org, inst))
+}
+
+/// Extract DNS zone (issuer claim) from the secret key
+///
+/// Secret key is a JWT. Its signature is not checked here, as it's the
+/// server who validates the key. The key is only decoded to find out
+/// which cloud endpoint it belongs to.
+fn dns_zone(secret_key: &str) -> anyhow::Result<String> {
+    let payload = secret_key.split('.').nth(1)
+        .context("malformed secret key: not a JWT")?;
+    let data = base64::decode_config(payload, base64::URL_SAFE_NO_PAD)
+        .context("malformed secret key: bad base64 in claims")?;
+    let claims: Claims = serde_json::from_slice(&data)
+        .context("malformed secret key: bad claims")?;
+    Ok(claims.issuer)
+}
+
+/// CRC-16/XMODEM checksum used to spread instances over DNS buckets
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for byte in data {
+        crc ^= u16::from(*byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Hostname of the cloud instance
+pub fn hostname(org: &str, instance: &str, secret_key: &str)
+    -> anyhow::Result<String>
+{
+    let zone = dns_zone(secret_key)?;
+    let bucket = crc16(format!("{}/{}", org, instance).as_bytes()) % 100;
+    Ok(format!("{}--{}.c-{:02}.i.{}",
+               instance, org, bucket, zone).to_lowercase())
+}
+
+#[test]
+fn instance_name() {
+    assert_eq!(parse_instance_name("org1/inst-2").unwrap(),
+               ("org1", "inst-2"));
+    assert!(parse_instance_name("inst").is_err());
+    assert!(parse_instance_name("/inst").is_err());
+    assert!(parse_instance_name("org/").is_err());
+    assert!(parse_instance_name("org/a/b").is_err());
+    assert!(parse_instance_name("org/a_b").is_err());
+}
+
+#[test]
+fn cloud_hostname() {
+    assert_eq!(crc16(b"123456789"), 0x31c3);
+    let claims = base64::encode_config(
+        r#"{"iss":"aws.edgedb.cloud"}"#, base64::URL_SAFE_NO_PAD);
+    let key = format!("header.{}.signature", claims);
+    let host = hostname("Org", "inst", &key).unwrap();
+    let bucket = crc16(b"Org/inst") % 100;
+    assert_eq!(host, format!("inst--org.c-{:02}.i.aws.edgedb.cloud", bucket));
+    assert!(hostname("org", "inst", "garbage").is_err());
+}
@@ -1,10 +1,19 @@
 //! Credentials file handling routines
 use std::default::Default;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use async_std::fs;
+use async_std::io::WriteExt;
+use rand::{thread_rng, Rng};
 use serde::{Serialize, Deserialize};
 
 
 /// A structure that represents contents of the credentials file
-#[derive(Serialize, Deserialize, Debug)]
+///
+/// Note: `Debug` implementation doesn't show the password and secret key.
+#[derive(Serialize, Deserialize)]
 #[non_exhaustive]
 pub struct Credentials {
     #[serde(default, skip_serializing_if="Option::is_none")]
@@ -15,6 +24,8 @@ pub struct Credentials {
     #[serde(default, skip_serializing_if="Option::is_none")]
     pub password: Option<String>,
     #[serde(default, skip_serializing_if="Option::is_none")]
+    pub secret_key: Option<String>,
+    #[serde(default, skip_serializing_if="Option::is_none")]
     pub database: Option<String>,
 }
 
@@ -22,6 +33,39 @@ fn default_port() -> u16 {
     5656
 }
 
+/// Directory where the command-line tool stores credentials of instances
+///
+/// This is `~/.edgedb/credentials`.
+pub fn credentials_dir() -> anyhow::Result<PathBuf> {
+    let home = if cfg!(windows) {
+        std::env::var_os("USERPROFILE")
+    } else {
+        std::env::var_os("HOME")
+    };
+    let home = home.filter(|h| !h.is_empty())
+        .context("cannot determine home directory")?;
+    Ok(Path::new(&home).join(".edgedb").join("credentials"))
+}
+
+/// Path to the credentials file of the local instance `name`
+pub fn instance_file(name: &str) -> anyhow::Result<PathBuf> {
+    instance_file_in(&credentials_dir()?, name)
+}
+
+fn instance_file_in(dir: &Path, name: &str) -> anyhow::Result<PathBuf> {
+    if name.contains('/') {
+        anyhow::bail!("{:?} looks like a cloud instance name, \
+                       cloud instances are not supported yet", name);
+    }
+    if name.is_empty() || !name.chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        anyhow::bail!("invalid instance name {:?}: only letters, digits, \
+                       underscores and dashes are allowed", name);
+    }
+    Ok(dir.join(format!("{}.json", name)))
+}
+
 impl Default for Credentials {
     fn default() -> Credentials {
         Credentials {
@@ -29,7 +73,121 @@ impl Default for Credentials {
             port: 5656,
             user: "edgedb".into(),
             password: None,
+            secret_key: None,
             database: None,
         }
     }
 }
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("user", &self.user)
+            .field("password", &self.password.as_ref().map(|_| "<hidden>"))
+            .field("secret_key",
+                   &self.secret_key.as_ref().map(|_| "<hidden>"))
+            .field("database", &self.database)
+            .finish()
+    }
+}
+
+impl Credentials {
+    /// Write credentials file
+    ///
+    /// File is written atomically: data is stored into a new temporary file
+    /// with a unique name in the same directory, which is then renamed over
+    /// the target path. On unix the file is created with `0600`
+    /// permissions, as it may contain a password.
+    pub async fn write(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        self._write(path).await.with_context(|| {
+            format!("cannot write credentials file {}", path.display())
+        })
+    }
+    async fn _write(&self, path: &Path) -> anyhow::Result<()> {
+        let data = serde_json::to_vec_pretty(self)?;
+        let file_name = path.file_name()
+            .context("path has no file name")?;
+        let mut tmp_name = std::ffi::OsString::from(".");
+        tmp_name.push(file_name);
+        tmp_name.push(format!(".{}.{:08x}.tmp",
+                              std::process::id(), thread_rng().gen::<u32>()));
+        let tmp_path = path.with_file_name(tmp_name);
+
+        let mut options = fs::OpenOptions::new();
+        // never reuse an existing file, as permissions only apply to a
+        // newly created one
+        options.write(true).create_new(true);
+        #[cfg(unix)] {
+            use async_std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&tmp_path).await?;
+        let result = async {
+            file.write_all(&data).await?;
+            file.sync_all().await?;
+            drop(file);
+            fs::rename(&tmp_path, path).await
+        }.await;
+        if result.is_err() {
+            fs::remove_file(&tmp_path).await.ok();
+        }
+        Ok(result?)
+    }
+}
+
+#[test]
+fn write() {
+    let dir = std::env::temp_dir()
+        .join(format!("edgedb-credentials-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("inst1.json");
+    let creds = Credentials {
+        host: Some("example.com".into()),
+        password: Some("xyz".into()),
+        .. Default::default()
+    };
+    async_std::task::block_on(creds.write(&path)).unwrap();
+    let read: Credentials = serde_json::from_slice(
+        &std::fs::read(&path).unwrap()).unwrap();
+    assert_eq!(read.host.as_deref(), Some("example.com"));
+    assert_eq!(read.password.as_deref(), Some("xyz"));
+    #[cfg(unix)] {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    // a file left by someone else doesn't affect the write
+    std::fs::write(dir.join(".inst1.json.tmp"), "").unwrap();
+    async_std::task::block_on(creds.write(&path)).unwrap();
+    let mut names = std::fs::read_dir(&dir).unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec![".inst1.json.tmp", "inst1.json"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn debug_hides_password() {
+    let creds = Credentials {
+        password: Some("lZTBy1RVCfOpBAOwSCwIyBIR".into()),
+        .. Default::default()
+    };
+    let debug = format!("{:?}", creds);
+    assert!(!debug.contains("lZTBy1RVCfOpBAOwSCwIyBIR"));
+    assert!(debug.contains("<hidden>"));
+}
+
+#[test]
+fn instance_path() {
+    let dir = Path::new("/home/user/.edgedb/credentials");
+    assert_eq!(instance_file_in(dir, "my_inst-1").unwrap(),
+               dir.join("my_inst-1.json"));
+    assert!(instance_file_in(dir, "").is_err());
+    assert!(instance_file_in(dir, "../x").is_err());
+    let err = instance_file_in(dir, "org/inst").unwrap_err();
+    assert!(err.to_string().contains("cloud instance"));
+}
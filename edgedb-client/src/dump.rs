@@ -0,0 +1,530 @@
+//! Dumping databases into files and restoring them
+//!
+//! Dump files use the same format as the official command-line tool, so
+//! they can be restored with either. The whole instance can be dumped into
+//! a directory using [`dump_all`] (and restored using [`restore_all`]),
+//! which writes:
+//!
+//! * `init.edgeql` -- a script that recreates roles and system config
+//! * `<database>.dump` -- a dump of every database, name is url-encoded
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use async_std::fs;
+use async_std::io::prelude::{ReadExt, WriteExt};
+use async_std::stream::{Stream, StreamExt};
+use async_std::task;
+use bytes::Bytes;
+use futures_io::{AsyncRead, AsyncWrite};
+use ring::digest::{digest, SHA1_FOR_LEGACY_USE_ONLY};
+
+use edgedb_protocol::client_message::{ClientMessage, Dump};
+use edgedb_protocol::client_message::{Restore, RestoreBlock};
+use edgedb_protocol::server_message::ServerMessage;
+use edgedb_protocol::value::Value;
+
+use crate::builder::Builder;
+use crate::client::{Connection, Sequence};
+
+
+/// Signature at the start of every dump file
+pub const MAGIC: &[u8] = b"\xFF\xD8\x00\x00\xD8EDGEDB\x00DUMP\x00";
+/// Version of the dump file format written by this library
+pub const FORMAT_VERSION: i64 = 1;
+/// Name of the script recreating roles and config in the `--all` layout
+pub const INIT_FILE: &str = "init.edgeql";
+
+/// Name of the dump file for a database in the `--all` layout
+pub fn dump_file_name(database: &str) -> String {
+    let name = url::form_urlencoded::byte_serialize(database.as_bytes())
+        .collect::<String>();
+    format!("{}.dump", name)
+}
+
+async fn write_packet<W>(output: &mut W, kind: u8, data: &[u8])
+    -> anyhow::Result<()>
+    where W: AsyncWrite + Unpin,
+{
+    let hash = digest(&SHA1_FOR_LEGACY_USE_ONLY, data);
+    output.write_all(&[kind]).await?;
+    output.write_all(hash.as_ref()).await?;
+    output.write_all(&(data.len() as u32).to_be_bytes()).await?;
+    output.write_all(data).await?;
+    Ok(())
+}
+
+/// Dump of a database received from the server block by block
+///
+/// Returned by [`Connection::dump_blocks`]. The dump is complete when
+/// [`next_block`](DumpBlocks::next_block) returns `None`; dropping it
+/// earlier leaves the connection unusable.
+pub struct DumpBlocks<'a> {
+    seq: Sequence<'a>,
+    header: Bytes,
+    done: bool,
+}
+
+impl Connection {
+    /// Dump the database this connection is connected to
+    ///
+    /// Output is written in the dump file format, starting with
+    /// [`MAGIC`] and format version.
+    pub async fn dump<W>(&mut self, mut output: W) -> anyhow::Result<()>
+        where W: AsyncWrite + Unpin,
+    {
+        let mut dump = self.dump_blocks().await?;
+        output.write_all(MAGIC).await?;
+        output.write_all(&FORMAT_VERSION.to_be_bytes()).await?;
+        write_packet(&mut output, b'H', dump.header()).await?;
+        while let Some(block) = dump.next_block().await? {
+            write_packet(&mut output, b'D', &block).await?;
+        }
+        output.flush().await?;
+        Ok(())
+    }
+    /// Start dumping the database this connection is connected to
+    ///
+    /// Unlike [`dump`](Connection::dump) this doesn't use the dump file
+    /// format, but returns the header and data blocks as sent by the
+    /// server, so dumps can be stored in any way (and restored using
+    /// [`restore_blocks`](Connection::restore_blocks)). Blocks are read
+    /// from the network one by one, so memory usage doesn't depend on the
+    /// size of the database:
+    ///
+    /// ```rust,ignore
+    /// let mut dump = conn.dump_blocks().await?;
+    /// storage.put_header(dump.header()).await?;
+    /// while let Some(block) = dump.next_block().await? {
+    ///     storage.put_block(block).await?;
+    /// }
+    /// ```
+    pub async fn dump_blocks(&mut self) -> anyhow::Result<DumpBlocks<'_>> {
+        let mut seq = self.start_sequence().await?;
+        seq.send_messages(&[
+            ClientMessage::Dump(Dump { headers: HashMap::new() }),
+            ClientMessage::Sync,
+        ]).await?;
+        let header = match seq.message().await? {
+            ServerMessage::DumpHeader(packet) => packet.data,
+            ServerMessage::ErrorResponse(err) => {
+                seq.expect_ready().await?;
+                return Err(anyhow::anyhow!(err));
+            }
+            msg => anyhow::bail!("unexpected message {:?}", msg),
+        };
+        Ok(DumpBlocks { seq, header, done: false })
+    }
+}
+
+impl DumpBlocks<'_> {
+    /// Header of the dump: schema and the list of blocks
+    pub fn header(&self) -> &Bytes {
+        &self.header
+    }
+    /// Receive next data block, returns `None` when dump is complete
+    pub async fn next_block(&mut self) -> anyhow::Result<Option<Bytes>> {
+        if self.done {
+            return Ok(None);
+        }
+        match self.seq.message().await? {
+            ServerMessage::DumpBlock(packet) => Ok(Some(packet.data)),
+            ServerMessage::CommandComplete(..) => {
+                self.seq.expect_ready().await?;
+                self.done = true;
+                Ok(None)
+            }
+            ServerMessage::ErrorResponse(err) => {
+                self.seq.expect_ready().await?;
+                self.done = true;
+                Err(anyhow::anyhow!(err))
+            }
+            msg => anyhow::bail!("unexpected message {:?}", msg),
+        }
+    }
+}
+
+/// Options for [`restore_all`]
+#[derive(Debug, Clone)]
+pub struct RestoreAllOptions {
+    overwrite: bool,
+    jobs: usize,
+}
+
+impl Default for RestoreAllOptions {
+    fn default() -> RestoreAllOptions {
+        RestoreAllOptions {
+            overwrite: false,
+            jobs: 4,
+        }
+    }
+}
+
+impl RestoreAllOptions {
+    /// Drop and recreate databases that already exist
+    ///
+    /// By default restore fails if any of the databases exists, except
+    /// the one the builder is connected to (which must be empty).
+    pub fn with_overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+    /// Maximum number of databases restored concurrently
+    ///
+    /// Default is 4. Values less than 1 are treated as 1.
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+    pub fn overwrite(&self) -> bool {
+        self.overwrite
+    }
+    pub fn jobs(&self) -> usize {
+        self.jobs
+    }
+}
+
+/// Read next packet, returns `None` at the end of file
+async fn read_packet<R>(input: &mut R) -> anyhow::Result<Option<(u8, Bytes)>>
+    where R: AsyncRead + Unpin,
+{
+    let mut head = [0u8; 25];
+    match input.read_exact(&mut head[..1]).await {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    input.read_exact(&mut head[1..]).await
+        .context("dump file is truncated")?;
+    let len = u32::from_be_bytes([head[21], head[22], head[23], head[24]]);
+    let mut data = vec![0u8; len as usize];
+    input.read_exact(&mut data).await.context("dump file is truncated")?;
+    let hash = digest(&SHA1_FOR_LEGACY_USE_ONLY, &data);
+    if hash.as_ref() != &head[1..21] {
+        anyhow::bail!("dump file is corrupted: checksum mismatch");
+    }
+    Ok(Some((head[0], data.into())))
+}
+
+impl Connection {
+    /// Restore a dump into the database this connection is connected to
+    ///
+    /// Database must be empty.
+    pub async fn restore<R>(&mut self, mut input: R) -> anyhow::Result<()>
+        where R: AsyncRead + Unpin,
+    {
+        let mut magic = [0u8; MAGIC.len() + 8];
+        input.read_exact(&mut magic).await
+            .context("dump file is truncated")?;
+        if &magic[..MAGIC.len()] != MAGIC {
+            anyhow::bail!("not a dump file");
+        }
+        let mut version = [0u8; 8];
+        version.copy_from_slice(&magic[MAGIC.len()..]);
+        let version = i64::from_be_bytes(version);
+        if version > FORMAT_VERSION {
+            anyhow::bail!("unsupported dump format version {}", version);
+        }
+        let header = match read_packet(&mut input).await? {
+            Some((b'H', data)) => data,
+            _ => anyhow::bail!("dump file has no header"),
+        };
+
+        let mut seq = self.start_restore(header).await?;
+        while let Some((kind, data)) = read_packet(&mut input).await? {
+            if kind != b'D' {
+                anyhow::bail!("unexpected packet {:?} in dump file",
+                              kind as char);
+            }
+            seq.send_messages(&[
+                ClientMessage::RestoreBlock(RestoreBlock { data }),
+            ]).await?;
+        }
+        finish_restore(seq).await
+    }
+    /// Restore a dump from the header and data blocks
+    ///
+    /// Header and blocks are the ones returned by
+    /// [`dump_blocks`](Connection::dump_blocks), in the same order.
+    /// Blocks are sent to the server as soon as `blocks` yields them.
+    /// Database must be empty.
+    pub async fn restore_blocks<S>(&mut self, header: Bytes, mut blocks: S)
+        -> anyhow::Result<()>
+        where S: Stream<Item=anyhow::Result<Bytes>> + Unpin,
+    {
+        let mut seq = self.start_restore(header).await?;
+        while let Some(data) = blocks.next().await {
+            seq.send_messages(&[
+                ClientMessage::RestoreBlock(RestoreBlock { data: data? }),
+            ]).await?;
+        }
+        finish_restore(seq).await
+    }
+    async fn start_restore(&mut self, header: Bytes)
+        -> anyhow::Result<Sequence<'_>>
+    {
+        let mut seq = self.start_sequence().await?;
+        seq.send_messages(&[
+            ClientMessage::Restore(Restore {
+                headers: HashMap::new(),
+                jobs: 1,
+                data: header,
+            }),
+        ]).await?;
+        match seq.message().await? {
+            ServerMessage::RestoreReady(..) => Ok(seq),
+            ServerMessage::ErrorResponse(err) => {
+                seq.expect_ready().await?;
+                Err(anyhow::anyhow!(err))
+            }
+            msg => anyhow::bail!("unexpected message {:?}", msg),
+        }
+    }
+}
+
+async fn finish_restore(mut seq: Sequence<'_>) -> anyhow::Result<()> {
+    seq.send_messages(&[ClientMessage::RestoreEof]).await?;
+    match seq.message().await? {
+        ServerMessage::CommandComplete(..) => {
+            seq.expect_ready().await?;
+            Ok(())
+        }
+        ServerMessage::ErrorResponse(err) => {
+            seq.expect_ready().await?;
+            Err(anyhow::anyhow!(err))
+        }
+        msg => anyhow::bail!("unexpected message {:?}", msg),
+    }
+}
+
+fn quote_name(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+/// Find dump files in the `--all` layout, returns database names
+async fn read_layout(dir: &Path) -> anyhow::Result<Vec<String>> {
+    use async_std::prelude::StreamExt;
+
+    let mut databases = Vec::new();
+    let mut entries = fs::read_dir(dir).await
+        .with_context(|| format!("cannot read {}", dir.display()))?;
+    while let Some(entry) = entries.next().await {
+        let name = entry?.file_name();
+        let name = match name.to_str().and_then(|n| n.strip_suffix(".dump")) {
+            Some(name) => name,
+            None => continue,
+        };
+        let database = url::form_urlencoded::parse(
+                format!("n={}", name).as_bytes())
+            .map(|(_, value)| value.into_owned())
+            .next().unwrap_or_default();
+        databases.push(database);
+    }
+    databases.sort();
+    Ok(databases)
+}
+
+/// Restore databases, roles and system config written by [`dump_all`]
+///
+/// Roles and config are restored first, then missing databases are
+/// created and dumps are restored using up to
+/// [`jobs`](RestoreAllOptions::with_jobs) concurrent connections. Returns
+/// names of the restored databases.
+pub async fn restore_all(builder: &Builder, dir: impl AsRef<Path>,
+                         options: &RestoreAllOptions)
+    -> anyhow::Result<Vec<String>>
+{
+    let dir = dir.as_ref();
+    let databases = read_layout(dir).await?;
+    let init = fs::read_to_string(dir.join(INIT_FILE)).await
+        .with_context(|| format!("cannot read {}", INIT_FILE))?;
+
+    let mut conn = builder.connect().await?;
+    let existing = list_databases(&mut conn).await?;
+    let admin_db = builder.get_database();
+    let conflicts = databases.iter()
+        .filter(|db| *db != admin_db && existing.contains(db))
+        .collect::<Vec<_>>();
+    if !conflicts.is_empty() && !options.overwrite {
+        anyhow::bail!("databases {:?} already exist; \
+                       use overwrite option to replace them", conflicts);
+    }
+    if !init.trim().is_empty() {
+        conn.execute(&init).await.context("cannot restore roles and config")?;
+    }
+    for database in &databases {
+        if database == admin_db {
+            continue;
+        }
+        if existing.contains(database) {
+            log::info!("Dropping database {:?}", database);
+            conn.execute(format!("DROP DATABASE {}", quote_name(database)))
+                .await?;
+        }
+        conn.execute(format!("CREATE DATABASE {}", quote_name(database)))
+            .await?;
+    }
+
+    let queue = Arc::new(Mutex::new(databases.clone()));
+    let workers = (0..options.jobs.min(databases.len())).map(|_| {
+        let queue = queue.clone();
+        let builder = builder.clone();
+        let dir = dir.to_owned();
+        task::spawn(async move {
+            loop {
+                let database = match queue.lock().expect("queue").pop() {
+                    Some(database) => database,
+                    None => return Ok(()),
+                };
+                log::info!("Restoring database {:?}", database);
+                let path = dir.join(dump_file_name(&database));
+                let result: anyhow::Result<()> = async {
+                    let file = fs::File::open(&path).await?;
+                    let mut conn = builder.with_database(&database)
+                        .connect().await?;
+                    conn.restore(async_std::io::BufReader::new(file)).await
+                }.await;
+                result.with_context(|| {
+                    format!("cannot restore database {:?}", database)
+                })?;
+            }
+        })
+    }).collect::<Vec<_>>();
+    let mut errors = Vec::new();
+    for worker in workers {
+        if let Err(e) = worker.await {
+            errors.push(e);
+        }
+    }
+    match errors.pop() {
+        Some(e) => {
+            for other in errors {
+                log::error!("{:#}", other);
+            }
+            Err(e)
+        }
+        None => Ok(databases),
+    }
+}
+
+/// Write file atomically by writing a temporary file and renaming it
+async fn dump_file(conn: &mut Connection, path: &Path) -> anyhow::Result<()>
+{
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    let mut file = fs::File::create(&tmp_path).await?;
+    conn.dump(&mut file).await?;
+    file.sync_all().await?;
+    drop(file);
+    fs::rename(&tmp_path, path).await?;
+    Ok(())
+}
+
+/// List non-system databases of the instance
+pub async fn list_databases(conn: &mut Connection)
+    -> anyhow::Result<Vec<String>>
+{
+    use async_std::prelude::StreamExt;
+
+    let mut query = conn.query::<String>("SELECT sys::Database.name",
+                                         &Value::empty_tuple()).await?;
+    let mut names = Vec::new();
+    while let Some(name) = query.next().await {
+        let name = name?;
+        if !name.starts_with("__") {
+            names.push(name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Dump all databases, roles and system config into a directory
+///
+/// Directory is created if it doesn't exist. Connections are made using
+/// `builder`, with database overridden for each dumped database. Returns
+/// names of the dumped databases.
+pub async fn dump_all(builder: &Builder, dir: impl AsRef<Path>)
+    -> anyhow::Result<Vec<String>>
+{
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir).await
+        .with_context(|| format!("cannot create {}", dir.display()))?;
+    let mut conn = builder.connect().await?;
+    let config: String = conn.query_row("DESCRIBE SYSTEM CONFIG",
+                                        &Value::empty_tuple()).await?;
+    let roles: String = conn.query_row("DESCRIBE ROLES",
+                                       &Value::empty_tuple()).await?;
+    let init = format!("{}\n{}\n", roles.trim_end(), config.trim_end());
+    fs::write(dir.join(INIT_FILE), init).await
+        .with_context(|| format!("cannot write {}", INIT_FILE))?;
+
+    let databases = list_databases(&mut conn).await?;
+    for database in &databases {
+        log::info!("Dumping database {:?}", database);
+        let mut conn = builder.with_database(database).connect().await?;
+        let path = dir.join(dump_file_name(database));
+        dump_file(&mut conn, &path).await
+            .with_context(|| format!("cannot dump database {:?}", database))?;
+    }
+    Ok(databases)
+}
+
+#[test]
+fn file_name() {
+    assert_eq!(dump_file_name("edgedb"), "edgedb.dump");
+    assert_eq!(dump_file_name("my db/1"), "my+db%2F1.dump");
+}
+
+#[test]
+fn restore_options() {
+    let opts = RestoreAllOptions::default().with_jobs(0).with_overwrite(true);
+    assert_eq!(opts.jobs(), 1);
+    assert!(opts.overwrite());
+}
+
+#[test]
+fn layout() {
+    let dir = std::env::temp_dir()
+        .join(format!("edgedb-dump-layout-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for name in &["edgedb", "my db/1"] {
+        std::fs::write(dir.join(dump_file_name(name)), b"").unwrap();
+    }
+    std::fs::write(dir.join(INIT_FILE), b"").unwrap();
+    let databases = task::block_on(read_layout(&dir)).unwrap();
+    assert_eq!(databases, vec!["edgedb", "my db/1"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn packet() {
+    let mut buf = Vec::new();
+    async_std::task::block_on(write_packet(&mut buf, b'D', b"abc")).unwrap();
+    assert_eq!(buf[0], b'D');
+    assert_eq!(&buf[1..21], &b"\xa9\x99\x3e\x36\x47\x06\x81\x6a\xba\x3e\
+                               \x25\x71\x78\x50\xc2\x6c\x9c\xd0\xd8\x9d"[..]);
+    assert_eq!(&buf[21..25], &[0, 0, 0, 3]);
+    assert_eq!(&buf[25..], b"abc");
+}
+
+#[test]
+fn read_write_packet() {
+    let mut buf = Vec::new();
+    task::block_on(async {
+        write_packet(&mut buf, b'H', b"header").await.unwrap();
+        write_packet(&mut buf, b'D', b"block").await.unwrap();
+        let mut input = &buf[..];
+        let (kind, data) = read_packet(&mut input).await.unwrap().unwrap();
+        assert_eq!((kind, &data[..]), (b'H', &b"header"[..]));
+        let (kind, data) = read_packet(&mut input).await.unwrap().unwrap();
+        assert_eq!((kind, &data[..]), (b'D', &b"block"[..]));
+        assert!(read_packet(&mut input).await.unwrap().is_none());
+
+        buf[30] ^= 1;
+        assert!(read_packet(&mut &buf[..]).await.is_err());
+    });
+}
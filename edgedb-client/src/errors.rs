@@ -1,7 +1,14 @@
 //! Errors that can be returned by a client
 use std::fmt;
+use std::ops::Range;
+use std::str;
+
 use bytes::Bytes;
 
+use edgedb_protocol::error_response::FIELD_POSITION_START;
+use edgedb_protocol::error_response::FIELD_POSITION_END;
+use edgedb_protocol::server_message::ErrorResponse;
+
 /// Request has timed out or interrupted in the middle, should reconnect
 #[derive(Debug, thiserror::Error)]
 #[error("Connection is inconsistent state. Please reconnect.")]
@@ -12,6 +19,64 @@ pub struct ConnectionDirty;
 #[error("Password required for the specified user/host")]
 pub struct PasswordRequired;
 
+/// Builder configuration is invalid
+///
+/// Contains all the errors found by [`Builder::validate`].
+///
+/// [`Builder::validate`]: crate::Builder::validate
+#[derive(Debug, thiserror::Error)]
+#[error("invalid connection configuration: {}", problems.join("; "))]
+pub struct InvalidConfiguration {
+    pub problems: Vec<String>,
+}
+
+/// Connection is requested from the pool that is closed
+#[derive(Debug, thiserror::Error)]
+#[error("connection pool is closed")]
+pub struct PoolClosed;
+
+/// No connection became available within the pool's acquire timeout
+///
+/// See [`PoolOptions::with_acquire_timeout`].
+///
+/// [`PoolOptions::with_acquire_timeout`]:
+///     crate::client::PoolOptions::with_acquire_timeout
+#[derive(Debug, thiserror::Error)]
+#[error("timed out waiting for a connection from the pool")]
+pub struct AcquireTimeout;
+
+/// Transaction is started while connection is already in a transaction
+///
+/// This usually means that `transaction()` was called from inside of the
+/// body of another transaction. Use savepoints for nested blocks instead.
+#[derive(Debug, thiserror::Error)]
+#[error("transaction() called inside of a transaction block; \
+         use savepoints for nested transactions")]
+pub struct NestedTransactionError;
+
+/// Transaction control statement is passed to a query method
+///
+/// Statements like `START TRANSACTION` or `COMMIT` can't be used with
+/// `query()` and similar methods, use `transaction()` instead.
+#[derive(Debug, thiserror::Error)]
+#[error("transaction control statements can't be used in queries; \
+         use transaction() method instead")]
+pub struct TransactionControlStatement;
+
+/// Column of the query result can't be exported
+///
+/// Returned by [`Connection::copy_csv`] and `Connection::query_arrow`
+/// for columns that have no counterpart in the output format, such as
+/// links and arrays.
+///
+/// [`Connection::copy_csv`]: crate::client::Connection::copy_csv
+#[derive(Debug, thiserror::Error)]
+#[error("cannot export column {column:?} of type {type_name}")]
+pub struct UnsupportedColumn {
+    pub column: String,
+    pub type_name: String,
+}
+
 /// This error returned when trying to query a DDL statement
 #[derive(Debug)]
 pub struct NoResultExpected {
@@ -26,3 +91,129 @@ impl fmt::Display for NoResultExpected {
             String::from_utf8_lossy(&self.completion_message[..]))
     }
 }
+
+/// One of the statements executed by `execute_many` or `insert_many`, or
+/// one of the queries of a [`Pipeline`] failed
+///
+/// Statements before `index` have been executed successfully, but whether
+/// their effects persist depends on the method (see its documentation).
+///
+/// [`Pipeline`]: crate::client::Pipeline
+#[derive(Debug, thiserror::Error)]
+#[error("statement #{index} failed: {error}")]
+pub struct ExecuteManyError {
+    pub index: usize,
+    pub error: ErrorResponse,
+}
+
+/// Query text attached as a context to the query errors
+///
+/// Only attached when enabled by [`Builder::query_source_in_errors`].
+/// The original [`ErrorResponse`] is still available via
+/// `anyhow::Error::downcast_ref`.
+///
+/// [`Builder::query_source_in_errors`]:
+///     crate::Builder::query_source_in_errors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuerySource {
+    /// Query text, truncated to the configured length
+    pub text: String,
+    /// Whether `text` is truncated
+    pub truncated: bool,
+    /// Byte range of the error in the original query, if server reported
+    /// the position. Note: range might be outside of the `text` if the
+    /// latter is truncated.
+    pub span: Option<Range<usize>>,
+}
+
+fn position(err: &ErrorResponse, field: u16) -> Option<usize> {
+    str::from_utf8(err.attributes.get(&field)?).ok()?.parse().ok()
+}
+
+/// Convert character offset into byte offset in `text`
+fn byte_offset(text: &str, chars: usize) -> Option<usize> {
+    text.char_indices().map(|(idx, _)| idx)
+        .chain(Some(text.len()))
+        .nth(chars)
+}
+
+impl QuerySource {
+    /// Server reports positions in characters of the statement text sent,
+    /// `prefix` is the number of characters added in front of the query
+    pub(crate) fn new(query: &str, err: &ErrorResponse,
+                      limit: usize, prefix: usize)
+        -> QuerySource
+    {
+        let span = (|| {
+            let start = position(err, FIELD_POSITION_START)?
+                .checked_sub(prefix)?;
+            let end = position(err, FIELD_POSITION_END)?
+                .checked_sub(prefix)?.max(start);
+            Some(byte_offset(query, start)?..byte_offset(query, end)?)
+        })();
+        let mut len = limit.min(query.len());
+        while !query.is_char_boundary(len) {
+            len -= 1;
+        }
+        QuerySource {
+            text: query[..len].to_owned(),
+            truncated: len < query.len(),
+            span,
+        }
+    }
+    /// The line of the query where error is, or the first line
+    pub fn snippet(&self) -> &str {
+        let start = self.span.as_ref()
+            .map(|s| s.start.min(self.text.len()))
+            .unwrap_or(0);
+        let line_start = self.text[..start].rfind('\n')
+            .map(|p| p + 1).unwrap_or(0);
+        let line_end = self.text[start..].find('\n')
+            .map(|p| start + p).unwrap_or(self.text.len());
+        self.text[line_start..line_end].trim()
+    }
+}
+
+impl std::error::Error for QuerySource {}
+
+impl fmt::Display for QuerySource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "error in query `{}", self.snippet())?;
+        if self.snippet().len() < self.text.trim().len() || self.truncated {
+            write!(f, " ...")?;
+        }
+        write!(f, "`")
+    }
+}
+
+#[test]
+fn query_source() {
+    use std::collections::HashMap;
+    use edgedb_protocol::server_message::ErrorSeverity;
+
+    let mut attributes = HashMap::new();
+    attributes.insert(FIELD_POSITION_START, Bytes::from("19"));
+    attributes.insert(FIELD_POSITION_END, Bytes::from("22"));
+    let err = ErrorResponse {
+        severity: ErrorSeverity::Error,
+        code: 0x_04_03_00_00,
+        message: "object type 'Usr' does not exist".into(),
+        attributes,
+    };
+    let query = "SELECT 'ü';\nSELECT Usr { name }";
+    let src = QuerySource::new(query, &err, 1024, 0);
+    assert_eq!(src.span, Some(20..23));
+    assert_eq!(&query[src.span.clone().unwrap()], "Usr");
+    assert_eq!(src.snippet(), "SELECT Usr { name }");
+    assert!(!src.truncated);
+    assert_eq!(src.to_string(), "error in query `SELECT Usr { name } ...`");
+
+    // position is shifted by the query tag
+    let src = QuerySource::new("SELECT Usr", &err, 1024, 12);
+    assert_eq!(src.span, Some(7..10));
+
+    let src = QuerySource::new(query, &err, 9, 0);
+    assert_eq!(src.text, "SELECT '");
+    assert!(src.truncated);
+    assert_eq!(src.snippet(), "SELECT '");
+}
@@ -1,4 +1,4 @@
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ProtocolVersion {
     pub(crate) major_ver: u16,
     pub(crate) minor_ver: u16,
@@ -11,7 +11,13 @@ impl ProtocolVersion {
             minor_ver: 9,
         }
     }
-    fn ver(&self) -> (u16, u16) {
+    pub fn major_ver(&self) -> u16 {
+        self.major_ver
+    }
+    pub fn minor_ver(&self) -> u16 {
+        self.minor_ver
+    }
+    pub(crate) fn ver(&self) -> (u16, u16) {
         (self.major_ver, self.minor_ver)
     }
     pub fn supports_inline_typenames(&self) -> bool {
@@ -1,10 +1,27 @@
 mod builder;
+mod cloud;
 mod sealed;
 mod features;
+mod statement;
+mod resolve;
 pub mod errors;
 pub mod reader;
 pub mod client;
 pub mod server_params;
+pub mod messages;
+pub mod cancel;
+pub mod server_info;
+pub mod status;
+pub mod analyze;
+pub mod auth;
+#[cfg(feature="credentials")]
 pub mod credentials;
+pub mod transaction;
+pub mod retry;
+pub mod transport;
+pub mod blocking;
+pub mod dump;
+pub mod metadata_cache;
 
-pub use builder::Builder;
+pub use builder::{Builder, ConfigProblem, Severity};
+pub use builder::doctor;
@@ -0,0 +1,99 @@
+//! Log messages sent by the server
+//!
+//! Server may send `LogMessage` frames at any point of a request, for
+//! example deprecation warnings. By default they are written to the `log`
+//! crate with target `edgedb::server`. Use
+//! [`on_log_message`] or [`subscribe_log_messages`] to handle them in the
+//! application. Warnings received during a query are also available via
+//! [`QueryResponse::warnings`].
+//!
+//! [`on_log_message`]: crate::client::Connection::on_log_message
+//! [`subscribe_log_messages`]:
+//!     crate::client::Connection::subscribe_log_messages
+//! [`QueryResponse::warnings`]: crate::reader::QueryResponse::warnings
+use async_std::channel::{unbounded, Receiver, Sender};
+
+use edgedb_protocol::server_message::{LogMessage, MessageSeverity};
+
+
+type Handler = Box<dyn Fn(&LogMessage) + Send + Sync>;
+
+/// Handlers and subscribers of server log messages
+#[derive(Default)]
+pub(crate) struct MessageWatch {
+    handlers: Vec<Handler>,
+    subscribers: Vec<Sender<LogMessage>>,
+    warnings: Vec<LogMessage>,
+}
+
+fn log_level(severity: MessageSeverity) -> log::Level {
+    match severity {
+        MessageSeverity::Debug => log::Level::Debug,
+        MessageSeverity::Info | MessageSeverity::Notice => log::Level::Info,
+        MessageSeverity::Warning => log::Level::Warn,
+        MessageSeverity::Unknown(_) => log::Level::Info,
+    }
+}
+
+impl MessageWatch {
+    pub fn add_handler(&mut self, handler: Handler) {
+        self.handlers.push(handler);
+    }
+    pub fn subscribe(&mut self) -> Receiver<LogMessage> {
+        let (tx, rx) = unbounded();
+        self.subscribers.push(tx);
+        rx
+    }
+    /// Warnings received since the start of the current request
+    pub fn warnings(&self) -> &[LogMessage] {
+        &self.warnings
+    }
+    pub fn clear_warnings(&mut self) {
+        self.warnings.clear();
+    }
+    /// Pass message to handlers, or log it if there are none
+    pub fn dispatch(&mut self, message: LogMessage) {
+        if message.severity == MessageSeverity::Warning {
+            self.warnings.push(message.clone());
+        }
+        self.subscribers.retain(|s| s.try_send(message.clone()).is_ok());
+        if self.handlers.is_empty() && self.subscribers.is_empty() {
+            log::log!(target: "edgedb::server", log_level(message.severity),
+                      "{} (code 0x{:08x})", message.text, message.code);
+        }
+        for handler in &self.handlers {
+            handler(&message);
+        }
+    }
+}
+
+#[test]
+fn dispatch() {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    let message = LogMessage {
+        severity: MessageSeverity::Warning,
+        code: 0xF0_00_00_00,
+        text: "deprecated".into(),
+        attributes: HashMap::new(),
+    };
+    let mut watch = MessageWatch::default();
+    watch.dispatch(message.clone());  // logged only
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen2 = seen.clone();
+    watch.add_handler(Box::new(move |m| {
+        seen2.lock().unwrap().push(m.text.clone())
+    }));
+    let rx = watch.subscribe();
+    watch.dispatch(message.clone());
+    assert_eq!(*seen.lock().unwrap(), vec![String::from("deprecated")]);
+    assert_eq!(rx.try_recv().unwrap(), message);
+    drop(rx);
+    watch.dispatch(message);
+    assert!(watch.subscribers.is_empty());
+    assert_eq!(seen.lock().unwrap().len(), 2);
+    assert_eq!(watch.warnings().len(), 3);
+    watch.clear_warnings();
+    assert!(watch.warnings().is_empty());
+}
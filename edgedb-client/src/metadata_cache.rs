@@ -0,0 +1,202 @@
+//! Offline cache of query descriptors
+//!
+//! The cache is a JSON file meant to be checked into the repository along
+//! with the code. It's filled by [`MetadataCache::prepare`] while the
+//! database is available and lets code generators and query checkers get
+//! type information for the queries in environments without a server
+//! (e.g. on CI).
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Context;
+use async_std::fs;
+use async_std::io::WriteExt;
+use rand::{thread_rng, Rng};
+use serde::{Serialize, Deserialize};
+
+use edgedb_protocol::server_message::CommandDataDescription;
+
+use crate::client::Connection;
+
+/// Version of the cache file format
+pub const CACHE_FORMAT_VERSION: u32 = 1;
+
+/// Query descriptors keyed by the query text
+///
+/// Descriptors are stored in the serialization format of
+/// [`CommandDataDescription::to_bytes`]. Entries are sorted by query text,
+/// so the file can be diffed reasonably well.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetadataCache {
+    version: u32,
+    queries: BTreeMap<String, String>,
+}
+
+impl MetadataCache {
+    /// Create an empty cache
+    pub fn new() -> MetadataCache {
+        MetadataCache {
+            version: CACHE_FORMAT_VERSION,
+            queries: BTreeMap::new(),
+        }
+    }
+    /// Describe every query in `queries` using the connection
+    ///
+    /// Returns a new cache that contains only these queries, so stale
+    /// entries are dropped when the cache is rebuilt.
+    pub async fn prepare<I, S>(conn: &mut Connection, queries: I)
+        -> anyhow::Result<MetadataCache>
+        where I: IntoIterator<Item=S>,
+              S: AsRef<str>,
+    {
+        let mut cache = MetadataCache::new();
+        for query in queries {
+            let query = query.as_ref();
+            let description = conn.query_describe(query).await
+                .with_context(|| {
+                    format!("cannot describe query {:?}", query)
+                })?;
+            cache.insert(query, &description)?;
+        }
+        Ok(cache)
+    }
+    /// Read the cache file
+    pub async fn read(path: impl AsRef<Path>)
+        -> anyhow::Result<MetadataCache>
+    {
+        let path = path.as_ref();
+        let data = fs::read(path).await
+            .with_context(|| {
+                format!("cannot read metadata cache {}", path.display())
+            })?;
+        let cache: MetadataCache = serde_json::from_slice(&data)
+            .with_context(|| {
+                format!("malformed metadata cache {}", path.display())
+            })?;
+        anyhow::ensure!(cache.version == CACHE_FORMAT_VERSION,
+            "metadata cache {} has unsupported version {}, \
+             regenerate it with the current tools",
+            path.display(), cache.version);
+        Ok(cache)
+    }
+    /// Write the cache file
+    ///
+    /// File is written atomically: data is stored into a new, uniquely
+    /// named temporary file in the same directory, which is then renamed
+    /// over the target path.
+    pub async fn write(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        self._write(path).await.with_context(|| {
+            format!("cannot write metadata cache {}", path.display())
+        })
+    }
+    async fn _write(&self, path: &Path) -> anyhow::Result<()> {
+        let mut data = serde_json::to_vec_pretty(self)?;
+        data.push(b'\n');
+        let file_name = path.file_name()
+            .context("path has no file name")?;
+        let mut tmp_name = std::ffi::OsString::from(".");
+        tmp_name.push(file_name);
+        tmp_name.push(format!(".{}.{:08x}.tmp",
+                              std::process::id(), thread_rng().gen::<u32>()));
+        let tmp_path = path.with_file_name(tmp_name);
+
+        // never write through an existing file (e.g. a symlink)
+        let mut file = fs::OpenOptions::new()
+            .write(true).create_new(true)
+            .open(&tmp_path).await?;
+        let result = async {
+            file.write_all(&data).await?;
+            file.sync_all().await?;
+            drop(file);
+            fs::rename(&tmp_path, path).await
+        }.await;
+        if result.is_err() {
+            fs::remove_file(&tmp_path).await.ok();
+        }
+        Ok(result?)
+    }
+    /// Add or replace the description of the query
+    pub fn insert(&mut self, query: &str,
+                  description: &CommandDataDescription)
+        -> anyhow::Result<()>
+    {
+        let data = description.to_bytes()?;
+        self.queries.insert(query.into(), base64::encode(&data[..]));
+        Ok(())
+    }
+    /// Get the description of the query
+    ///
+    /// Returns `None` if the query is not in the cache. Note that query text
+    /// must match exactly, including whitespace.
+    pub fn get(&self, query: &str)
+        -> anyhow::Result<Option<CommandDataDescription>>
+    {
+        let data = match self.queries.get(query) {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let data = base64::decode(data)
+            .with_context(|| format!("bad cache entry for {:?}", query))?;
+        let description = CommandDataDescription::from_bytes(data.into())
+            .with_context(|| format!("bad cache entry for {:?}", query))?;
+        Ok(Some(description))
+    }
+    /// Remove the query from the cache, returns true if it was there
+    pub fn remove(&mut self, query: &str) -> bool {
+        self.queries.remove(query).is_some()
+    }
+    /// Iterate over cached queries in sorted order
+    pub fn queries(&self) -> impl Iterator<Item=&str> {
+        self.queries.keys().map(|q| &q[..])
+    }
+    pub fn len(&self) -> usize {
+        self.queries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+}
+
+impl Default for MetadataCache {
+    fn default() -> MetadataCache {
+        MetadataCache::new()
+    }
+}
+
+#[test]
+fn roundtrip() {
+    use std::collections::HashMap;
+    use bytes::Bytes;
+    use edgedb_protocol::client_message::Cardinality;
+    use edgedb_protocol::model::Uuid;
+
+    let description = CommandDataDescription {
+        headers: HashMap::new(),
+        result_cardinality: Cardinality::One,
+        input_typedesc_id: Uuid::from_u128(0xFF),
+        input_typedesc: Bytes::from_static(
+            b"\x04\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\xff\0\0"),
+        output_typedesc_id: Uuid::from_u128(0x105),
+        output_typedesc: Bytes::from_static(
+            b"\x02\0\0\0\0\0\0\0\0\0\0\0\0\0\0\x01\x05"),
+    };
+    let mut cache = MetadataCache::new();
+    cache.insert("SELECT 1", &description).unwrap();
+    assert_eq!(cache.get("SELECT 1").unwrap(), Some(description.clone()));
+    assert_eq!(cache.get("SELECT 2").unwrap(), None);
+
+    let dir = std::env::temp_dir()
+        .join(format!("edgedb-metadata-cache-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("queries.json");
+    async_std::task::block_on(cache.write(&path)).unwrap();
+    let read = async_std::task::block_on(MetadataCache::read(&path))
+        .unwrap();
+    assert_eq!(read, cache);
+    assert_eq!(read.queries().collect::<Vec<_>>(), vec!["SELECT 1"]);
+    assert_eq!(read.get("SELECT 1").unwrap(), Some(description));
+    // temporary file is renamed
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
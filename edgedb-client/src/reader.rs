@@ -5,32 +5,55 @@ use std::future::{Future};
 use std::marker::PhantomData;
 use std::pin::Pin;
 use std::sync::Arc;
-use std::slice;
 use std::task::{Poll, Context};
 
-use async_std::io::Read as AsyncRead;
 use async_std::stream::{Stream, StreamExt};
-use async_listen::ByteStream;
-use bytes::{Bytes, BytesMut, BufMut};
+use bytes::{Bytes, BytesMut};
+use futures_io::AsyncRead;
 use snafu::{Snafu, ResultExt, Backtrace};
 
+#[cfg(feature="unstable")]
+use edgedb_protocol::client_message::ClientMessage;
 use edgedb_protocol::server_message::{ServerMessage, ErrorResponse};
 use edgedb_protocol::server_message::{ReadyForCommand, TransactionState};
+use edgedb_protocol::server_message::LogMessage;
 use edgedb_protocol::errors::{DecodeError};
 use edgedb_protocol::queryable::{Queryable, Decoder};
 use edgedb_protocol::codec::Codec;
 use edgedb_protocol::value::Value;
 
+use crate::cancel::CancelHandle;
 use crate::client;
+use crate::errors::QuerySource;
+use crate::messages::MessageWatch;
+use crate::server_params::ParamWatch;
+use crate::transport::ReadHalf;
 
 
 const BUFFER_SIZE: usize = 8192;
 const MAX_BUFFER: usize = 1_048_576;
 
+/// Data read from the stream but not consumed yet
+///
+/// Buffer is followed by a zeroed (or already consumed) tail that the
+/// stream reads into. The tail is kept between reads, so the space is
+/// only initialized when the buffer grows rather than on every read.
+#[derive(Debug)]
+pub(crate) struct InputBuffer {
+    buf: BytesMut,
+    filled: usize,
+}
+
 pub struct Reader<'a> {
-    pub(crate) stream: &'a ByteStream,
-    pub(crate) buf: &'a mut BytesMut,
+    pub(crate) stream: &'a mut ReadHalf,
+    pub(crate) buf: &'a mut InputBuffer,
     pub(crate) transaction_state: &'a mut TransactionState,
+    pub(crate) params: &'a mut ParamWatch,
+    pub(crate) messages: &'a mut MessageWatch,
+    pub(crate) cancel: &'a CancelHandle,
+    pub(crate) shutdown: Option<&'a (dyn Fn() + Send + Sync)>,
+    pub(crate) peeked: Option<ServerMessage>,
+    pub(crate) timeout: Option<Pin<Box<dyn Future<Output=()> + Send>>>,
 }
 
 pub struct MessageFuture<'a, 'r: 'a> {
@@ -44,6 +67,18 @@ pub struct QueryResponse<'a, D> {
     pub(crate) error: Option<ErrorResponse>,
     pub(crate) buffer: Vec<Bytes>,
     pub(crate) decoder: D,
+    pub(crate) request: Option<String>,
+    /// End of stream or an error was returned
+    pub(crate) done: bool,
+}
+
+/// Rows of the query along with the metadata of the result
+#[derive(Debug, Clone)]
+pub struct QueryResult<T> {
+    pub rows: Vec<T>,
+    /// Warnings sent by the server while executing the query, for example
+    /// about deprecated syntax
+    pub warnings: Vec<LogMessage>,
 }
 
 #[derive(Debug, Snafu)]
@@ -55,10 +90,30 @@ pub enum ReadError {
     Io { source: io::Error },
     #[snafu(display("server message out of order: {:?}", message))]
     OutOfOrder { message: ServerMessage, backtrace: Backtrace },
-    #[snafu(display("request error: {}", error))]
-    RequestError { error: ErrorResponse, backtrace: Backtrace },
+    /// Error returned by the server while executing the query
+    ///
+    /// `query` is only set when enabled by
+    /// [`Builder::query_source_in_errors`].
+    ///
+    /// [`Builder::query_source_in_errors`]:
+    ///     crate::Builder::query_source_in_errors
+    #[snafu(display("{}request error: {}",
+        query.as_ref().map(|q| format!("{}: ", q)).unwrap_or_default(),
+        error))]
+    RequestError {
+        error: ErrorResponse,
+        query: Option<QuerySource>,
+        backtrace: Backtrace,
+    },
     #[snafu(display("end of stream"))]
     Eos,
+    #[snafu(display("request cancelled"))]
+    Cancelled,
+    /// Request took longer than the configured query timeout
+    ///
+    /// See [`Builder::query_timeout`](crate::Builder::query_timeout).
+    #[snafu(display("request timed out"))]
+    Timeout,
 }
 
 pub trait Decode {
@@ -106,6 +161,11 @@ impl<'r> Reader<'r> {
             reader: self,
         }
     }
+    /// Return the message back, so it's the next one received
+    pub(crate) fn unread(&mut self, msg: ServerMessage) {
+        debug_assert!(self.peeked.is_none());
+        self.peeked = Some(msg);
+    }
     pub fn consume_ready(&mut self, ready: ReadyForCommand) {
         *self.transaction_state = ready.transaction_state;
     }
@@ -117,52 +177,55 @@ impl<'r> Reader<'r> {
                     self.consume_ready(ready);
                     return Ok(())
                 }
-                // TODO(tailhook) should we react on messages somehow?
-                //                At list parse LogMessage's?
                 _ => {},
             }
         }
     }
+    /// Poll next message, recording `ParameterStatus` messages and
+    /// dispatching `LogMessage`s on the way
+    ///
+    /// Server can send these at any time, so they are never returned to
+    /// the caller.
     fn poll_message(&mut self, cx: &mut Context)
         -> Poll<Result<ServerMessage, ReadError>>
     {
-        let Reader { ref mut buf, ref mut stream, .. } = self;
-        let frame_len = loop {
-            let mut next_read = BUFFER_SIZE;
-            let buf_len = buf.len();
-            if buf_len > 5 {
-                let len = u32::from_be_bytes(
-                    buf[1..5].try_into().unwrap())
-                    as usize;
-                if buf_len >= len + 1 {
-                    break len+1;
-                }
-                next_read = max(min(len + 1 - buf_len, MAX_BUFFER),
-                                BUFFER_SIZE);
-                debug_assert!(next_read > 0);
+        if self.cancel.poll_cancelled(cx) {
+            // server aborts the query when connection is closed
+            if let Some(shutdown) = self.shutdown {
+                shutdown();
             }
-
-            buf.reserve(next_read);
-            unsafe {
-                // this is safe because the underlying ByteStream always
-                // initializes read bytes
-                let chunk = buf.chunk_mut();
-                let dest: &mut [u8] = slice::from_raw_parts_mut(
-                    chunk.as_mut_ptr(), chunk.len());
-                match Pin::new(&mut *stream).poll_read(cx, dest) {
-                    Poll::Ready(Ok(0)) => {
-                        return Poll::Ready(Err(ReadError::Eos));
-                    }
-                    Poll::Ready(Ok(bytes)) => {
-                        buf.advance_mut(bytes);
-                        continue;
-                    }
-                    Poll::Ready(r @ Err(_)) => { r.context(Io)?; }
-                    Poll::Pending => return Poll::Pending,
+            return Poll::Ready(Err(ReadError::Cancelled));
+        }
+        if let Some(timeout) = &mut self.timeout {
+            if timeout.as_mut().poll(cx).is_ready() {
+                // completed future must not be polled again
+                self.timeout = None;
+                return Poll::Ready(Err(ReadError::Timeout));
+            }
+        }
+        if let Some(msg) = self.peeked.take() {
+            return Poll::Ready(Ok(msg));
+        }
+        loop {
+            match self.poll_raw_message(cx) {
+                Poll::Ready(Ok(ServerMessage::ParameterStatus(par))) => {
+                    self.params.update(par.name, par.value);
+                }
+                Poll::Ready(Ok(ServerMessage::LogMessage(msg))) => {
+                    self.messages.dispatch(msg);
                 }
+                result => return result,
             }
+        }
+    }
+    fn poll_raw_message(&mut self, cx: &mut Context)
+        -> Poll<Result<ServerMessage, ReadError>>
+    {
+        let frame = match poll_frame(&mut **self.stream, self.buf, cx) {
+            Poll::Ready(Ok(frame)) => frame,
+            Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+            Poll::Pending => return Poll::Pending,
         };
-        let frame = buf.split_to(frame_len).freeze();
         let result = ServerMessage::decode(&frame).context(DecodeErr)?;
         log::debug!(target: "edgedb::incoming::frame",
                     "Frame Contents: {:#?}", result);
@@ -170,6 +233,139 @@ impl<'r> Reader<'r> {
     }
 }
 
+impl InputBuffer {
+    pub fn new() -> InputBuffer {
+        InputBuffer {
+            buf: BytesMut::with_capacity(BUFFER_SIZE),
+            filled: 0,
+        }
+    }
+    #[cfg(any(feature="unstable", test))]
+    fn is_empty(&self) -> bool {
+        self.filled == 0
+    }
+    #[cfg(feature="unstable")]
+    fn into_data(mut self) -> BytesMut {
+        self.buf.truncate(self.filled);
+        self.buf
+    }
+}
+
+/// Read a single frame (message type, length and payload) into `input`
+fn poll_frame<S>(stream: &mut S, input: &mut InputBuffer, cx: &mut Context)
+    -> Poll<Result<Bytes, ReadError>>
+    where S: AsyncRead + Unpin + ?Sized,
+{
+    let InputBuffer { buf, filled } = input;
+    let frame_len = loop {
+        let mut next_read = BUFFER_SIZE;
+        let buf_len = *filled;
+        if buf_len >= 5 {
+            let len = u32::from_be_bytes(
+                buf[1..5].try_into().unwrap())
+                as usize;
+            if buf_len >= len + 1 {
+                break len+1;
+            }
+            next_read = max(min(len + 1 - buf_len, MAX_BUFFER),
+                            BUFFER_SIZE);
+            debug_assert!(next_read > 0);
+        }
+
+        if buf.len() < buf_len + next_read {
+            if buf.capacity() < buf_len + next_read {
+                // don't copy the tail if buffer is reallocated
+                buf.truncate(buf_len);
+            }
+            buf.resize(buf_len + next_read, 0);
+        }
+        let result = Pin::new(&mut *stream)
+            .poll_read(cx, &mut buf[buf_len..]);
+        match result {
+            Poll::Ready(Ok(0)) => {
+                return Poll::Ready(Err(ReadError::Eos));
+            }
+            Poll::Ready(Ok(bytes)) => {
+                *filled += bytes;
+                continue;
+            }
+            Poll::Ready(r @ Err(_)) => {
+                r.context(Io)?;
+            }
+            Poll::Pending => {
+                return Poll::Pending;
+            }
+        }
+    };
+    *filled -= frame_len;
+    Poll::Ready(Ok(buf.split_to(frame_len).freeze()))
+}
+
+/// Reads and decodes protocol messages from an arbitrary stream
+///
+/// This is a building block for protocol tooling (proxies, traffic
+/// analyzers, fuzzers) rather than for applications. Server messages are
+/// returned as is: unlike the connection, this reader doesn't handle
+/// `ParameterStatus` or `LogMessage`.
+///
+/// Only available with the `unstable` feature, the API may change in
+/// minor releases.
+#[cfg(feature="unstable")]
+pub struct MessageReader<S> {
+    stream: S,
+    buf: InputBuffer,
+}
+
+#[cfg(feature="unstable")]
+impl<S: AsyncRead + Unpin> MessageReader<S> {
+    pub fn new(stream: S) -> MessageReader<S> {
+        MessageReader {
+            stream,
+            buf: InputBuffer::new(),
+        }
+    }
+    /// Returns the stream and the data read but not consumed yet
+    pub fn into_parts(self) -> (S, BytesMut) {
+        (self.stream, self.buf.into_data())
+    }
+    /// Read next raw frame: message type, length and payload
+    ///
+    /// Returns `None` if stream is closed at the message boundary.
+    pub async fn next_frame(&mut self) -> Result<Option<Bytes>, ReadError> {
+        let MessageReader { stream, buf } = self;
+        let result = async_std::future::poll_fn(|cx| {
+            poll_frame(stream, buf, cx)
+        }).await;
+        match result {
+            Ok(frame) => Ok(Some(frame)),
+            Err(ReadError::Eos) if self.buf.is_empty() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+    /// Read next message sent by the server
+    pub async fn next_message(&mut self)
+        -> Result<Option<ServerMessage>, ReadError>
+    {
+        match self.next_frame().await? {
+            Some(frame) => {
+                Ok(Some(ServerMessage::decode(&frame).context(DecodeErr)?))
+            }
+            None => Ok(None),
+        }
+    }
+    /// Read next message sent by the client
+    pub async fn next_client_message(&mut self)
+        -> Result<Option<ClientMessage>, ReadError>
+    {
+        match self.next_frame().await? {
+            Some(frame) => {
+                Ok(Some(ClientMessage::decode(&frame).context(DecodeErr)?))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
 impl Future for MessageFuture<'_, '_> {
     type Output = Result<ServerMessage, ReadError>;
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
@@ -185,7 +381,15 @@ impl<D> QueryResponse<'_, D>
         Ok(())
     }
     pub async fn get_completion(mut self) -> anyhow::Result<Bytes> {
-        Ok(self.seq._process_exec().await?)
+        let request = self.request.as_deref().unwrap_or("");
+        Ok(self.seq._process_exec(request).await?)
+    }
+    /// Warnings received from the server for this query so far
+    ///
+    /// Warnings may arrive at any point of the response, so the list is
+    /// only complete when all rows are read.
+    pub fn warnings(&self) -> &[LogMessage] {
+        self.seq.reader.messages.warnings()
     }
 }
 
@@ -195,6 +399,23 @@ impl<D> Stream for QueryResponse<'_, D>
     type Item = Result<D::Output, ReadError>;
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context)
         -> Poll<Option<Self::Item>>
+    {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        let result = self.as_mut().poll_response(cx);
+        if matches!(result, Poll::Ready(None) | Poll::Ready(Some(Err(_)))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+impl<D> QueryResponse<'_, D>
+    where D: Decode,
+{
+    fn poll_response(mut self: Pin<&mut Self>, cx: &mut Context)
+        -> Poll<Option<Result<D::Output, ReadError>>>
     {
         assert!(self.seq.active);  // TODO(tailhook) maybe debug_assert
         let QueryResponse {
@@ -203,6 +424,8 @@ impl<D> Stream for QueryResponse<'_, D>
             ref mut error,
             ref mut seq,
             ref decoder,
+            ref request,
+            done: _,
         } = *self;
         while buffer.len() == 0 {
             match seq.reader.poll_message(cx) {
@@ -227,8 +450,10 @@ impl<D> Stream for QueryResponse<'_, D>
                     if let Some(error) = error.take() {
                         seq.reader.consume_ready(r);
                         seq.end_clean();
+                        let query = request.as_ref()
+                            .and_then(|r| seq.query_source(r, &error));
                         return Poll::Ready(Some(
-                            RequestError { error }.fail()));
+                            RequestError { error, query }.fail()));
                     } else {
                         if !*complete {
                             return OutOfOrder {
@@ -257,3 +482,70 @@ impl<D> Stream for QueryResponse<'_, D>
         Poll::Ready(Some(decoder.decode(chunk).context(DecodeErr)))
     }
 }
+
+#[cfg(feature="unstable")]
+#[test]
+fn message_reader() {
+    use async_std::task::block_on;
+    use edgedb_protocol::client_message::ClientMessage;
+
+    let mut data = BytesMut::new();
+    ClientMessage::Sync.encode(&mut data).unwrap();
+    ClientMessage::Terminate.encode(&mut data).unwrap();
+    let mut reader = MessageReader::new(&data[..]);
+    block_on(async {
+        assert_eq!(reader.next_client_message().await.unwrap(),
+                   Some(ClientMessage::Sync));
+        assert_eq!(reader.next_client_message().await.unwrap(),
+                   Some(ClientMessage::Terminate));
+        assert_eq!(reader.next_client_message().await.unwrap(), None);
+    });
+    let mut reader = MessageReader::new(&data[..3]);
+    assert!(matches!(block_on(reader.next_frame()), Err(ReadError::Eos)));
+}
+
+#[test]
+fn input_buffer() {
+    use async_std::task::block_on;
+
+    /// Returns `Pending` before each chunk of at most 3 bytes
+    struct Chunks(Vec<u8>, bool);
+    impl AsyncRead for Chunks {
+        fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context,
+                     buf: &mut [u8])
+            -> Poll<io::Result<usize>>
+        {
+            self.1 = !self.1;
+            if self.1 {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let n = min(min(buf.len(), 3), self.0.len());
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0.drain(..n);
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    let mut data = Vec::new();
+    for payload in &[&b"a"[..], b"bcdefgh", b""] {
+        data.push(b'X');
+        data.extend(&(payload.len() as u32 + 4).to_be_bytes());
+        data.extend(*payload);
+    }
+    let mut stream = Chunks(data, false);
+    let mut input = InputBuffer::new();
+    let mut next = || block_on(async_std::future::poll_fn(|cx| {
+        let result = poll_frame(&mut stream, &mut input, cx);
+        if result.is_pending() {
+            // space for reading stays initialized
+            assert!(input.buf.len() >= input.filled + 3);
+        }
+        result
+    }));
+    assert_eq!(&next().unwrap()[5..], b"a");
+    assert_eq!(&next().unwrap()[5..], b"bcdefgh");
+    assert_eq!(&next().unwrap()[5..], b"");
+    assert!(matches!(next(), Err(ReadError::Eos)));
+    assert!(input.is_empty());
+}
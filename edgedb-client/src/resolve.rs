@@ -0,0 +1,71 @@
+//! Caching of resolved server addresses
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_std::net::ToSocketAddrs;
+
+
+/// Default time resolved addresses are cached for
+pub const DEFAULT_DNS_TTL: Duration = Duration::from_secs(30);
+
+struct Entry {
+    host: String,
+    port: u16,
+    addrs: Vec<SocketAddr>,
+    resolved: Instant,
+}
+
+/// A cache of resolved addresses shared by clones of the builder
+///
+/// Addresses are re-resolved when the cached entry is older than the TTL
+/// and after a failed connection attempt, so that failover via DNS
+/// (e.g. pointing a CNAME at a standby server) is picked up by new
+/// connections without restarting the application.
+#[derive(Clone, Default)]
+pub struct AddrCache {
+    entry: Arc<Mutex<Option<Entry>>>,
+}
+
+impl AddrCache {
+    pub async fn resolve(&self, host: &str, port: u16, ttl: Duration)
+        -> std::io::Result<Vec<SocketAddr>>
+    {
+        if let Some(entry) = &*self.entry.lock().expect("cache lock") {
+            if entry.host == host && entry.port == port &&
+                entry.resolved.elapsed() < ttl
+            {
+                return Ok(entry.addrs.clone());
+            }
+        }
+        let addrs = (host, port).to_socket_addrs().await?
+            .collect::<Vec<_>>();
+        log::debug!("Resolved {}:{} to {:?}", host, port, addrs);
+        *self.entry.lock().expect("cache lock") = Some(Entry {
+            host: host.into(),
+            port,
+            addrs: addrs.clone(),
+            resolved: Instant::now(),
+        });
+        Ok(addrs)
+    }
+    /// Forget cached addresses, so they are resolved on the next attempt
+    pub fn invalidate(&self) {
+        self.entry.lock().expect("cache lock").take();
+    }
+}
+
+#[test]
+fn cache() {
+    use async_std::task::block_on;
+
+    let cache = AddrCache::default();
+    let ttl = Duration::from_secs(3600);
+    let addrs = block_on(cache.resolve("127.0.0.1", 5656, ttl)).unwrap();
+    assert_eq!(addrs, vec!["127.0.0.1:5656".parse().unwrap()]);
+    assert!(cache.entry.lock().unwrap().is_some());
+    let addrs = block_on(cache.resolve("127.0.0.2", 5656, ttl)).unwrap();
+    assert_eq!(addrs, vec!["127.0.0.2:5656".parse().unwrap()]);
+    cache.invalidate();
+    assert!(cache.entry.lock().unwrap().is_none());
+}
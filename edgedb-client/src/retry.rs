@@ -0,0 +1,146 @@
+//! Retry policy for connecting and transactions
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rand::{thread_rng, Rng};
+
+use edgedb_protocol::server_message::ErrorResponse;
+
+use crate::errors::ExecuteManyError;
+use crate::reader::ReadError;
+
+
+/// Maximum delay returned by [`default_backoff`] excluding jitter
+pub const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Options that control how failed operations are retried
+#[derive(Clone)]
+pub struct RetryOptions {
+    attempts: u32,
+    backoff: Arc<dyn Fn(u32) -> Duration + Send + Sync>,
+}
+
+/// Default backoff function: exponential backoff with jitter
+///
+/// Returns `100ms * 2^attempt` (limited by [`MAX_BACKOFF`]) plus random
+/// value up to 100ms, so that multiple clients failing simultaneously don't
+/// retry at the same time.
+pub fn default_backoff(attempt: u32) -> Duration {
+    let base = Duration::from_millis(100)
+        .checked_mul(1 << attempt.min(16))
+        .unwrap_or(MAX_BACKOFF)
+        .min(MAX_BACKOFF);
+    base + Duration::from_millis(thread_rng().gen_range(0u64..100u64))
+}
+
+impl Default for RetryOptions {
+    fn default() -> RetryOptions {
+        RetryOptions {
+            attempts: 3,
+            backoff: Arc::new(default_backoff),
+        }
+    }
+}
+
+impl RetryOptions {
+    /// Set maximum number of attempts (including the first one)
+    ///
+    /// Default is 3. Values less than 1 are treated as 1.
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+    /// Set backoff function
+    ///
+    /// Function receives attempt number (`0` for the delay before the first
+    /// retry) and returns time to sleep before the next attempt.
+    pub fn with_backoff<F>(mut self, backoff: F) -> Self
+        where F: Fn(u32) -> Duration + Send + Sync + 'static,
+    {
+        self.backoff = Arc::new(backoff);
+        self
+    }
+    /// Maximum number of attempts (including the first one)
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+    /// Time to sleep before the retry number `attempt` (zero-based)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        (self.backoff)(attempt)
+    }
+}
+
+/// Returns true if transaction failed because of a concurrent transaction
+///
+/// This covers serialization failures and deadlocks, which are reported
+/// as `TransactionSerializationError` and `TransactionDeadlockError` (or
+/// generic `TransactionConflictError` by newer servers). Such
+/// transactions are safe to retry from the start.
+pub fn is_transaction_conflict(e: &anyhow::Error) -> bool {
+    let response = if let Some(err) = e.downcast_ref::<ErrorResponse>() {
+        err
+    } else if let Some(ReadError::RequestError { error, .. })
+        = e.downcast_ref::<ReadError>()
+    {
+        error
+    } else if let Some(err) = e.downcast_ref::<ExecuteManyError>() {
+        &err.error
+    } else {
+        return false;
+    };
+    matches!(response.code,
+        0x_05_03_00_01 | 0x_05_03_00_02 | 0x_05_03_01_00..=0x_05_03_01_FF)
+}
+
+impl fmt::Debug for RetryOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryOptions")
+            .field("attempts", &self.attempts)
+            .finish()
+    }
+}
+
+#[test]
+fn backoff() {
+    for attempt in 0..40 {
+        let value = default_backoff(attempt);
+        let base = Duration::from_millis(100 << attempt.min(6))
+            .min(MAX_BACKOFF);
+        assert!(value >= base, "{:?} < {:?}", value, base);
+        assert!(value < base + Duration::from_millis(100));
+    }
+    let opts = RetryOptions::default()
+        .with_attempts(0)
+        .with_backoff(|n| Duration::from_secs(n.into()));
+    assert_eq!(opts.attempts(), 1);
+    assert_eq!(opts.backoff(3), Duration::from_secs(3));
+}
+
+#[test]
+fn transaction_conflict() {
+    use std::collections::HashMap;
+    use anyhow::Context;
+    use edgedb_protocol::server_message::ErrorSeverity;
+
+    let error = |code| ErrorResponse {
+        severity: ErrorSeverity::Error,
+        code,
+        message: "error".into(),
+        attributes: HashMap::new(),
+    };
+    assert!(is_transaction_conflict(&error(0x_05_03_00_01).into()));
+    assert!(is_transaction_conflict(&error(0x_05_03_01_00).into()));
+    assert!(is_transaction_conflict(&error(0x_05_03_01_02).into()));
+    assert!(!is_transaction_conflict(&error(0x_05_03_00_00).into()));
+    assert!(!is_transaction_conflict(&error(0x_05_02_00_01).into()));
+    let with_context = Err::<(), _>(error(0x_05_03_00_02))
+        .context("query failed").unwrap_err();
+    assert!(is_transaction_conflict(&with_context));
+    let many: anyhow::Error = ExecuteManyError {
+        index: 1,
+        error: error(0x_05_03_00_01),
+    }.into();
+    assert!(is_transaction_conflict(&many));
+    assert!(!is_transaction_conflict(&anyhow::anyhow!("other")));
+}
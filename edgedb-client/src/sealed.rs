@@ -1 +1,7 @@
-pub trait SealedParam {}
+use bytes::Bytes;
+
+pub trait SealedParam: Sized {
+    /// Name of the parameter in the `ParameterStatus` message
+    const NAME: &'static str;
+    fn decode(value: &Bytes) -> anyhow::Result<Self>;
+}
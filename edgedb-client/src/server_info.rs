@@ -0,0 +1,184 @@
+//! Server version and supported features
+use std::fmt;
+use std::str::FromStr;
+
+use crate::features::ProtocolVersion;
+
+
+/// Release stage of the server version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Dev,
+    Alpha,
+    Beta,
+    Rc,
+    Final,
+}
+
+/// Parsed server version, e.g. `1.0-beta.2+d20210315.gabcdef`
+///
+/// Versions are compared by major, minor, stage and stage number. Build
+/// metadata (the part after `+`) is kept only for display.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ServerVersion {
+    major: u32,
+    minor: u32,
+    stage: Stage,
+    stage_number: u32,
+    build: String,
+}
+
+/// Information about the server the connection is established to
+#[derive(Debug, Clone)]
+pub struct ServerInfo {
+    version: ServerVersion,
+    protocol: ProtocolVersion,
+}
+
+impl ServerVersion {
+    pub fn new(major: u32, minor: u32) -> ServerVersion {
+        ServerVersion {
+            major,
+            minor,
+            stage: Stage::Final,
+            stage_number: 0,
+            build: String::new(),
+        }
+    }
+    pub fn major(&self) -> u32 {
+        self.major
+    }
+    pub fn minor(&self) -> u32 {
+        self.minor
+    }
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+    pub fn stage_number(&self) -> u32 {
+        self.stage_number
+    }
+}
+
+impl FromStr for ServerVersion {
+    type Err = anyhow::Error;
+    fn from_str(value: &str) -> anyhow::Result<ServerVersion> {
+        let err = || anyhow::anyhow!("invalid server version {:?}", value);
+        let (version, build) = match value.split_once('+') {
+            Some((version, build)) => (version, build),
+            None => (value, ""),
+        };
+        let (number, stage) = match version.split_once('-') {
+            Some((number, stage)) => (number, Some(stage)),
+            None => (version, None),
+        };
+        let (major, minor) = match number.split_once('.') {
+            Some((major, minor)) => (major, minor),
+            None => (number, "0"),
+        };
+        let (stage, stage_number) = match stage {
+            None => (Stage::Final, 0),
+            Some(stage) => {
+                let (name, num) = match stage.split_once('.') {
+                    Some((name, num)) => {
+                        (name, num.parse().map_err(|_| err())?)
+                    }
+                    None => (stage, 0),
+                };
+                let stage = match name {
+                    "dev" => Stage::Dev,
+                    "alpha" => Stage::Alpha,
+                    "beta" => Stage::Beta,
+                    "rc" => Stage::Rc,
+                    _ => return Err(err()),
+                };
+                (stage, num)
+            }
+        };
+        Ok(ServerVersion {
+            major: major.parse().map_err(|_| err())?,
+            minor: minor.parse().map_err(|_| err())?,
+            stage,
+            stage_number,
+            build: build.into(),
+        })
+    }
+}
+
+impl fmt::Display for ServerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        let stage = match self.stage {
+            Stage::Dev => "dev",
+            Stage::Alpha => "alpha",
+            Stage::Beta => "beta",
+            Stage::Rc => "rc",
+            Stage::Final => "",
+        };
+        if !stage.is_empty() {
+            write!(f, "-{}.{}", stage, self.stage_number)?;
+        }
+        if !self.build.is_empty() {
+            write!(f, "+{}", self.build)?;
+        }
+        Ok(())
+    }
+}
+
+impl ServerInfo {
+    pub fn new(version: ServerVersion, protocol: ProtocolVersion)
+        -> ServerInfo
+    {
+        ServerInfo { version, protocol }
+    }
+    pub fn version(&self) -> &ServerVersion {
+        &self.version
+    }
+    pub fn protocol(&self) -> &ProtocolVersion {
+        &self.protocol
+    }
+    /// Returns true if JSON output formats are supported
+    pub fn supports_json_io(&self) -> bool {
+        self.protocol.ver() >= (0, 7)
+    }
+    /// Returns true if server can restore dumps of format `version`
+    ///
+    /// Version is the one written in the dump file header (see
+    /// [`dump::FORMAT_VERSION`](crate::dump::FORMAT_VERSION)).
+    pub fn supports_dump_format(&self, version: i64) -> bool {
+        version == 1 && self.protocol.ver() >= (0, 7)
+    }
+    /// Returns true if server returns type names of objects inline
+    pub fn supports_inline_typenames(&self) -> bool {
+        self.protocol.supports_inline_typenames()
+    }
+    /// Returns true if server supports global variables
+    pub fn supports_globals(&self) -> bool {
+        self.version >= ServerVersion::new(2, 0)
+    }
+}
+
+#[test]
+fn parse_version() {
+    let ver: ServerVersion = "1.0-beta.2+d20210315.gabcdef".parse().unwrap();
+    assert_eq!((ver.major(), ver.minor()), (1, 0));
+    assert_eq!((ver.stage(), ver.stage_number()), (Stage::Beta, 2));
+    assert_eq!(ver.to_string(), "1.0-beta.2+d20210315.gabcdef");
+    let final_ver: ServerVersion = "1.0".parse().unwrap();
+    assert!(ver < final_ver);
+    assert!("1.0-rc.1".parse::<ServerVersion>().unwrap() < final_ver);
+    assert!("1.1-alpha.1".parse::<ServerVersion>().unwrap() > final_ver);
+    assert_eq!("2".parse::<ServerVersion>().unwrap(),
+               ServerVersion::new(2, 0));
+    assert!("1.0-gamma.1".parse::<ServerVersion>().is_err());
+    assert!("x.y".parse::<ServerVersion>().is_err());
+}
+
+#[test]
+fn features() {
+    let info = ServerInfo::new("1.0".parse().unwrap(),
+                               ProtocolVersion::current());
+    assert!(info.supports_json_io());
+    assert!(info.supports_dump_format(1));
+    assert!(!info.supports_dump_format(2));
+    assert!(!info.supports_globals());
+}
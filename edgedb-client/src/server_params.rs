@@ -1,9 +1,26 @@
 //! Parameters returned by the server on initial handshake
+//!
+//! Server may also change parameters later, while a connection is in use.
+//! Such updates can be observed using
+//! [`subscribe_params`](crate::client::Connection::subscribe_params).
+//! Latest values of known parameters are returned by
+//! [`server_param`](crate::client::Connection::server_param).
 use std::collections::HashMap;
+use std::io::Cursor;
+use std::time::Duration;
 
+use async_std::channel::{unbounded, Receiver, Sender};
+use anyhow::Context;
+use bytes::{Bytes, Buf};
 use serde::{Deserialize, Serialize};
+use serde_json::from_slice;
 use typemap::Key;
 
+use edgedb_protocol::client_message::Cardinality;
+use edgedb_protocol::model::Uuid;
+use edgedb_protocol::server_message::CommandDataDescription;
+use edgedb_protocol::value::Value;
+
 use crate::sealed::SealedParam;
 
 
@@ -18,6 +35,31 @@ pub struct PostgresAddress {
     pub server_settings: HashMap<String, String>,
 }
 
+/// Configuration of the server that is relevant for clients
+///
+/// Sent as the `system_config` parameter, fields are `None` if the server
+/// doesn't report them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SystemConfig {
+    /// Server closes connections that are idle longer than this
+    pub session_idle_timeout: Option<Duration>,
+}
+
+/// A change of the server parameter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParameterUpdate {
+    pub name: String,
+    pub value: Bytes,
+}
+
+/// Latest raw values of parameters and subscribers to their updates
+#[derive(Debug, Default)]
+pub(crate) struct ParamWatch {
+    values: HashMap<String, Bytes>,
+    subscribers: Vec<Sender<ParameterUpdate>>,
+}
+
 /// A trait that represents param sent from server
 pub trait ServerParam: SealedParam
     + typemap::Key + typemap::DebugAny + Send + Sync
@@ -28,5 +70,150 @@ impl Key for PostgresAddress {
     type Value = PostgresAddress;
 }
 
-impl SealedParam for PostgresAddress { }
+impl SealedParam for PostgresAddress {
+    const NAME: &'static str = "pgaddr";
+    fn decode(value: &Bytes) -> anyhow::Result<PostgresAddress> {
+        Ok(from_slice(&value[..])?)
+    }
+}
 impl ServerParam for PostgresAddress { }
+
+impl Key for SystemConfig {
+    type Value = SystemConfig;
+}
+
+impl SealedParam for SystemConfig {
+    const NAME: &'static str = "system_config";
+    /// Value is a type descriptor followed by the data, both prefixed
+    /// with length
+    fn decode(value: &Bytes) -> anyhow::Result<SystemConfig> {
+        let mut cur = Cursor::new(value.clone());
+        let typedesc = read_bytes(&mut cur).context("type descriptor")?;
+        let data = read_bytes(&mut cur).context("data")?;
+        anyhow::ensure!(typedesc.len() >= 16, "type descriptor is too short");
+        let description = CommandDataDescription {
+            headers: HashMap::new(),
+            result_cardinality: Cardinality::One,
+            input_typedesc_id: Uuid::from_u128(0),
+            input_typedesc: Bytes::new(),
+            output_typedesc_id: Uuid::from_slice(&typedesc[..16])?,
+            output_typedesc: typedesc.slice(16..),
+        };
+        let codec = description.output()?.build_codec()?;
+        let (shape, fields) = match codec.decode(&data)? {
+            Value::Object { shape, fields } => (shape, fields),
+            value => anyhow::bail!("unexpected value of kind {}",
+                                   value.kind()),
+        };
+        let mut config = SystemConfig::default();
+        for (element, field) in shape.elements.iter().zip(fields) {
+            if let ("session_idle_timeout", Some(Value::Duration(d)))
+                = (&element.name[..], field)
+            {
+                config.session_idle_timeout = Some(d.abs_duration());
+            }
+        }
+        Ok(config)
+    }
+}
+impl ServerParam for SystemConfig { }
+
+fn read_bytes(cur: &mut Cursor<Bytes>) -> anyhow::Result<Bytes> {
+    anyhow::ensure!(cur.remaining() >= 4, "unexpected end of data");
+    let len = cur.get_u32() as usize;
+    anyhow::ensure!(cur.remaining() >= len, "unexpected end of data");
+    let pos = cur.position() as usize;
+    cur.advance(len);
+    Ok(cur.get_ref().slice(pos..pos+len))
+}
+
+impl ParameterUpdate {
+    /// Decode the value if this is an update of the parameter `T`
+    pub fn decode<T: ServerParam>(&self) -> Option<anyhow::Result<T>> {
+        if self.name == T::NAME {
+            Some(T::decode(&self.value))
+        } else {
+            None
+        }
+    }
+}
+
+impl ParamWatch {
+    pub fn update(&mut self, name: Bytes, value: Bytes) {
+        let name = String::from_utf8_lossy(&name).into_owned();
+        log::debug!("Server parameter {:?} updated", name);
+        let update = ParameterUpdate { name, value };
+        self.subscribers.retain(|s| s.try_send(update.clone()).is_ok());
+        self.values.insert(update.name, update.value);
+    }
+    pub fn get(&self, name: &str) -> Option<&Bytes> {
+        self.values.get(name)
+    }
+    pub fn values(&self) -> &HashMap<String, Bytes> {
+        &self.values
+    }
+    /// Take values from `other`, notifying subscribers of this watch
+    pub fn merge(&mut self, other: ParamWatch) {
+        for (name, value) in other.values {
+            self.update(name.into(), value);
+        }
+    }
+    pub fn subscribe(&mut self) -> Receiver<ParameterUpdate> {
+        let (tx, rx) = unbounded();
+        self.subscribers.push(tx);
+        rx
+    }
+}
+
+#[test]
+fn watch() {
+    let mut watch = ParamWatch::default();
+    let rx = watch.subscribe();
+    watch.update("x".into(), "1".into());
+    assert_eq!(watch.get("x"), Some(&Bytes::from("1")));
+    assert_eq!(rx.try_recv().unwrap(), ParameterUpdate {
+        name: "x".into(),
+        value: "1".into(),
+    });
+    drop(rx);
+    watch.update("x".into(), "2".into());
+    assert!(watch.subscribers.is_empty());
+    assert_eq!(watch.get("x"), Some(&Bytes::from("2")));
+}
+
+#[test]
+fn system_config() {
+    use bytes::{BytesMut, BufMut};
+
+    let mut typedesc = BytesMut::new();
+    typedesc.put_u128(1);  // root type id
+    typedesc.put_u8(2);  // base scalar: duration
+    typedesc.put_u128(0x10e);
+    typedesc.put_u8(1);  // object shape
+    typedesc.put_u128(1);
+    typedesc.put_u16(1);
+    typedesc.put_u8(0);
+    typedesc.put_u32(20);
+    typedesc.put_slice(b"session_idle_timeout");
+    typedesc.put_u16(0);
+    let mut data = BytesMut::new();
+    data.put_u32(1);
+    data.put_u32(0);
+    data.put_u32(16);
+    data.put_i64(60_000_000);
+    data.put_u64(0);
+    let mut value = BytesMut::new();
+    value.put_u32(typedesc.len() as u32);
+    value.put_slice(&typedesc);
+    value.put_u32(data.len() as u32);
+    value.put_slice(&data);
+    let update = ParameterUpdate {
+        name: "system_config".into(),
+        value: value.freeze(),
+    };
+    let config = update.decode::<SystemConfig>().unwrap().unwrap();
+    assert_eq!(config.session_idle_timeout, Some(Duration::from_secs(60)));
+    assert!(update.decode::<PostgresAddress>().is_none());
+    let truncated = update.value.slice(..10);
+    assert!(SystemConfig::decode(&truncated).is_err());
+}
@@ -0,0 +1,74 @@
+//! Lightweight inspection of EdgeQL statements on the client side
+
+
+fn words(text: &str) -> impl Iterator<Item=&str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        loop {
+            rest = rest.trim_start_matches(|c: char| {
+                c.is_whitespace() || c == ';'
+            });
+            if rest.starts_with('#') {
+                rest = rest.find('\n').map(|p| &rest[p..]).unwrap_or("");
+                continue;
+            }
+            break;
+        }
+        let end = rest
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(rest.len());
+        if end == 0 {
+            return None;
+        }
+        let (word, tail) = rest.split_at(end);
+        rest = tail;
+        Some(word)
+    })
+}
+
+/// Returns true if statement starts, ends or controls a transaction
+///
+/// Such statements must not be used with the query methods, because
+/// transaction state is managed by the [`transaction`] API.
+///
+/// [`transaction`]: crate::client::Connection::transaction
+pub fn is_transaction_control(statement: &str) -> bool {
+    let mut words = words(statement);
+    let first = match words.next() {
+        Some(word) => word,
+        None => return false,
+    };
+    if first.eq_ignore_ascii_case("commit") ||
+        first.eq_ignore_ascii_case("rollback")
+    {
+        return true;
+    }
+    let second = match words.next() {
+        Some(word) => word,
+        None => return false,
+    };
+    (first.eq_ignore_ascii_case("start") &&
+        second.eq_ignore_ascii_case("transaction")) ||
+    ((first.eq_ignore_ascii_case("declare") ||
+      first.eq_ignore_ascii_case("release")) &&
+        second.eq_ignore_ascii_case("savepoint"))
+}
+
+#[test]
+fn transaction_control() {
+    assert!(is_transaction_control("START TRANSACTION"));
+    assert!(is_transaction_control("  start transaction isolation \
+                                    serializable"));
+    assert!(is_transaction_control("commit;"));
+    assert!(is_transaction_control("# comment\nCOMMIT"));
+    assert!(is_transaction_control("ROLLBACK TO SAVEPOINT x"));
+    assert!(is_transaction_control("declare savepoint x"));
+    assert!(is_transaction_control("Release Savepoint x"));
+    assert!(!is_transaction_control("SELECT 1"));
+    assert!(!is_transaction_control("SELECT commit"));
+    assert!(!is_transaction_control("SELECT (commit := 1)"));
+    assert!(!is_transaction_control("start_time"));
+    assert!(!is_transaction_control("START MIGRATION TO {}"));
+    assert!(!is_transaction_control(""));
+    assert!(!is_transaction_control("# commit"));
+}
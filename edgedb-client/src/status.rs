@@ -0,0 +1,72 @@
+//! Completion status of the executed commands
+use std::fmt;
+
+use bytes::Bytes;
+
+
+/// Status reported by the server in `CommandComplete` message
+///
+/// Status text starts with the kind of the statement (e.g. `INSERT` or
+/// `CREATE TYPE`), optionally followed by the number of affected objects.
+/// Note: not all server versions report the count, so
+/// [`affected`](CommandStatus::affected) returns `None` in that case rather
+/// than zero. Use
+/// [`query_affected`](crate::client::Connection::query_affected) if the
+/// count is needed regardless of the server version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandStatus {
+    data: Bytes,
+    kind: String,
+    affected: Option<u64>,
+}
+
+impl CommandStatus {
+    pub(crate) fn new(data: Bytes) -> CommandStatus {
+        let text = String::from_utf8_lossy(&data[..]);
+        let text = text.trim();
+        let (kind, affected) = match text.rsplit_once(' ') {
+            Some((kind, num)) => match num.parse() {
+                Ok(num) => (kind.trim_end(), Some(num)),
+                Err(_) => (text, None),
+            },
+            None => (text, None),
+        };
+        CommandStatus {
+            kind: kind.to_owned(),
+            affected,
+            data,
+        }
+    }
+    /// Kind of the statement, e.g. `UPDATE` or `CREATE TYPE`
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+    /// Number of objects affected, if reported by the server
+    pub fn affected(&self) -> Option<u64> {
+        self.affected
+    }
+    /// Raw status data as sent by the server
+    pub fn status_data(&self) -> &Bytes {
+        &self.data
+    }
+}
+
+impl fmt::Display for CommandStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        String::from_utf8_lossy(&self.data[..]).fmt(f)
+    }
+}
+
+#[test]
+fn parse() {
+    let status = CommandStatus::new(Bytes::from_static(b"UPDATE"));
+    assert_eq!(status.kind(), "UPDATE");
+    assert_eq!(status.affected(), None);
+    let status = CommandStatus::new(Bytes::from_static(b"CREATE TYPE"));
+    assert_eq!(status.kind(), "CREATE TYPE");
+    assert_eq!(status.affected(), None);
+    let status = CommandStatus::new(Bytes::from_static(b"DELETE 3"));
+    assert_eq!(status.kind(), "DELETE");
+    assert_eq!(status.affected(), Some(3));
+    assert_eq!(status.to_string(), "DELETE 3");
+}
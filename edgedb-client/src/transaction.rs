@@ -0,0 +1,351 @@
+//! Transaction API
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+
+use async_std::task::sleep;
+use edgedb_protocol::server_message::TransactionState;
+
+use crate::client::Connection;
+use crate::errors::NestedTransactionError;
+use crate::retry::{is_transaction_conflict, RetryOptions};
+
+
+/// A boxed future returned by the transaction body
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output=T> + Send + 'a>>;
+
+/// Transaction isolation level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsolationLevel {
+    Serializable,
+    RepeatableRead,
+}
+
+/// Options used to start a transaction
+///
+/// Defaults are the same as the defaults of the `START TRANSACTION`
+/// statement: serializable, read-write, not deferrable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionOptions {
+    isolation: IsolationLevel,
+    read_only: bool,
+    deferrable: bool,
+}
+
+/// A connection in the transaction block
+///
+/// This is passed to the body of [`Connection::transaction`]. All the
+/// methods of the [`Connection`] are available via `Deref`, and savepoints
+/// can be used to recover from errors in the middle of the transaction.
+pub struct Transaction<'a> {
+    conn: &'a mut Connection,
+}
+
+fn quote_name(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+impl Default for TransactionOptions {
+    fn default() -> TransactionOptions {
+        TransactionOptions {
+            isolation: IsolationLevel::Serializable,
+            read_only: false,
+            deferrable: false,
+        }
+    }
+}
+
+impl TransactionOptions {
+    pub fn with_isolation(mut self, isolation: IsolationLevel) -> Self {
+        self.isolation = isolation;
+        self
+    }
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+    pub fn with_deferrable(mut self, deferrable: bool) -> Self {
+        self.deferrable = deferrable;
+        self
+    }
+    pub fn isolation(&self) -> IsolationLevel {
+        self.isolation
+    }
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+    pub fn deferrable(&self) -> bool {
+        self.deferrable
+    }
+    /// Returns `START TRANSACTION` statement for these options
+    pub fn start_statement(&self) -> String {
+        format!("START TRANSACTION ISOLATION {}, {}, {}",
+            match self.isolation {
+                IsolationLevel::Serializable => "SERIALIZABLE",
+                IsolationLevel::RepeatableRead => "REPEATABLE READ",
+            },
+            if self.read_only { "READ ONLY" } else { "READ WRITE" },
+            if self.deferrable { "DEFERRABLE" } else { "NOT DEFERRABLE" })
+    }
+}
+
+impl Connection {
+    /// Run `body` in a transaction
+    ///
+    /// Transaction is started with the [`TransactionOptions`] of the
+    /// connection (always `READ ONLY` for
+    /// [read-only](Connection::with_read_only) connections). It is committed
+    /// if `body` returns `Ok` and rolled back otherwise.
+    ///
+    /// If the transaction fails because of a conflict with a concurrent
+    /// transaction (see [`is_transaction_conflict`]), it's rolled back and
+    /// `body` is run again in a new transaction, up to the number of
+    /// attempts in the connection's [`RetryOptions`] with the configured
+    /// backoff between attempts. So `body` must be safe to rerun, e.g.
+    /// it shouldn't have side effects outside of the database.
+    ///
+    /// Transactions can't be nested: if connection is already in a
+    /// transaction block (for example `transaction()` is called on the
+    /// [`Transaction`] object itself), [`NestedTransactionError`] is
+    /// returned. Use [`savepoints`](Transaction::savepoint) instead.
+    ///
+    /// Body returns a boxed future:
+    ///
+    /// ```rust,ignore
+    /// conn.transaction(|tx| Box::pin(async move {
+    ///     tx.execute("INSERT User { name := 'John' }").await?;
+    ///     Ok(())
+    /// })).await?;
+    /// ```
+    ///
+    /// [`RetryOptions`]: crate::retry::RetryOptions
+    pub async fn transaction<T, F>(&mut self, body: F)
+        -> anyhow::Result<T>
+        where F: for<'t> FnMut(&'t mut Transaction<'_>)
+                    -> BoxFuture<'t, anyhow::Result<T>>,
+    {
+        let options = self.transaction_options.clone();
+        let retry = self.retry_options.clone();
+        self.transaction_with(options, retry, body).await
+    }
+    /// Run `body` in a transaction with the specified options
+    ///
+    /// Same as [`transaction`](Connection::transaction), but `options` and
+    /// `retry` are used instead of the options of the connection for this
+    /// transaction only:
+    ///
+    /// ```rust,ignore
+    /// let options = TransactionOptions::default()
+    ///     .with_isolation(IsolationLevel::RepeatableRead)
+    ///     .with_read_only(true);
+    /// let retry = RetryOptions::default().with_attempts(1);
+    /// let total = conn.transaction_with(options, retry, |tx| Box::pin(
+    ///     async move {
+    ///         tx.query_row::<i64>("SELECT sum(Account.balance)",
+    ///                             &Value::empty_tuple()).await
+    ///     })).await?;
+    /// ```
+    ///
+    /// [Read-only](Connection::with_read_only) connections still start
+    /// `READ ONLY` transactions regardless of `options`.
+    ///
+    /// [`RetryOptions`]: crate::retry::RetryOptions
+    pub async fn transaction_with<T, F>(&mut self,
+        options: TransactionOptions, retry: RetryOptions, mut body: F)
+        -> anyhow::Result<T>
+        where F: for<'t> FnMut(&'t mut Transaction<'_>)
+                    -> BoxFuture<'t, anyhow::Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            self.start_transaction(&options).await?;
+            let result = {
+                let mut tx = Transaction { conn: &mut *self };
+                let result = body(&mut tx).await;
+                tx.conn.finish_transaction(result).await
+            };
+            match result {
+                Err(e) if attempt + 1 < retry.attempts()
+                    && is_transaction_conflict(&e)
+                    && self.is_consistent()
+                    && self.transaction_state() ==
+                        TransactionState::NotInTransaction
+                => {
+                    let delay = retry.backoff(attempt);
+                    log::info!("Retrying transaction in {:?}: {:#}",
+                               delay, e);
+                    sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+    /// Start transaction, read-only mode of the connection takes precedence
+    pub(crate) async fn start_transaction(&mut self,
+                                          options: &TransactionOptions)
+        -> anyhow::Result<()>
+    {
+        if self.transaction_state() != TransactionState::NotInTransaction {
+            Err(NestedTransactionError)?;
+        }
+        let mut options = options.clone();
+        if self.read_only {
+            options = options.with_read_only(true);
+        }
+        let start = options.start_statement();
+        self.execute_control(start).await?;
+        Ok(())
+    }
+    /// Commit transaction if `result` is `Ok`, roll back otherwise
+    pub(crate) async fn finish_transaction<T>(&mut self,
+                                              result: anyhow::Result<T>)
+        -> anyhow::Result<T>
+    {
+        match result {
+            Ok(value) => {
+                match self.execute_control("COMMIT".into()).await {
+                    Ok(_) => Ok(value),
+                    Err(e) => {
+                        // failed commit may leave transaction open
+                        self.rollback_if_needed().await;
+                        Err(e)
+                    }
+                }
+            }
+            Err(e) => {
+                self.rollback_if_needed().await;
+                Err(e)
+            }
+        }
+    }
+    async fn rollback_if_needed(&mut self) {
+        if self.is_consistent() &&
+            self.transaction_state() != TransactionState::NotInTransaction
+        {
+            let rollback = self.execute_control("ROLLBACK".into());
+            if let Err(rollback) = rollback.await {
+                log::warn!("Error rolling back transaction: {:#}",
+                           rollback);
+            }
+        }
+    }
+}
+
+impl<'a> Transaction<'a> {
+    pub(crate) fn new(conn: &'a mut Connection) -> Transaction<'a> {
+        Transaction { conn }
+    }
+    /// Declare a savepoint
+    ///
+    /// Savepoint can be used to roll back part of the transaction, including
+    /// recovering from an error without aborting the whole transaction.
+    pub async fn savepoint(&mut self, name: &str) -> anyhow::Result<()> {
+        self.conn.execute_control(
+            format!("DECLARE SAVEPOINT {}", quote_name(name))).await?;
+        Ok(())
+    }
+    /// Roll back to the savepoint previously declared with
+    /// [`savepoint`](Transaction::savepoint)
+    ///
+    /// This also works if transaction is in a failed state, i.e. after
+    /// a statement has returned an error.
+    pub async fn rollback_to(&mut self, name: &str) -> anyhow::Result<()> {
+        self.conn.execute_control(
+            format!("ROLLBACK TO SAVEPOINT {}", quote_name(name))).await?;
+        Ok(())
+    }
+    /// Release the savepoint, keeping changes made since it was declared
+    pub async fn release(&mut self, name: &str) -> anyhow::Result<()> {
+        self.conn.execute_control(
+            format!("RELEASE SAVEPOINT {}", quote_name(name))).await?;
+        Ok(())
+    }
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            // state of the transaction is unknown, don't reuse connection
+            self.conn.dirty = true;
+        }
+    }
+}
+
+impl Deref for Transaction<'_> {
+    type Target = Connection;
+    fn deref(&self) -> &Connection {
+        self.conn
+    }
+}
+
+impl DerefMut for Transaction<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn
+    }
+}
+
+#[test]
+fn start_statement() {
+    assert_eq!(TransactionOptions::default().start_statement(),
+        "START TRANSACTION ISOLATION SERIALIZABLE, \
+         READ WRITE, NOT DEFERRABLE");
+    assert_eq!(TransactionOptions::default()
+            .with_isolation(IsolationLevel::RepeatableRead)
+            .with_read_only(true)
+            .with_deferrable(true)
+            .start_statement(),
+        "START TRANSACTION ISOLATION REPEATABLE READ, READ ONLY, DEFERRABLE");
+}
+
+#[test]
+fn quote() {
+    assert_eq!(quote_name("sp1"), "`sp1`");
+    assert_eq!(quote_name("a`b"), "`a``b`");
+}
+
+#[cfg(unix)]
+#[test]
+fn poisoned_by_panic() {
+    use std::collections::HashMap;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use async_std::io::prelude::WriteExt;
+    use async_std::io::ReadExt;
+    use async_std::os::unix::net::UnixStream;
+    use async_std::task::{block_on, spawn};
+    use bytes::BytesMut;
+    use edgedb_protocol::server_message::{ServerMessage, Authentication};
+    use edgedb_protocol::server_message::ReadyForCommand;
+
+    use crate::builder::Builder;
+    use crate::transport::Transport;
+
+    let (client, mut server) = UnixStream::pair().unwrap();
+    let server = spawn(async move {
+        let mut buf = [0u8; 1024];
+        server.read(&mut buf).await.unwrap();
+        let mut out = BytesMut::new();
+        ServerMessage::Authentication(Authentication::Ok)
+            .encode(&mut out).unwrap();
+        ServerMessage::ReadyForCommand(ReadyForCommand {
+            headers: HashMap::new(),
+            transaction_state: TransactionState::NotInTransaction,
+        }).encode(&mut out).unwrap();
+        server.write_all(&out).await.unwrap();
+        server
+    });
+    let mut conn = block_on(Builder::new()
+        .connect_transport(Transport::from(client))).unwrap();
+    assert!(conn.is_consistent());
+    {
+        let _tx = Transaction::new(&mut conn);
+    }
+    assert!(conn.is_consistent());
+    catch_unwind(AssertUnwindSafe(|| {
+        let _tx = Transaction::new(&mut conn);
+        panic!("user code panics");
+    })).unwrap_err();
+    assert!(!conn.is_consistent());
+    drop(block_on(server));
+}
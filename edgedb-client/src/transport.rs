@@ -0,0 +1,70 @@
+//! Runtime-agnostic transport the protocol runs over
+//!
+//! The client is written against generic [`AsyncRead`] and [`AsyncWrite`]
+//! traits from the `futures-io` crate. A [`Transport`] can be created from
+//! any pair of read and write halves of a byte stream, or converted from
+//! the stream types of the supported runtimes:
+//!
+//! * `async_std::net::TcpStream` and `async_std::os::unix::net::UnixStream`
+//! * `tokio::net::TcpStream` and `tokio::net::UnixStream` (with the `tokio`
+//!   feature enabled)
+//!
+//! A transport is then passed to [`Builder::connect_transport`] to perform
+//! the handshake.
+//!
+//! Transports converted from the runtime streams also know how to shut
+//! the stream down, so that [cancelled](crate::cancel) requests are
+//! aborted on the server. Use [`Transport::with_shutdown`] to provide the
+//! same for a custom stream.
+//!
+//! [`Builder::connect_transport`]: crate::Builder::connect_transport
+use std::fmt;
+
+use futures_io::{AsyncRead, AsyncWrite};
+
+mod async_std_impl;
+#[cfg(feature="tokio")]
+mod tokio_impl;
+
+
+pub(crate) type ReadHalf = Box<dyn AsyncRead + Send + Sync + Unpin>;
+pub(crate) type WriteHalf = Box<dyn AsyncWrite + Send + Sync + Unpin>;
+pub(crate) type Shutdown = Box<dyn Fn() + Send + Sync>;
+
+/// A byte stream connected to the server
+pub struct Transport {
+    pub(crate) read: ReadHalf,
+    pub(crate) write: WriteHalf,
+    pub(crate) shutdown: Option<Shutdown>,
+}
+
+impl Transport {
+    /// Create transport from separate read and write halves of a stream
+    pub fn new<R, W>(read: R, write: W) -> Transport
+        where R: AsyncRead + Send + Sync + Unpin + 'static,
+              W: AsyncWrite + Send + Sync + Unpin + 'static,
+    {
+        Transport {
+            read: Box::new(read),
+            write: Box::new(write),
+            shutdown: None,
+        }
+    }
+    /// Set a function that shuts the underlying stream down
+    ///
+    /// It's called when a request is cancelled, so it must make both the
+    /// server and the read half see the end of stream (like
+    /// `TcpStream::shutdown(Shutdown::Both)` does).
+    pub fn with_shutdown<F>(mut self, shutdown: F) -> Transport
+        where F: Fn() + Send + Sync + 'static,
+    {
+        self.shutdown = Some(Box::new(shutdown));
+        self
+    }
+}
+
+impl fmt::Debug for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Transport").finish()
+    }
+}
@@ -0,0 +1,21 @@
+use std::net::Shutdown;
+
+use async_std::net::TcpStream;
+
+use crate::transport::Transport;
+
+
+impl From<TcpStream> for Transport {
+    fn from(stream: TcpStream) -> Transport {
+        Transport::new(stream.clone(), stream.clone())
+            .with_shutdown(move || { stream.shutdown(Shutdown::Both).ok(); })
+    }
+}
+
+#[cfg(unix)]
+impl From<async_std::os::unix::net::UnixStream> for Transport {
+    fn from(stream: async_std::os::unix::net::UnixStream) -> Transport {
+        Transport::new(stream.clone(), stream.clone())
+            .with_shutdown(move || { stream.shutdown(Shutdown::Both).ok(); })
+    }
+}
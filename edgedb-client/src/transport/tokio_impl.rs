@@ -0,0 +1,80 @@
+use std::io;
+use std::net::Shutdown;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_io::{AsyncRead, AsyncWrite};
+use tokio::io::{AsyncRead as TokioRead, AsyncWrite as TokioWrite, ReadBuf};
+
+use crate::transport::Transport;
+
+
+/// Adapts tokio IO traits to the `futures-io` ones
+struct Compat<T>(T);
+
+impl<T: TokioRead + Unpin> AsyncRead for Compat<T> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8])
+        -> Poll<io::Result<usize>>
+    {
+        let mut buf = ReadBuf::new(buf);
+        match Pin::new(&mut self.0).poll_read(cx, &mut buf) {
+            Poll::Ready(Ok(())) => Poll::Ready(Ok(buf.filled().len())),
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T: TokioWrite + Unpin> AsyncWrite for Compat<T> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context, buf: &[u8])
+        -> Poll<io::Result<usize>>
+    {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context)
+        -> Poll<io::Result<()>>
+    {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context)
+        -> Poll<io::Result<()>>
+    {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+/// Owned halves can't be shut down synchronously, so a duplicate of the
+/// socket is used instead
+fn with_shutdown(transport: Transport, socket: io::Result<socket2::Socket>)
+    -> Transport
+{
+    match socket {
+        Ok(socket) => transport.with_shutdown(move || {
+            socket.shutdown(Shutdown::Both).ok();
+        }),
+        Err(e) => {
+            log::warn!("Can't duplicate socket, cancelled requests \
+                        will run until connection is dropped: {}", e);
+            transport
+        }
+    }
+}
+
+impl From<tokio::net::TcpStream> for Transport {
+    fn from(stream: tokio::net::TcpStream) -> Transport {
+        let socket = socket2::SockRef::from(&stream).try_clone();
+        let (read, write) = stream.into_split();
+        let transport = Transport::new(Compat(read), Compat(write));
+        with_shutdown(transport, socket)
+    }
+}
+
+#[cfg(unix)]
+impl From<tokio::net::UnixStream> for Transport {
+    fn from(stream: tokio::net::UnixStream) -> Transport {
+        let socket = socket2::SockRef::from(&stream).try_clone();
+        let (read, write) = stream.into_split();
+        let transport = Transport::new(Compat(read), Compat(write));
+        with_shutdown(transport, socket)
+    }
+}
@@ -0,0 +1,624 @@
+//! Replays protocol exchanges described in `tests/conformance/*.txt`
+//!
+//! Each fixture is a sequence of client messages expected by the fake
+//! server and raw bytes the server sends in response (a frame may span
+//! several lines). Client messages are decoded and compared in full:
+//! headers, statement text and arguments. The connection handshake is
+//! shared by all fixtures and lives in `prelude.txt`. This locks in
+//! wire compatibility of the client without a running server.
+#![cfg(unix)]
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_std::io::prelude::WriteExt;
+use async_std::io::ReadExt;
+use async_std::os::unix::net::{UnixListener, UnixStream};
+use async_std::stream::StreamExt;
+use async_std::task::{block_on, spawn, JoinHandle};
+use bytes::Bytes;
+
+use edgedb_client::Builder;
+use edgedb_client::client::Connection;
+use edgedb_client::errors::{ExecuteManyError, QuerySource};
+use edgedb_client::reader::ReadError;
+use edgedb_client::transport::Transport;
+use edgedb_protocol::client_message::ClientMessage;
+use edgedb_protocol::server_message::ErrorResponse;
+use edgedb_protocol::state::{GlobalsDelta, GlobalsModifier};
+use edgedb_protocol::value::Value;
+
+
+enum Step {
+    Client(String),
+    Server(Vec<u8>),
+}
+
+fn load(name: &str) -> Vec<Step> {
+    // every exchange starts with the handshake
+    let mut steps = load_file("prelude");
+    steps.extend(load_file(name));
+    steps
+}
+
+fn load_file(name: &str) -> Vec<Step> {
+    let path = format!("tests/conformance/{}.txt", name);
+    let text = fs::read_to_string(&path).expect("fixture exists");
+    let mut steps = Vec::new();
+    for line in text.lines() {
+        if let Some(msg) = line.strip_prefix('>') {
+            // indented line continues the previous client message
+            match steps.last_mut() {
+                Some(Step::Client(prev)) if msg.starts_with("   ") => {
+                    prev.push(' ');
+                    prev.push_str(msg.trim());
+                }
+                _ => steps.push(Step::Client(msg.trim().to_owned())),
+            }
+        } else if let Some(hex) = line.strip_prefix('<') {
+            let frame = hex.split_whitespace()
+                .map(|b| u8::from_str_radix(b, 16).expect("hex byte"))
+                .collect();
+            steps.push(Step::Server(frame));
+        } else {
+            let line = line.trim();
+            assert!(line.is_empty() || line.starts_with('#'),
+                    "bad line in {}: {:?}", path, line);
+        }
+    }
+    steps
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn name(name: &[u8]) -> String {
+    if name.is_empty() {
+        String::new()
+    } else {
+        format!(" name={}", hex(name))
+    }
+}
+
+fn headers(headers: &HashMap<u16, Bytes>) -> String {
+    let headers = headers.iter().collect::<BTreeMap<_, _>>();
+    headers.iter()
+        .map(|(name, value)| format!(" {:04x}={}", name, hex(value)))
+        .collect()
+}
+
+/// Render a client message in the form used by fixtures
+fn render(msg: &ClientMessage) -> String {
+    use ClientMessage::*;
+    match msg {
+        ClientHandshake(h) => {
+            let params = h.params.iter().collect::<BTreeMap<_, _>>();
+            format!("ClientHandshake {}.{} {:?} extensions={}",
+                    h.major_ver, h.minor_ver, params, h.extensions.len())
+        }
+        ExecuteScript(m) => {
+            format!("ExecuteScript{} {:?}", headers(&m.headers), m.script_text)
+        }
+        Prepare(m) => {
+            format!("Prepare{}{} {:?} {:?} {:?}", headers(&m.headers),
+                    name(&m.statement_name), m.io_format,
+                    m.expected_cardinality, m.command_text)
+        }
+        DescribeStatement(m) => {
+            format!("DescribeStatement{}{} {:?}", headers(&m.headers),
+                    name(&m.statement_name), m.aspect)
+        }
+        Execute(m) => {
+            format!("Execute{}{} args={}", headers(&m.headers),
+                    name(&m.statement_name), hex(&m.arguments))
+        }
+        OptimisticExecute(m) => {
+            format!("OptimisticExecute{} {:?} {:?} {:?} in={} out={} args={}",
+                    headers(&m.headers), m.io_format, m.expected_cardinality,
+                    m.command_text, m.input_typedesc_id, m.output_typedesc_id,
+                    hex(&m.arguments))
+        }
+        Dump(m) => format!("Dump{}", headers(&m.headers)),
+        Restore(m) => {
+            format!("Restore{} jobs={} data={}", headers(&m.headers),
+                    m.jobs, hex(&m.data))
+        }
+        RestoreBlock(m) => format!("RestoreBlock data={}", hex(&m.data)),
+        RestoreEof => "RestoreEof".into(),
+        Sync => "Sync".into(),
+        Flush => "Flush".into(),
+        Terminate => "Terminate".into(),
+        msg => format!("{:?}", msg),
+    }
+}
+
+async fn serve(mut stream: UnixStream, steps: Vec<Step>) -> UnixStream {
+    for step in steps {
+        match step {
+            Step::Client(expected) => {
+                let mut frame = vec![0u8; 5];
+                stream.read_exact(&mut frame).await.unwrap();
+                let len = u32::from_be_bytes([
+                    frame[1], frame[2], frame[3], frame[4]]) as usize;
+                frame.resize(len + 1, 0);
+                stream.read_exact(&mut frame[5..]).await.unwrap();
+                let msg = ClientMessage::decode(&Bytes::from(frame))
+                    .expect("valid client message");
+                assert_eq!(render(&msg), expected,
+                           "unexpected client message");
+            }
+            Step::Server(frame) => {
+                stream.write_all(&frame).await.unwrap();
+            }
+        }
+    }
+    stream
+}
+
+async fn replay(name: &str) -> (Connection, JoinHandle<UnixStream>) {
+    let (client, server) = UnixStream::pair().unwrap();
+    let server = spawn(serve(server, load(name)));
+    let conn = Builder::new()
+        .connect_transport(Transport::from(client)).await.unwrap();
+    (conn, server)
+}
+
+#[test]
+fn handshake() {
+    block_on(async {
+        let (conn, server) = replay("handshake").await;
+        assert!(conn.is_consistent());
+        assert_eq!(conn.get_raw_param("pgaddr").unwrap(),
+                   "/work/tmp/db/.s.PGSQL.60128");
+        drop(server.await);
+    });
+}
+
+#[test]
+fn query() {
+    block_on(async {
+        let (mut conn, server) = replay("query").await;
+        let value = conn.query_row::<i64>("SELECT 7", &Value::empty_tuple())
+            .await.unwrap();
+        assert_eq!(value, 7);
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+#[test]
+fn query_cached() {
+    block_on(async {
+        let (mut conn, server) = replay("query_cached").await;
+        for _ in 0..3 {
+            let value = conn.query_row::<i64>("SELECT 7",
+                                              &Value::empty_tuple())
+                .await.unwrap();
+            assert_eq!(value, 7);
+        }
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+#[test]
+fn pipeline() {
+    block_on(async {
+        let (mut conn, server) = replay("pipeline").await;
+        let results = conn.pipeline()
+            .add("SELECT 7", Value::empty_tuple())
+            .add("SELECT 8", Value::empty_tuple())
+            .run().await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].rows::<i64>().unwrap(), vec![7]);
+        assert_eq!(results[1].values().unwrap(), vec![Value::Int64(8)]);
+        assert_eq!(results[1].status().kind(), "SELECT");
+        assert!(results[0].rows::<String>().is_err());
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+fn int_args(values: &[i64]) -> Vec<Value> {
+    values.iter().map(|&n| Value::Tuple(vec![Value::Int64(n)])).collect()
+}
+
+#[test]
+fn execute_many() {
+    block_on(async {
+        let (mut conn, server) = replay("execute_many").await;
+        let statuses = conn.execute_many("INSERT Item { n := <int64>$0 }",
+                                         int_args(&[1, 2]))
+            .await.unwrap();
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses[1].kind(), "INSERT");
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+#[test]
+fn execute_many_error() {
+    block_on(async {
+        let (mut conn, server) = replay("execute_many_error").await;
+        let err = conn.execute_many("INSERT Item { n := <int64>$0 }",
+                                    int_args(&[1, 2, 3]))
+            .await.unwrap_err();
+        let err = err.downcast_ref::<ExecuteManyError>().unwrap();
+        assert_eq!(err.index, 1);
+        assert_eq!(err.error.code, 0x_05_02_00_01);
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+#[test]
+fn insert_many() {
+    block_on(async {
+        // a single chunk is sent exactly like `execute_many`
+        let (mut conn, server) = replay("execute_many").await;
+        let count = conn.insert_many("INSERT Item { n := <int64>$0 }",
+                                     int_args(&[1, 2]))
+            .await.unwrap();
+        assert_eq!(count, 2);
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+#[test]
+fn insert_many_error() {
+    block_on(async {
+        let (mut conn, server) = replay("execute_many_error").await;
+        let err = conn.insert_many("INSERT Item { n := <int64>$0 }",
+                                   int_args(&[1, 2, 3]))
+            .await.unwrap_err();
+        let err = err.downcast_ref::<ExecuteManyError>().unwrap();
+        assert_eq!(err.index, 1);
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+#[test]
+fn query_timeout() {
+    block_on(async {
+        let (conn, server) = replay("timeout").await;
+        let mut conn = conn
+            .with_query_timeout(Some(Duration::from_millis(50)));
+        let err = conn.query_row::<i64>("SELECT 7", &Value::empty_tuple())
+            .await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<ReadError>(),
+                         Some(ReadError::Timeout)));
+        assert!(!conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+#[test]
+fn query_timeout_rows() {
+    block_on(async {
+        let (conn, server) = replay("timeout_rows").await;
+        let mut conn = conn
+            .with_query_timeout(Some(Duration::from_millis(50)));
+        let mut rows = conn.query::<i64>("SELECT 7", &Value::empty_tuple())
+            .await.unwrap();
+        let err = rows.next().await.unwrap().unwrap_err();
+        assert!(matches!(err, ReadError::Timeout));
+        // stream is finished after an error
+        assert!(rows.next().await.is_none());
+        drop(rows);
+        assert!(!conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+#[test]
+fn reconnect() {
+    block_on(async {
+        let path = std::env::temp_dir().join(format!(
+            "edgedb-conformance-{}.sock", std::process::id()));
+        fs::remove_file(&path).ok();
+        let listener = UnixListener::bind(&path).await.unwrap();
+        let server = spawn(async move {
+            let (first, _) = listener.accept().await.unwrap();
+            // server hangs, so client times out
+            let first = serve(first, load("timeout")).await;
+            let (second, _) = listener.accept().await.unwrap();
+            (first, serve(second, load("query")).await)
+        });
+        let mut conn = Builder::new()
+            .unix_addr(&path)
+            .query_timeout(Some(Duration::from_millis(50)))
+            .reconnect(true)
+            .connect().await.unwrap();
+        let err = conn.query_row::<i64>("SELECT 7", &Value::empty_tuple())
+            .await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<ReadError>(),
+                         Some(ReadError::Timeout)));
+        let value = conn.query_row::<i64>("SELECT 7", &Value::empty_tuple())
+            .await.unwrap();
+        assert_eq!(value, 7);
+        assert!(conn.is_consistent());
+        drop(server.await);
+        fs::remove_file(&path).ok();
+    });
+}
+
+#[test]
+fn reconnect_session() {
+    struct Globals;
+    impl GlobalsDelta for Globals {
+        fn apply(&self, globals: &mut GlobalsModifier) {
+            globals.set("user_id", 1i64);
+        }
+    }
+    block_on(async {
+        let path = std::env::temp_dir().join(format!(
+            "edgedb-conformance-session-{}.sock", std::process::id()));
+        fs::remove_file(&path).ok();
+        let listener = UnixListener::bind(&path).await.unwrap();
+        // each fixture checks that aliases are set before globals
+        let server = spawn(async move {
+            let mut streams = Vec::new();
+            for name in &["session", "session_reconnect", "session_replay"] {
+                let (stream, _) = listener.accept().await.unwrap();
+                streams.push(serve(stream, load(name)).await);
+            }
+            streams
+        });
+        let mut conn = Builder::new()
+            .unix_addr(&path)
+            .query_timeout(Some(Duration::from_millis(50)))
+            .reconnect(true)
+            .connect().await.unwrap();
+        conn.set_module_aliases(&[("", "app")]).await.unwrap();
+        conn.set_globals(&Globals).await.unwrap();
+        for _ in 0..2 {
+            let err = conn.query_row::<i64>("SELECT 7", &Value::empty_tuple())
+                .await.unwrap_err();
+            assert!(matches!(err.downcast_ref::<ReadError>(),
+                             Some(ReadError::Timeout)));
+        }
+        let value = conn.query_row::<i64>("SELECT 7", &Value::empty_tuple())
+            .await.unwrap();
+        assert_eq!(value, 7);
+        assert!(conn.is_consistent());
+        drop(server.await);
+        fs::remove_file(&path).ok();
+    });
+}
+
+#[test]
+fn cancel() {
+    block_on(async {
+        let (mut conn, server) = replay("timeout").await;
+        let handle = conn.cancel_handle();
+        spawn(async move {
+            async_std::task::sleep(Duration::from_millis(50)).await;
+            handle.cancel();
+        });
+        let err = conn.query_row::<i64>("SELECT 7", &Value::empty_tuple())
+            .await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<ReadError>(),
+                         Some(ReadError::Cancelled)));
+        // connection is still alive, but server sees it closed
+        let mut stream = server.await;
+        let mut buf = [0u8; 1];
+        let read = async_std::future::timeout(Duration::from_secs(5),
+                                              stream.read(&mut buf));
+        assert_eq!(read.await.expect("connection is closed").unwrap(), 0);
+        assert!(!conn.is_consistent());
+    });
+}
+
+#[test]
+fn error() {
+    block_on(async {
+        let (mut conn, server) = replay("error").await;
+        let err = conn.query_row::<i64>("SELECT Usr", &Value::empty_tuple())
+            .await.unwrap_err();
+        let response = err.downcast_ref::<ErrorResponse>().unwrap();
+        assert_eq!(response.code, 0x_04_03_00_00);
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+async fn replay_with_source(name: &str)
+    -> (Connection, JoinHandle<UnixStream>)
+{
+    let (client, server) = UnixStream::pair().unwrap();
+    let server = spawn(serve(server, load(name)));
+    let conn = Builder::new()
+        .query_source_in_errors(Some(1024))
+        .connect_transport(Transport::from(client)).await.unwrap();
+    (conn, server)
+}
+
+#[test]
+fn error_exec_query() {
+    block_on(async {
+        let (mut conn, server) = replay_with_source("error_exec").await;
+        let mut rows = conn.query::<i64>("SELECT {1, 2}",
+                                         &Value::empty_tuple())
+            .await.unwrap();
+        let err = rows.next().await.unwrap().unwrap_err();
+        drop(rows);
+        match &err {
+            ReadError::RequestError { error, query: Some(query), .. } => {
+                assert_eq!(error.code, 0x_05_01_00_01);
+                assert_eq!(query.text, "SELECT {1, 2}");
+            }
+            _ => panic!("unexpected error {:?}", err),
+        }
+        assert!(err.to_string()
+                .starts_with("error in query `SELECT {1, 2}`: request error"));
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+#[test]
+fn error_exec_execute() {
+    block_on(async {
+        let (mut conn, server) = replay_with_source("error_exec").await;
+        let err = conn.execute_args("SELECT {1, 2}", &Value::empty_tuple())
+            .await.unwrap_err();
+        let query = err.downcast_ref::<QuerySource>().unwrap();
+        assert_eq!(query.text, "SELECT {1, 2}");
+        let response = err.downcast_ref::<ErrorResponse>().unwrap();
+        assert_eq!(response.code, 0x_05_01_00_01);
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+#[test]
+fn log_message() {
+    block_on(async {
+        let (mut conn, server) = replay("log_message").await;
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let seen = messages.clone();
+        conn.on_log_message(move |m| {
+            seen.lock().unwrap().push(m.text.clone());
+        });
+        let value = conn.query_row::<i64>("SELECT 7", &Value::empty_tuple())
+            .await.unwrap();
+        assert_eq!(value, 7);
+        assert_eq!(*messages.lock().unwrap(),
+                   vec![String::from("deprecated")]);
+        drop(server.await);
+    });
+}
+
+#[test]
+fn describe() {
+    use edgedb_protocol::server_message::CommandDataDescription;
+
+    block_on(async {
+        let (mut conn, server) = replay("describe").await;
+        let desc = conn.query_describe("SELECT 7").await.unwrap();
+        assert!(conn.is_consistent());
+        assert!(desc.output().unwrap().root_pos().is_some());
+        assert!(desc.input().unwrap().is_empty_tuple());
+        let stored = desc.to_bytes().unwrap();
+        assert_eq!(CommandDataDescription::from_bytes(stored).unwrap(), desc);
+        drop(server.await);
+    });
+}
+
+#[test]
+fn query_with_format() {
+    use async_std::prelude::StreamExt;
+    use edgedb_client::client::IoFormat;
+
+    block_on(async {
+        let (mut conn, server) = replay("query").await;
+        let mut query = conn.query_with_format::<i64>("SELECT 7",
+            &Value::empty_tuple(), IoFormat::Binary).await.unwrap();
+        assert_eq!(query.next().await.transpose().unwrap(), Some(7));
+        assert_eq!(query.next().await.transpose().unwrap(), None);
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+#[test]
+fn query_json_value() {
+    block_on(async {
+        let (mut conn, server) = replay("query_json").await;
+        let value = conn.query_json_value("SELECT 7", &Value::empty_tuple())
+            .await.unwrap();
+        assert_eq!(value, serde_json::json!([7]));
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+#[test]
+fn query_single_json() {
+    block_on(async {
+        let (mut conn, server) = replay("query_json_els").await;
+        let value = conn.query_single_json("SELECT 7", &Value::empty_tuple())
+            .await.unwrap();
+        assert_eq!(value, "7");
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+#[test]
+fn query_affected() {
+    block_on(async {
+        let (mut conn, server) = replay("query_affected").await;
+        let count = conn.query_affected("UPDATE User SET { active := true };",
+            &Value::empty_tuple()).await.unwrap();
+        assert_eq!(count, 7);
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+#[test]
+fn query_with_warnings() {
+    block_on(async {
+        let (mut conn, server) = replay("log_message").await;
+        let result = conn.query_with_warnings::<i64>("SELECT 7",
+            &Value::empty_tuple()).await.unwrap();
+        assert_eq!(result.rows, vec![7]);
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(result.warnings[0].text, "deprecated");
+        drop(server.await);
+    });
+}
+
+#[test]
+#[cfg(feature="rayon")]
+fn query_parallel() {
+    block_on(async {
+        let (mut conn, server) = replay("query").await;
+        let rows = conn.query_parallel::<i64>("SELECT 7",
+                                              &Value::empty_tuple())
+            .await.unwrap();
+        assert_eq!(rows, vec![7]);
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+#[test]
+fn copy_csv() {
+    block_on(async {
+        let (mut conn, server) = replay("query").await;
+        let mut out = Vec::new();
+        let rows = conn.copy_csv("SELECT 7", &Value::empty_tuple(), &mut out)
+            .await.unwrap();
+        assert_eq!(rows, 1);
+        assert_eq!(String::from_utf8(out).unwrap(), "value\r\n7\r\n");
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
+
+#[test]
+fn dump_restore_blocks() {
+    block_on(async {
+        let (mut conn, server) = replay("dump").await;
+        let mut dump = conn.dump_blocks().await.unwrap();
+        let header = dump.header().clone();
+        let mut blocks = Vec::new();
+        while let Some(block) = dump.next_block().await.unwrap() {
+            blocks.push(Ok(block));
+        }
+        assert_eq!(&header[..], b"hdr");
+        assert_eq!(blocks.len(), 1);
+        conn.restore_blocks(header, async_std::stream::from_iter(blocks))
+            .await.unwrap();
+        assert!(conn.is_consistent());
+        drop(server.await);
+    });
+}
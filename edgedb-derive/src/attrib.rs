@@ -1,14 +1,14 @@
 use syn::punctuated::Punctuated;
 use syn::parse::{Parse, ParseStream};
 
-#[derive(Debug)]
 enum FieldAttr {
     Json,
+    Rename(syn::LitStr),
 }
 
-#[derive(Debug)]
 enum ContainerAttr {
     Json,
+    Module(syn::LitStr),
 }
 
 struct FieldAttrList(pub Punctuated<FieldAttr, syn::Token![,]>);
@@ -16,14 +16,18 @@ struct ContainerAttrList(pub Punctuated<ContainerAttr, syn::Token![,]>);
 
 pub struct FieldAttrs {
     pub json: bool,
+    pub rename: Option<syn::LitStr>,
 }
 
 pub struct ContainerAttrs {
     pub json: bool,
+    pub module: Option<syn::LitStr>,
 }
 
 mod kw {
     syn::custom_keyword!(json);
+    syn::custom_keyword!(rename);
+    syn::custom_keyword!(module);
 }
 
 impl Parse for FieldAttr {
@@ -32,6 +36,10 @@ impl Parse for FieldAttr {
         if lookahead.peek(kw::json) {
             let _ident: syn::Ident = input.parse()?;
             Ok(FieldAttr::Json)
+        } else if lookahead.peek(kw::rename) {
+            let _ident: syn::Ident = input.parse()?;
+            let _eq: syn::Token![=] = input.parse()?;
+            Ok(FieldAttr::Rename(input.parse()?))
         } else {
             Err(lookahead.error())
         }
@@ -44,6 +52,10 @@ impl Parse for ContainerAttr {
         if lookahead.peek(kw::json) {
             let _ident: syn::Ident = input.parse()?;
             Ok(ContainerAttr::Json)
+        } else if lookahead.peek(kw::module) {
+            let _ident: syn::Ident = input.parse()?;
+            let _eq: syn::Token![=] = input.parse()?;
+            Ok(ContainerAttr::Module(input.parse()?))
         } else {
             Err(lookahead.error())
         }
@@ -66,6 +78,7 @@ impl FieldAttrs {
     fn default() -> FieldAttrs{
         FieldAttrs {
             json: false,
+            rename: None,
         }
     }
     pub fn from_syn(attrs: &[syn::Attribute]) -> syn::Result<FieldAttrs> {
@@ -78,6 +91,7 @@ impl FieldAttrs {
                 for item in chunk.0 {
                     match item {
                         FieldAttr::Json => res.json = true,
+                        FieldAttr::Rename(name) => res.rename = Some(name),
                     }
                 }
             }
@@ -90,6 +104,7 @@ impl ContainerAttrs {
     fn default() -> ContainerAttrs{
         ContainerAttrs {
             json: false,
+            module: None,
         }
     }
     pub fn from_syn(attrs: &[syn::Attribute]) -> syn::Result<ContainerAttrs> {
@@ -102,6 +117,7 @@ impl ContainerAttrs {
                 for item in chunk.0 {
                     match item {
                         ContainerAttr::Json => res.json = true,
+                        ContainerAttr::Module(name) => res.module = Some(name),
                     }
                 }
             }
@@ -0,0 +1,50 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::attrib::{ContainerAttrs, FieldAttrs};
+
+
+pub fn derive_struct(s: &syn::ItemStruct) -> syn::Result<TokenStream> {
+    let name = &s.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        s.generics.split_for_impl();
+    let attrs = ContainerAttrs::from_syn(&s.attrs)?;
+    let module = attrs.module.map(|m| m.value())
+        .unwrap_or_else(|| "default".into());
+    let fields = match &s.fields {
+        syn::Fields::Named(named) => &named.named,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &s.fields, "only named fields are supported"));
+        }
+    };
+    let mut setters = Vec::with_capacity(fields.len());
+    for field in fields {
+        let attrs = FieldAttrs::from_syn(&field.attrs)?;
+        if attrs.json {
+            return Err(syn::Error::new_spanned(field,
+                "json attribute is not supported for globals"));
+        }
+        let ident = field.ident.as_ref().unwrap();
+        let global = match attrs.rename {
+            Some(rename) if rename.value().contains("::") => rename.value(),
+            Some(rename) => format!("{}::{}", module, rename.value()),
+            None => format!("{}::{}", module, ident),
+        };
+        setters.push(quote! {
+            globals.set(#global, ::std::clone::Clone::clone(&self.#ident));
+        });
+    }
+    let expanded = quote! {
+        impl #impl_generics ::edgedb_protocol::state::GlobalsDelta
+            for #name #ty_generics #where_clause
+        {
+            fn apply(&self,
+                globals: &mut ::edgedb_protocol::state::GlobalsModifier)
+            {
+                #(#setters)*
+            }
+        }
+    };
+    Ok(expanded)
+}
@@ -4,6 +4,7 @@ use proc_macro::TokenStream;
 use syn::{self, parse_macro_input};
 
 mod attrib;
+mod globals;
 mod json;
 mod shape;
 
@@ -17,6 +18,22 @@ pub fn edgedb_queryable(input: TokenStream) -> TokenStream {
     }
 }
 
+/// Derive `edgedb_protocol::state::GlobalsDelta` for a struct
+///
+/// Every field is mapped to a global named `module::field_name`, where
+/// module is `default` unless overridden by `#[edgedb(module="name")]` on
+/// the struct. Field can be renamed using `#[edgedb(rename="name")]`
+/// (which may contain a module too). Field types must be convertible into
+/// `Value`; `Option::None` resets the global.
+#[proc_macro_derive(GlobalsDelta, attributes(edgedb))]
+pub fn edgedb_globals_delta(input: TokenStream) -> TokenStream {
+    let s = parse_macro_input!(input as syn::ItemStruct);
+    match globals::derive_struct(&s) {
+        Ok(stream) => stream.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
 fn derive(item: &syn::Item) -> syn::Result<proc_macro2::TokenStream> {
     let attrs = match item {
         syn::Item::Struct(s) => &s.attrs,
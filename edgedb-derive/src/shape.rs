@@ -20,7 +20,9 @@ pub fn derive_struct(s: &syn::ItemStruct) -> syn::Result<TokenStream> {
                 let attrs = FieldAttrs::from_syn(&field.attrs)?;
                 let name = field.ident.clone().unwrap();
                 fields.push(Field {
-                    str_name: syn::LitStr::new(&name.to_string(), name.span()),
+                    str_name: attrs.rename.clone().unwrap_or_else(|| {
+                        syn::LitStr::new(&name.to_string(), name.span())
+                    }),
                     name,
                     ty: field.ty.clone(),
                     attrs,
@@ -37,8 +39,14 @@ pub fn derive_struct(s: &syn::ItemStruct) -> syn::Result<TokenStream> {
         .map(|f| f.name.clone()).collect::<Vec<_>>();
     let has_id = fieldname.iter()
         .find(|x| x.to_string() == "id").is_some();
-    let base_fields = fields.len()
-        + if has_id { 0 } else { 1 };
+    let base_fields = fields.len();
+    let id_field = if has_id {
+        None
+    } else {
+        Some(quote! {
+            + if decoder.has_implicit_id { 1 } else { 0 }
+        })
+    };
     let type_id_block = Some(quote! {
         if decoder.has_implicit_tid {
             elements.skip_element()?;
@@ -53,7 +61,9 @@ pub fn derive_struct(s: &syn::ItemStruct) -> syn::Result<TokenStream> {
         None
     } else {
         Some(quote! {
-            elements.skip_element()?;
+            if decoder.has_implicit_id {
+                elements.skip_element()?;
+            }
         })
     };
     let type_id_check = Some(quote! {
@@ -76,10 +86,12 @@ pub fn derive_struct(s: &syn::ItemStruct) -> syn::Result<TokenStream> {
         None
     } else {
         Some(quote! {
-            if(!shape.elements[idx].flag_implicit) {
-                return Err(ctx.expected("implicit id"));
+            if ctx.has_implicit_id {
+                if(!shape.elements[idx].flag_implicit) {
+                    return Err(ctx.expected("implicit id"));
+                }
+                idx += 1;
             }
-            idx += 1;
         })
     };
     let field_decoders = fields.iter().map(|field| {
@@ -133,6 +145,7 @@ pub fn derive_struct(s: &syn::ItemStruct) -> syn::Result<TokenStream> {
                 -> Result<Self, ::edgedb_protocol::errors::DecodeError>
             {
                 let nfields = #base_fields
+                    #id_field
                     + if decoder.has_implicit_tid { 1 } else { 0 }
                     + if decoder.has_implicit_tname { 1 } else { 0 };
                 let mut elements =
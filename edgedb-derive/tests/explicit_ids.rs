@@ -0,0 +1,26 @@
+use edgedb_derive::Queryable;
+use edgedb_protocol::queryable::{Queryable, Decoder};
+
+
+#[derive(Queryable, Debug, PartialEq)]
+struct User {
+    name: String,
+}
+
+#[test]
+fn implicit_id() {
+    let data = b"\0\0\0\x02\
+        \0\0\0\0\0\0\0\x10\xf2R\x04I\xd7\x04\x11\xea\xaeX\xcf\xdf\xf6\xd0Q\xac\
+        \0\0\0\0\0\0\0\x04John";
+    let res = User::decode(&Decoder::default(), data);
+    assert_eq!(res.unwrap(), User { name: "John".into() });
+}
+
+#[test]
+fn explicit_ids() {
+    let mut dec = Decoder::default();
+    dec.has_implicit_id = false;
+    let data = b"\0\0\0\x01\0\0\0\0\0\0\0\x04John";
+    let res = User::decode(&dec, data);
+    assert_eq!(res.unwrap(), User { name: "John".into() });
+}
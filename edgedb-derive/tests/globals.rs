@@ -0,0 +1,48 @@
+use edgedb_derive::GlobalsDelta;
+use edgedb_protocol::model::Uuid;
+use edgedb_protocol::state::{GlobalsDelta, GlobalsModifier};
+use edgedb_protocol::value::Value;
+
+
+#[derive(GlobalsDelta)]
+struct Globals {
+    current_user_id: Uuid,
+    locale: Option<String>,
+}
+
+#[derive(GlobalsDelta)]
+#[edgedb(module="app")]
+struct Renamed {
+    #[edgedb(rename="user_id")]
+    current_user_id: i64,
+    #[edgedb(rename="sys::tenant")]
+    tenant: String,
+}
+
+#[test]
+fn default_module() {
+    let globals = Globals {
+        current_user_id: Uuid::from_u128(1),
+        locale: None,
+    };
+    let mut modifier = GlobalsModifier::new();
+    globals.apply(&mut modifier);
+    assert_eq!(modifier.values(), &[
+        ("default::current_user_id".into(), Value::Uuid(Uuid::from_u128(1))),
+        ("default::locale".into(), Value::Nothing),
+    ]);
+}
+
+#[test]
+fn rename() {
+    let globals = Renamed {
+        current_user_id: 7,
+        tenant: "acme".into(),
+    };
+    let mut modifier = GlobalsModifier::new();
+    globals.apply(&mut modifier);
+    assert_eq!(modifier.into_values(), vec![
+        ("app::user_id".into(), Value::Int64(7)),
+        ("sys::tenant".into(), Value::Str("acme".into())),
+    ]);
+}
@@ -8,7 +8,14 @@ use snafu::{OptionExt, ensure};
 
 use crate::encoding::{Encode, Decode, Headers, encode};
 use crate::errors::{self, EncodeError, DecodeError};
-pub use crate::common::Cardinality;
+use crate::model::Uuid;
+pub use crate::common::{Cardinality, Capabilities};
+
+pub const HEADER_IMPLICIT_LIMIT: u16 = 0xFF01;
+pub const HEADER_IMPLICIT_TYPENAMES: u16 = 0xFF02;
+pub const HEADER_IMPLICIT_TYPEIDS: u16 = 0xFF03;
+pub const HEADER_ALLOW_CAPABILITIES: u16 = 0xFF04;
+pub const HEADER_EXPLICIT_OBJECTIDS: u16 = 0xFF05;
 
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -19,6 +26,7 @@ pub enum ClientMessage {
     Prepare(Prepare),
     DescribeStatement(DescribeStatement),
     Execute(Execute),
+    OptimisticExecute(OptimisticExecute),
     UnknownMessage(u8, Bytes),
     AuthenticationSaslInitialResponse(SaslInitialResponse),
     AuthenticationSaslResponse(SaslResponse),
@@ -79,6 +87,22 @@ pub struct Execute {
     pub arguments: Bytes,
 }
 
+/// Prepare and execute the statement in a single message
+///
+/// Server only executes the statement if the type descriptor ids match the
+/// ones of the compiled statement, otherwise it replies with the
+/// `CommandDataDescription` so the client can retry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptimisticExecute {
+    pub headers: Headers,
+    pub io_format: IoFormat,
+    pub expected_cardinality: Cardinality,
+    pub command_text: String,
+    pub input_typedesc_id: Uuid,
+    pub output_typedesc_id: Uuid,
+    pub arguments: Bytes,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Dump {
     pub headers: Headers,
@@ -101,13 +125,24 @@ pub enum DescribeAspect {
     DataDescription = 0x54,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Format of the data returned by the server for a statement
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum IoFormat {
+    /// Binary encoding described by the output type descriptor
     Binary = 0x62,
+    /// Whole result set as a single JSON array
     Json = 0x6a,
+    /// Each element of the result set as a separate JSON value
     JsonElements = 0x4a,
 }
 
+impl IoFormat {
+    /// Whether data in this format is returned as JSON strings
+    pub fn is_json(&self) -> bool {
+        !matches!(self, IoFormat::Binary)
+    }
+}
+
 
 struct Empty;
 impl ClientMessage {
@@ -121,6 +156,7 @@ impl ClientMessage {
             Prepare(h) => encode(buf, 0x50, h),
             DescribeStatement(h) => encode(buf, 0x44, h),
             Execute(h) => encode(buf, 0x45, h),
+            OptimisticExecute(h) => encode(buf, 0x4f, h),
             Dump(h) => encode(buf, 0x3e, h),
             Restore(h) => encode(buf, 0x3c, h),
             RestoreBlock(h) => encode(buf, 0x3d, h),
@@ -151,6 +187,8 @@ impl ClientMessage {
             0x51 => ExecuteScript::decode(&mut data).map(M::ExecuteScript),
             0x50 => Prepare::decode(&mut data).map(M::Prepare),
             0x45 => Execute::decode(&mut data).map(M::Execute),
+            0x4f => OptimisticExecute::decode(&mut data)
+                .map(M::OptimisticExecute),
             0x3e => Dump::decode(&mut data).map(M::Dump),
             0x3c => Restore::decode(&mut data).map(M::Restore),
             0x3d => RestoreBlock::decode(&mut data).map(M::RestoreBlock),
@@ -360,6 +398,11 @@ impl Encode for DescribeStatement {
         buf.reserve(7);
         buf.put_u16(u16::try_from(self.headers.len()).ok()
             .context(errors::TooManyHeaders)?);
+        for (&name, value) in &self.headers {
+            buf.reserve(2);
+            buf.put_u16(name);
+            value.encode(buf)?;
+        }
         buf.reserve(5);
         buf.put_u8(self.aspect as u8);
         self.statement_name.encode(buf)?;
@@ -369,14 +412,14 @@ impl Encode for DescribeStatement {
 
 impl Decode for DescribeStatement {
     fn decode(buf: &mut Cursor<Bytes>) -> Result<Self, DecodeError> {
-        ensure!(buf.remaining() >= 12, errors::Underflow);
+        ensure!(buf.remaining() >= 7, errors::Underflow);
         let num_headers = buf.get_u16();
         let mut headers = HashMap::new();
         for _ in 0..num_headers {
             ensure!(buf.remaining() >= 4, errors::Underflow);
             headers.insert(buf.get_u16(), Bytes::decode(buf)?);
         }
-        ensure!(buf.remaining() >= 8, errors::Underflow);
+        ensure!(buf.remaining() >= 5, errors::Underflow);
         let aspect = match buf.get_u8() {
             0x54 => DescribeAspect::DataDescription,
             c => errors::InvalidAspect { aspect: c }.fail()?,
@@ -427,6 +470,66 @@ impl Decode for Execute {
     }
 }
 
+impl Encode for OptimisticExecute {
+    fn encode(&self, buf: &mut BytesMut)
+        -> Result<(), EncodeError>
+    {
+        buf.reserve(2);
+        buf.put_u16(u16::try_from(self.headers.len()).ok()
+            .context(errors::TooManyHeaders)?);
+        for (&name, value) in &self.headers {
+            buf.reserve(2);
+            buf.put_u16(name);
+            value.encode(buf)?;
+        }
+        buf.reserve(2);
+        buf.put_u8(self.io_format as u8);
+        buf.put_u8(self.expected_cardinality as u8);
+        self.command_text.encode(buf)?;
+        self.input_typedesc_id.encode(buf)?;
+        self.output_typedesc_id.encode(buf)?;
+        self.arguments.encode(buf)?;
+        Ok(())
+    }
+}
+
+impl Decode for OptimisticExecute {
+    fn decode(buf: &mut Cursor<Bytes>) -> Result<Self, DecodeError> {
+        ensure!(buf.remaining() >= 2, errors::Underflow);
+        let num_headers = buf.get_u16();
+        let mut headers = HashMap::new();
+        for _ in 0..num_headers {
+            ensure!(buf.remaining() >= 4, errors::Underflow);
+            headers.insert(buf.get_u16(), Bytes::decode(buf)?);
+        }
+        ensure!(buf.remaining() >= 2, errors::Underflow);
+        let io_format = match buf.get_u8() {
+            0x62 => IoFormat::Binary,
+            0x6a => IoFormat::Json,
+            0x4a => IoFormat::JsonElements,
+            c => errors::InvalidIoFormat { io_format: c }.fail()?,
+        };
+        let expected_cardinality = match buf.get_u8() {
+            0x6f => Cardinality::One,
+            0x6d => Cardinality::Many,
+            c => errors::InvalidCardinality { cardinality: c }.fail()?,
+        };
+        let command_text = String::decode(buf)?;
+        let input_typedesc_id = Uuid::decode(buf)?;
+        let output_typedesc_id = Uuid::decode(buf)?;
+        let arguments = Bytes::decode(buf)?;
+        Ok(OptimisticExecute {
+            headers,
+            io_format,
+            expected_cardinality,
+            command_text,
+            input_typedesc_id,
+            output_typedesc_id,
+            arguments,
+        })
+    }
+}
+
 impl Encode for Dump {
     fn encode(&self, buf: &mut BytesMut)
         -> Result<(), EncodeError>
@@ -445,7 +548,7 @@ impl Encode for Dump {
 
 impl Decode for Dump {
     fn decode(buf: &mut Cursor<Bytes>) -> Result<Self, DecodeError> {
-        ensure!(buf.remaining() >= 12, errors::Underflow);
+        ensure!(buf.remaining() >= 2, errors::Underflow);
         let num_headers = buf.get_u16();
         let mut headers = HashMap::new();
         for _ in 0..num_headers {
@@ -485,6 +588,7 @@ impl Decode for Restore {
             headers.insert(buf.get_u16(), Bytes::decode(buf)?);
         }
 
+        ensure!(buf.remaining() >= 2, errors::Underflow);
         let jobs = buf.get_u16();
 
         let buf_pos = buf.position() as usize;
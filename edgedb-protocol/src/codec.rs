@@ -15,6 +15,9 @@ use crate::errors::{self, CodecError, DecodeError, EncodeError};
 use crate::value::Value;
 use crate::model;
 use crate::serialization::decode::{RawCodec, DecodeTupleLike, DecodeArrayLike};
+use crate::serialization::decode::{decode_range, RANGE_EMPTY, RANGE_NO_LOWER};
+use crate::serialization::decode::{RANGE_INC_LOWER, RANGE_INC_UPPER};
+use crate::serialization::decode::RANGE_NO_UPPER;
 
 pub const STD_UUID: UuidVal = UuidVal::from_u128(0x100);
 pub const STD_STR: UuidVal = UuidVal::from_u128(0x101);
@@ -33,6 +36,10 @@ pub const CAL_LOCAL_TIME: UuidVal = UuidVal::from_u128(0x10d);
 pub const STD_DURATION: UuidVal = UuidVal::from_u128(0x10e);
 pub const STD_JSON: UuidVal = UuidVal::from_u128(0x10f);
 pub const STD_BIGINT: UuidVal = UuidVal::from_u128(0x110);
+pub const PGVECTOR_VECTOR: UuidVal =
+    UuidVal::from_u128(0x9565dd88_04f5_11ee_a691_0b6ebe179825);
+pub const PGVECTOR_HALFVEC: UuidVal =
+    UuidVal::from_u128(0x4ba84534_188e_43b4_a7ce_cea2af0f405b);
 
 
 pub trait Codec: fmt::Debug + Send + Sync + 'static {
@@ -122,6 +129,12 @@ pub struct Bool;
 #[derive(Debug)]
 pub struct Json;
 
+#[derive(Debug)]
+pub struct Vector;
+
+#[derive(Debug)]
+pub struct HalfVector;
+
 #[derive(Debug)]
 pub struct Nothing;
 
@@ -160,6 +173,16 @@ pub struct Array {
 #[derive(Debug)]
 pub struct ArrayAdapter(Array);
 
+#[derive(Debug)]
+pub struct Range {
+    element: Arc<dyn Codec>,
+}
+
+#[derive(Debug)]
+pub struct MultiRange {
+    range: Range,
+}
+
 #[derive(Debug)]
 pub struct Enum {
     members: HashSet<Arc<str>>,
@@ -175,6 +198,12 @@ impl ObjectShape {
     }
 }
 
+impl NamedTupleShape {
+    pub fn new(elements: Vec<TupleElement>) -> NamedTupleShape {
+        NamedTupleShape(Arc::new(NamedTupleShapeInfo { elements }))
+    }
+}
+
 impl Deref for ObjectShape {
     type Target = ObjectShapeInfo;
     fn deref(&self) -> &ObjectShapeInfo {
@@ -212,6 +241,14 @@ impl<'a> CodecBuilder<'a> {
                 D::Enumeration(d) => Ok(Arc::new(Enum {
                     members: d.members.iter().map(|x| x[..].into()).collect(),
                 })),
+                D::Range(d) => Ok(Arc::new(Range {
+                    element: self.build(d.type_pos)?,
+                })),
+                D::MultiRange(d) => Ok(Arc::new(MultiRange {
+                    range: Range {
+                        element: self.build(d.type_pos)?,
+                    },
+                })),
                 // type annotations are stripped from codecs array before
                 // building a codec
                 D::TypeAnnotation(..) => unreachable!(),
@@ -252,6 +289,8 @@ pub fn scalar_codec(uuid: &UuidVal) -> Result<Arc<dyn Codec>, CodecError> {
         STD_DURATION => Ok(Arc::new(Duration {})),
         STD_JSON => Ok(Arc::new(Json {})),
         STD_BIGINT => Ok(Arc::new(BigInt {})),
+        PGVECTOR_VECTOR => Ok(Arc::new(Vector {})),
+        PGVECTOR_HALFVEC => Ok(Arc::new(HalfVector {})),
         _ => return errors::UndefinedBaseScalar { uuid: uuid.clone() }.fail()?,
     }
 }
@@ -812,6 +851,52 @@ impl Codec for Json {
     }
 }
 
+impl Codec for Vector {
+    fn decode(&self, buf: &[u8]) -> Result<Value, DecodeError> {
+        RawCodec::decode(buf).map(Value::Vector)
+    }
+    fn encode(&self, buf: &mut BytesMut, val: &Value)
+        -> Result<(), EncodeError>
+    {
+        let val = match val {
+            Value::Vector(val) => val,
+            _ => Err(errors::invalid_value(type_name::<Self>(), val))?,
+        };
+        let dim = u16::try_from(val.len())
+            .ok().context(errors::VectorTooLong)?;
+        buf.reserve(4 + val.len() * 4);
+        buf.put_u16(dim);
+        buf.put_u16(0);  // reserved
+        for item in val.iter() {
+            buf.put_f32(*item);
+        }
+        Ok(())
+    }
+}
+
+impl Codec for HalfVector {
+    fn decode(&self, buf: &[u8]) -> Result<Value, DecodeError> {
+        RawCodec::decode(buf).map(Value::HalfVector)
+    }
+    fn encode(&self, buf: &mut BytesMut, val: &Value)
+        -> Result<(), EncodeError>
+    {
+        let val = match val {
+            Value::HalfVector(val) => val.as_bits(),
+            _ => Err(errors::invalid_value(type_name::<Self>(), val))?,
+        };
+        let dim = u16::try_from(val.len())
+            .ok().context(errors::VectorTooLong)?;
+        buf.reserve(4 + val.len() * 2);
+        buf.put_u16(dim);
+        buf.put_u16(0);  // reserved
+        for item in val {
+            buf.put_u16(*item);
+        }
+        Ok(())
+    }
+}
+
 impl Codec for Scalar {
     fn decode(&self, buf: &[u8]) -> Result<Value, DecodeError> {
         self.inner.decode(buf)
@@ -935,6 +1020,104 @@ impl Codec for Array {
     }
 }
 
+impl Range {
+    fn decode_range(&self, buf: &[u8])
+        -> Result<model::Range<Box<Value>>, DecodeError>
+    {
+        let range = decode_range(buf)?;
+        Ok(model::Range {
+            lower: range.lower
+                .map(|b| self.element.decode(b).map(Box::new)).transpose()?,
+            upper: range.upper
+                .map(|b| self.element.decode(b).map(Box::new)).transpose()?,
+            inc_lower: range.inc_lower,
+            inc_upper: range.inc_upper,
+            empty: range.empty,
+        })
+    }
+    fn encode_range(&self, buf: &mut BytesMut,
+                    range: &model::Range<Box<Value>>)
+        -> Result<(), EncodeError>
+    {
+        buf.reserve(1);
+        if range.is_empty() {
+            buf.put_u8(RANGE_EMPTY);
+            return Ok(());
+        }
+        let mut flags = 0;
+        if range.inc_lower() {
+            flags |= RANGE_INC_LOWER;
+        }
+        if range.inc_upper() {
+            flags |= RANGE_INC_UPPER;
+        }
+        if range.lower().is_none() {
+            flags |= RANGE_NO_LOWER;
+        }
+        if range.upper().is_none() {
+            flags |= RANGE_NO_UPPER;
+        }
+        buf.put_u8(flags);
+        for bound in range.lower().into_iter().chain(range.upper()) {
+            buf.reserve(4);
+            let pos = buf.len();
+            buf.put_u32(0);  // replaced after serializing a value
+            self.element.encode(buf, bound)?;
+            let len = buf.len()-pos-4;
+            buf[pos..pos+4].copy_from_slice(&u32::try_from(len)
+                    .ok().context(errors::ElementTooLong)?
+                    .to_be_bytes());
+        }
+        Ok(())
+    }
+}
+
+impl Codec for Range {
+    fn decode(&self, buf: &[u8]) -> Result<Value, DecodeError> {
+        self.decode_range(buf).map(Value::Range)
+    }
+    fn encode(&self, buf: &mut BytesMut, val: &Value)
+        -> Result<(), EncodeError>
+    {
+        let range = match val {
+            Value::Range(range) => range,
+            _ => Err(errors::invalid_value(type_name::<Self>(), val))?,
+        };
+        self.encode_range(buf, range)
+    }
+}
+
+impl Codec for MultiRange {
+    fn decode(&self, buf: &[u8]) -> Result<Value, DecodeError> {
+        let ranges = DecodeArrayLike::new_multirange(buf)?
+            .map(|range| self.range.decode_range(range?))
+            .collect::<Result<_, _>>()?;
+        Ok(Value::MultiRange(ranges))
+    }
+    fn encode(&self, buf: &mut BytesMut, val: &Value)
+        -> Result<(), EncodeError>
+    {
+        let ranges = match val {
+            Value::MultiRange(ranges) => ranges,
+            _ => Err(errors::invalid_value(type_name::<Self>(), val))?,
+        };
+        buf.reserve(4);
+        buf.put_u32(ranges.len().try_into().ok()
+            .context(errors::ArrayTooLong)?);
+        for range in ranges {
+            buf.reserve(4);
+            let pos = buf.len();
+            buf.put_u32(0);  // replaced after serializing a value
+            self.range.encode_range(buf, range)?;
+            let len = buf.len()-pos-4;
+            buf[pos..pos+4].copy_from_slice(&u32::try_from(len)
+                    .ok().context(errors::ElementTooLong)?
+                    .to_be_bytes());
+        }
+        Ok(())
+    }
+}
+
 impl Codec for Enum {
     fn decode(&self, buf: &[u8]) -> Result<Value, DecodeError> {
         let val : &str = RawCodec::decode(buf)?;
@@ -1,7 +1,62 @@
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Cardinality {
     NoResult = 0x6e,
     One = 0x6f,
     Many = 0x6d,
 }
 
+
+/// Set of capabilities of a command
+///
+/// Used in the `ALLOW_CAPABILITIES` header of the client messages to
+/// restrict what a command is allowed to do, and in the `CAPABILITIES`
+/// header of the server messages to describe what command does.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Capabilities(u64);
+
+impl Capabilities {
+    pub const NONE: Capabilities = Capabilities(0);
+    pub const MODIFICATIONS: Capabilities = Capabilities(1 << 0);
+    pub const SESSION_CONFIG: Capabilities = Capabilities(1 << 1);
+    pub const TRANSACTION: Capabilities = Capabilities(1 << 2);
+    pub const DDL: Capabilities = Capabilities(1 << 3);
+    pub const PERSISTENT_CONFIG: Capabilities = Capabilities(1 << 4);
+    pub const ALL: Capabilities = Capabilities(!0);
+
+    pub fn from_bits(bits: u64) -> Capabilities {
+        Capabilities(bits)
+    }
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+    pub const fn union(self, other: Capabilities) -> Capabilities {
+        Capabilities(self.0 | other.0)
+    }
+    pub fn contains(self, other: Capabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl std::ops::BitOr for Capabilities {
+    type Output = Capabilities;
+    fn bitor(self, other: Capabilities) -> Capabilities {
+        Capabilities(self.0 | other.0)
+    }
+}
+
+impl std::ops::BitAnd for Capabilities {
+    type Output = Capabilities;
+    fn bitand(self, other: Capabilities) -> Capabilities {
+        Capabilities(self.0 & other.0)
+    }
+}
+
+impl std::ops::Sub for Capabilities {
+    type Output = Capabilities;
+    fn sub(self, other: Capabilities) -> Capabilities {
+        Capabilities(self.0 & !other.0)
+    }
+}
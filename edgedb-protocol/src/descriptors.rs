@@ -25,19 +25,19 @@ pub enum Descriptor {
     NamedTuple(NamedTupleTypeDescriptor),
     Array(ArrayTypeDescriptor),
     Enumeration(EnumerationTypeDescriptor),
+    Range(RangeTypeDescriptor),
+    MultiRange(MultiRangeTypeDescriptor),
     TypeAnnotation(TypeAnnotationDescriptor),
 }
 
 pub struct OutputTypedesc {
     pub(crate) array: Vec<Descriptor>,
-    #[allow(dead_code)] // TODO
     pub(crate) root_id: Uuid,
     pub(crate) root_pos: Option<TypePos>,
 }
 
 pub struct InputTypedesc {
     pub(crate) array: Vec<Descriptor>,
-    #[allow(dead_code)] // TODO
     pub(crate) root_id: Uuid,
     pub(crate) root_pos: TypePos,
 }
@@ -105,6 +105,18 @@ pub struct EnumerationTypeDescriptor {
     pub members: Vec<String>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeTypeDescriptor {
+    pub id: Uuid,
+    pub type_pos: TypePos,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiRangeTypeDescriptor {
+    pub id: Uuid,
+    pub type_pos: TypePos,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TypeAnnotationDescriptor {
     pub annotated_type: u8,
@@ -122,6 +134,10 @@ impl OutputTypedesc {
     pub fn build_codec(&self) -> Result<Arc<dyn Codec>, CodecError> {
         build_codec(self.root_pos(), self.descriptors())
     }
+    /// Type descriptor id of the result, zero if query returns no data
+    pub fn id(&self) -> &Uuid {
+        &self.root_id
+    }
     pub fn root_pos(&self) -> Option<TypePos> {
         self.root_pos
     }
@@ -134,6 +150,10 @@ impl InputTypedesc {
     pub fn build_codec(&self) -> Result<Arc<dyn Codec>, CodecError> {
         build_codec(Some(self.root_pos()), self.descriptors())
     }
+    /// Type descriptor id of the arguments
+    pub fn id(&self) -> &Uuid {
+        &self.root_id
+    }
     pub fn root_pos(&self) -> TypePos {
         self.root_pos
     }
@@ -165,6 +185,8 @@ impl Descriptor {
             NamedTuple(i) => &i.id,
             Array(i) => &i.id,
             Enumeration(i) => &i.id,
+            Range(i) => &i.id,
+            MultiRange(i) => &i.id,
             TypeAnnotation(i) => &i.id,
         }
     }
@@ -186,6 +208,10 @@ impl Decode for Descriptor {
             5 => NamedTupleTypeDescriptor::decode(buf).map(D::NamedTuple),
             6 => ArrayTypeDescriptor::decode(buf).map(D::Array),
             7 => EnumerationTypeDescriptor::decode(buf).map(D::Enumeration),
+            9 => RangeTypeDescriptor::decode(buf).map(D::Range),
+            0x0C => {
+                MultiRangeTypeDescriptor::decode(buf).map(D::MultiRange)
+            }
             0x7F..=0xFF => {
                 TypeAnnotationDescriptor::decode(buf).map(D::TypeAnnotation)
             }
@@ -329,6 +355,26 @@ impl Decode for EnumerationTypeDescriptor {
     }
 }
 
+impl Decode for RangeTypeDescriptor {
+    fn decode(buf: &mut Cursor<Bytes>) -> Result<Self, DecodeError> {
+        ensure!(buf.remaining() >= 19, errors::Underflow);
+        assert!(buf.get_u8() == 9);
+        let id = Uuid::decode(buf)?;
+        let type_pos = TypePos(buf.get_u16());
+        Ok(RangeTypeDescriptor { id, type_pos })
+    }
+}
+
+impl Decode for MultiRangeTypeDescriptor {
+    fn decode(buf: &mut Cursor<Bytes>) -> Result<Self, DecodeError> {
+        ensure!(buf.remaining() >= 19, errors::Underflow);
+        assert!(buf.get_u8() == 0x0C);
+        let id = Uuid::decode(buf)?;
+        let type_pos = TypePos(buf.get_u16());
+        Ok(MultiRangeTypeDescriptor { id, type_pos })
+    }
+}
+
 impl Decode for TypeAnnotationDescriptor {
     fn decode(buf: &mut Cursor<Bytes>) -> Result<Self, DecodeError> {
         ensure!(buf.remaining() >= 21, errors::Underflow);
@@ -103,6 +103,9 @@ pub fn error_name(code: u32) -> &'static str {
         0x_05_03_00_00 => "TransactionError",
         0x_05_03_00_01 => "TransactionSerializationError",
         0x_05_03_00_02 => "TransactionDeadlockError",
+        0x_05_03_01_00 => "TransactionConflictError",
+        0x_05_03_01_01 => "TransactionSerializationError",
+        0x_05_03_01_02 => "TransactionDeadlockError",
         0x_06_00_00_00 => "ConfigurationError",
         0x_07_00_00_00 => "AccessError",
         0x_07_01_00_00 => "AuthenticationError",
@@ -93,6 +93,8 @@ pub enum EncodeError {
     BigIntTooLong { backtrace: Backtrace },
     #[snafu(display("decimal has more than 256Ki digits"))]
     DecimalTooLong { backtrace: Backtrace },
+    #[snafu(display("vector has more than 64Ki dimensions"))]
+    VectorTooLong { backtrace: Backtrace },
     #[snafu(display("unknown message types cannot be encoded"))]
     UnknownMessageCantBeEncoded { backtrace: Backtrace },
     #[snafu(display("trying to encode invalid value type {} with codec {}",
@@ -1,3 +1,8 @@
+//! EdgeDB wire protocol: messages, type descriptors and codecs
+//!
+//! This crate contains no networking code. Messages are encoded into and
+//! decoded from byte buffers, so it can be used with any transport and by
+//! server-side tooling. Connection handling lives in `edgedb-client`.
 mod encoding;
 mod common;
 pub mod serialization;
@@ -10,3 +15,4 @@ pub mod value;
 pub mod codec;
 pub mod queryable;
 pub mod model;
+pub mod state;
@@ -1,10 +1,19 @@
 mod bignum;
 mod time;
 mod json;
+mod vector;
+mod range;
+
+#[cfg(feature = "async-graphql")]
+mod async_graphql_interop;
+#[cfg(feature = "juniper")]
+mod juniper_interop;
 
 pub use self::time::{ LocalDatetime, LocalDate, LocalTime, Duration, Datetime };
 pub use self::bignum:: {BigInt, Decimal};
 pub use self::json::Json;
+pub use self::vector::{Vector, HalfVector};
+pub use self::range::Range;
 pub use uuid::Uuid;
 
 use std::fmt;
@@ -19,6 +28,17 @@ impl fmt::Display for OutOfRangeError {
     }
 }
 
+/// Error parsing a value from string
+#[derive(Debug, PartialEq)]
+pub struct ParseError;
+
+impl std::error::Error for ParseError {}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        "invalid value format".fmt(f)
+    }
+}
+
 impl From<std::num::TryFromIntError> for OutOfRangeError {
     fn from(_: std::num::TryFromIntError) -> OutOfRangeError {
         OutOfRangeError
@@ -0,0 +1,95 @@
+//! GraphQL scalars for use with `async-graphql`
+//!
+//! All the types are represented as strings: `Datetime` in RFC 3339
+//! format, `Duration` as `H:MM:SS[.ffffff]`, `BigInt` as a decimal number
+//! and `Json` as JSON text.
+//!
+//! `Uuid` isn't covered: both the type and the scalar trait are foreign
+//! to this crate, so only `async-graphql` can implement it. Its `uuid` feature
+//! is built for `uuid` 1.x rather than 0.8 used here, so UUIDs should be
+//! returned as strings (e.g. via `Uuid::to_string`).
+use async_graphql::{InputType, InputValueError, InputValueResult};
+use async_graphql::{Scalar, ScalarType};
+use async_graphql::Value;
+
+use crate::model::{BigInt, Datetime, Duration, Json};
+
+
+fn parse_str<T>(value: Value) -> InputValueResult<T>
+    where T: std::str::FromStr + InputType,
+          T::Err: std::fmt::Display,
+{
+    match &value {
+        Value::String(s) => s.parse().map_err(InputValueError::custom),
+        _ => Err(InputValueError::expected_type(value)),
+    }
+}
+
+/// Date and time with time zone in RFC 3339 format
+#[Scalar(name="Datetime")]
+impl ScalarType for Datetime {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        parse_str(value)
+    }
+    fn to_value(&self) -> Value {
+        // debug format is RFC 3339
+        Value::String(format!("{:?}", self))
+    }
+}
+
+/// Duration in `H:MM:SS[.ffffff]` format
+#[Scalar(name="Duration")]
+impl ScalarType for Duration {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        parse_str(value)
+    }
+    fn to_value(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+/// Arbitrary precision integer as a string of decimal digits
+#[Scalar(name="BigInt")]
+impl ScalarType for BigInt {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        parse_str(value)
+    }
+    fn to_value(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+/// JSON document as text
+#[Scalar(name="Json")]
+impl ScalarType for Json {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        match &value {
+            // validated by the database when used in a query
+            Value::String(s) => Ok(Json::new_unchecked(s.clone())),
+            _ => Err(InputValueError::expected_type(value)),
+        }
+    }
+    fn to_value(&self) -> Value {
+        Value::String(String::from(&**self))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let value = Datetime::from_micros(1_234_567_890_123_456);
+        let gql = ScalarType::to_value(&value);
+        assert_eq!(gql, Value::String("2039-02-13T23:31:30.123456Z".into()));
+        assert_eq!(<Datetime as ScalarType>::parse(gql).unwrap(), value);
+
+        let value = BigInt::from(-123_456_789);
+        let gql = ScalarType::to_value(&value);
+        assert_eq!(gql, Value::String("-123456789".into()));
+        assert_eq!(<BigInt as ScalarType>::parse(gql).unwrap(), value);
+
+        assert!(<Duration as ScalarType>::parse(Value::from(1)).is_err());
+    }
+}
@@ -4,6 +4,10 @@ mod num_bigint_interop;
 #[cfg(feature = "bigdecimal")]
 mod bigdecimal_interop;
 
+use std::convert::TryFrom;
+
+use crate::model::ParseError;
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct BigInt {
     pub(crate) negative: bool,
@@ -128,6 +132,39 @@ impl From<i32> for BigInt {
     }
 }
 
+/// Parses decimal integer, optionally prefixed by a sign
+impl std::str::FromStr for BigInt {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<BigInt, ParseError> {
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseError);
+        }
+        // split into groups of 4 decimal digits aligned to the right
+        let first = match digits.len() % 4 {
+            0 => 4,
+            n => n,
+        };
+        let mut groups = vec![digits[..first].parse().unwrap()];
+        for idx in (first..digits.len()).step_by(4) {
+            groups.push(digits[idx..idx+4].parse().unwrap());
+        }
+        let result = BigInt {
+            negative,
+            weight: i16::try_from(groups.len() - 1).map_err(|_| ParseError)?,
+            digits: groups,
+        }.normalize();
+        if result.digits.is_empty() {
+            return Ok(BigInt::from(0u32));
+        }
+        Ok(result)
+    }
+}
+
 impl Decimal {
     #[allow(dead_code)]  // isn't used when BigDecimal is disabled
     fn normalize(mut self) -> Decimal {
@@ -165,7 +202,7 @@ pub(self) mod test_helpers{
 mod test {
     use std::str::FromStr;
     use std::convert::TryFrom;
-    use super::{Decimal, BigInt};
+    use super::{Decimal, BigInt, ParseError};
 
     #[test]
     fn big_int_conversion() {
@@ -233,6 +270,30 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse() {
+        let cases = [
+            0,
+            1,
+            -1,
+            1_0000,
+            -1_0000,
+            1_2345_6789,
+            i64::MAX,
+            i64::MIN,
+        ];
+        for i in cases.iter() {
+            let parsed: BigInt = i.to_string().parse().unwrap();
+            assert_eq!(parsed.to_string(), i.to_string());
+        }
+        assert_eq!("-0".parse(), Ok(BigInt::from(0)));
+        assert_eq!("+000120000".parse(), Ok(BigInt::from(120000)));
+        assert_eq!("-123456789".parse(), Ok(BigInt::from(-123456789)));
+        assert_eq!("".parse::<BigInt>(), Err(ParseError));
+        assert_eq!("-".parse::<BigInt>(), Err(ParseError));
+        assert_eq!("1e5".parse::<BigInt>(), Err(ParseError));
+    }
+
     #[test]
     fn display() {
         let cases = [
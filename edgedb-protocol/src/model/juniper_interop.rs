@@ -0,0 +1,96 @@
+//! GraphQL scalars for use with `juniper`
+//!
+//! All the types are represented as strings: `Datetime` in RFC 3339
+//! format, `Duration` as `H:MM:SS[.ffffff]`, `BigInt` as a decimal number
+//! and `Json` as JSON text.
+//!
+//! `Uuid` isn't covered: both the type and the scalar trait are foreign
+//! to this crate, so only `juniper` can implement it. Its `uuid` feature
+//! is built for `uuid` 0.7 rather than 0.8 used here, so UUIDs should be
+//! returned as strings (e.g. via `Uuid::to_string`).
+use juniper::{graphql_scalar, ParseScalarResult, ParseScalarValue, Value};
+
+use crate::model::{BigInt, Datetime, Duration, Json};
+
+
+graphql_scalar!(Datetime where Scalar = <S> {
+    description: "Date and time with time zone in RFC 3339 format"
+
+    resolve(&self) -> Value {
+        // debug format is RFC 3339
+        Value::scalar(format!("{:?}", self))
+    }
+
+    from_input_value(v: &InputValue) -> Option<Datetime> {
+        v.as_scalar_value::<String>().and_then(|s| s.parse().ok())
+    }
+
+    from_str<'a>(value: ScalarToken<'a>) -> ParseScalarResult<'a, S> {
+        <String as ParseScalarValue<S>>::from_str(value)
+    }
+});
+
+graphql_scalar!(Duration where Scalar = <S> {
+    description: "Duration in `H:MM:SS[.ffffff]` format"
+
+    resolve(&self) -> Value {
+        Value::scalar(self.to_string())
+    }
+
+    from_input_value(v: &InputValue) -> Option<Duration> {
+        v.as_scalar_value::<String>().and_then(|s| s.parse().ok())
+    }
+
+    from_str<'a>(value: ScalarToken<'a>) -> ParseScalarResult<'a, S> {
+        <String as ParseScalarValue<S>>::from_str(value)
+    }
+});
+
+graphql_scalar!(BigInt where Scalar = <S> {
+    description: "Arbitrary precision integer as a string of decimal digits"
+
+    resolve(&self) -> Value {
+        Value::scalar(self.to_string())
+    }
+
+    from_input_value(v: &InputValue) -> Option<BigInt> {
+        v.as_scalar_value::<String>().and_then(|s| s.parse().ok())
+    }
+
+    from_str<'a>(value: ScalarToken<'a>) -> ParseScalarResult<'a, S> {
+        <String as ParseScalarValue<S>>::from_str(value)
+    }
+});
+
+graphql_scalar!(Json where Scalar = <S> {
+    description: "JSON document as text"
+
+    resolve(&self) -> Value {
+        Value::scalar(String::from(&**self))
+    }
+
+    from_input_value(v: &InputValue) -> Option<Json> {
+        // validated by the database when used in a query
+        v.as_scalar_value::<String>().map(|s| Json::new_unchecked(s.clone()))
+    }
+
+    from_str<'a>(value: ScalarToken<'a>) -> ParseScalarResult<'a, S> {
+        <String as ParseScalarValue<S>>::from_str(value)
+    }
+});
+
+#[cfg(test)]
+mod test {
+    use juniper::{DefaultScalarValue, FromInputValue, InputValue};
+    use super::*;
+
+    #[test]
+    fn from_input_value() {
+        let input: InputValue<DefaultScalarValue> =
+            InputValue::scalar("1:02:03.5".to_string());
+        assert_eq!(Duration::from_input_value(&input),
+                   Some(Duration::from_micros(3_723_500_000)));
+        let input: InputValue<DefaultScalarValue> = InputValue::scalar(1);
+        assert_eq!(Duration::from_input_value(&input), None);
+    }
+}
@@ -0,0 +1,119 @@
+/// A value of a `range<T>` type
+///
+/// Missing bound means the range is unbounded on that side. Server returns
+/// ranges in a canonical form: for example, discrete ranges (`range<int64>`)
+/// always have inclusive lower and exclusive upper bound.
+///
+/// Use `Range::from(1..10)` (or `.into()`) to create a range and
+/// `Value::from(range)` to pass it as a query argument.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range<T> {
+    pub(crate) lower: Option<T>,
+    pub(crate) upper: Option<T>,
+    pub(crate) inc_lower: bool,
+    pub(crate) inc_upper: bool,
+    pub(crate) empty: bool,
+}
+
+impl<T> Range<T> {
+    /// An empty range, i.e. one that contains no values
+    pub fn empty() -> Range<T> {
+        Range {
+            lower: None,
+            upper: None,
+            inc_lower: false,
+            inc_upper: false,
+            empty: true,
+        }
+    }
+    /// Range that includes `lower` and excludes `upper` bound
+    pub fn new(lower: Option<T>, upper: Option<T>) -> Range<T> {
+        Range::with_inclusive(lower, upper, true, false)
+    }
+    /// Range with explicitly set inclusiveness of the bounds
+    ///
+    /// Inclusiveness of a missing bound is ignored.
+    pub fn with_inclusive(lower: Option<T>, upper: Option<T>,
+                          inc_lower: bool, inc_upper: bool)
+        -> Range<T>
+    {
+        Range {
+            inc_lower: inc_lower && lower.is_some(),
+            inc_upper: inc_upper && upper.is_some(),
+            lower,
+            upper,
+            empty: false,
+        }
+    }
+    pub fn lower(&self) -> Option<&T> {
+        self.lower.as_ref()
+    }
+    pub fn upper(&self) -> Option<&T> {
+        self.upper.as_ref()
+    }
+    pub fn inc_lower(&self) -> bool {
+        self.inc_lower
+    }
+    pub fn inc_upper(&self) -> bool {
+        self.inc_upper
+    }
+    pub fn is_empty(&self) -> bool {
+        self.empty
+    }
+    /// Convert bounds of the range keeping its shape
+    pub fn map<U, F: FnMut(T) -> U>(self, mut f: F) -> Range<U> {
+        Range {
+            lower: self.lower.map(&mut f),
+            upper: self.upper.map(&mut f),
+            inc_lower: self.inc_lower,
+            inc_upper: self.inc_upper,
+            empty: self.empty,
+        }
+    }
+}
+
+impl<T> From<std::ops::Range<T>> for Range<T> {
+    fn from(range: std::ops::Range<T>) -> Range<T> {
+        Range::new(Some(range.start), Some(range.end))
+    }
+}
+
+impl<T> From<std::ops::RangeInclusive<T>> for Range<T> {
+    fn from(range: std::ops::RangeInclusive<T>) -> Range<T> {
+        let (lower, upper) = range.into_inner();
+        Range::with_inclusive(Some(lower), Some(upper), true, true)
+    }
+}
+
+impl<T> From<std::ops::RangeFrom<T>> for Range<T> {
+    fn from(range: std::ops::RangeFrom<T>) -> Range<T> {
+        Range::new(Some(range.start), None)
+    }
+}
+
+impl<T> From<std::ops::RangeTo<T>> for Range<T> {
+    fn from(range: std::ops::RangeTo<T>) -> Range<T> {
+        Range::new(None, Some(range.end))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Range;
+
+    #[test]
+    fn std_ranges() {
+        let range = Range::from(1..10);
+        assert_eq!((range.lower(), range.upper()), (Some(&1), Some(&10)));
+        assert!(range.inc_lower() && !range.inc_upper());
+        let range = Range::from(1..=10);
+        assert!(range.inc_lower() && range.inc_upper());
+        let range = Range::from(..10);
+        assert_eq!(range.lower(), None);
+        assert!(!range.inc_lower());
+        assert!(!range.is_empty());
+        assert!(Range::<i32>::empty().is_empty());
+        assert_eq!(Range::from(5..).map(i64::from),
+                   Range::new(Some(5i64), None));
+    }
+}
@@ -1,5 +1,6 @@
-use crate::model::OutOfRangeError;
+use crate::model::{OutOfRangeError, ParseError};
 use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
 use std::time::SystemTime;
 use std::fmt::{Debug, Display};
 
@@ -442,6 +443,139 @@ impl std::ops::Add<std::time::Duration> for Datetime {
     }
 }
 
+/// Parse exactly `len` ASCII digits
+fn parse_digits<T: FromStr>(s: &str, len: usize) -> Option<T> {
+    if s.len() != len || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+/// Parse fractional part of a second (up to 6 digits) into microseconds
+fn parse_fraction(s: &str) -> Option<u32> {
+    if s.is_empty() || s.len() > 6 || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let value: u32 = s.parse().ok()?;
+    Some(value * 10u32.pow(6 - s.len() as u32))
+}
+
+/// Parse `MM:SS[.ffffff]` part of the time, returns microseconds
+fn parse_minutes(s: &str) -> Option<u64> {
+    let (ms, micros) = match s.split_once('.') {
+        Some((ms, fract)) => (ms, parse_fraction(fract)?),
+        None => (s, 0),
+    };
+    let (minute, second) = ms.split_once(':')?;
+    let minute: u64 = parse_digits(minute, 2)?;
+    let second: u64 = parse_digits(second, 2)?;
+    if minute >= 60 || second >= 60 {
+        return None;
+    }
+    Some((minute * 60 + second) * 1_000_000 + micros as u64)
+}
+
+/// Parses `HH:MM:SS[.ffffff]`
+impl FromStr for LocalTime {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<LocalTime, ParseError> {
+        let (hour, rest) = s.split_once(':').ok_or(ParseError)?;
+        let hour: u64 = parse_digits(hour, 2).ok_or(ParseError)?;
+        let micros = parse_minutes(rest).ok_or(ParseError)?;
+        LocalTime::try_from_micros(hour * 3_600_000_000 + micros)
+            .map_err(|_| ParseError)
+    }
+}
+
+/// Parses `YYYY-MM-DD`, years before 1 or after 9999 are prefixed by sign
+impl FromStr for LocalDate {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<LocalDate, ParseError> {
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        let mut parts = digits.splitn(3, '-');
+        let year = parts.next().ok_or(ParseError)?;
+        let year: i32 = parse_digits(year, year.len().max(4))
+            .ok_or(ParseError)?;
+        let month = parts.next().and_then(|m| parse_digits(m, 2))
+            .ok_or(ParseError)?;
+        let day = parts.next().and_then(|d| parse_digits(d, 2))
+            .ok_or(ParseError)?;
+        let year = if negative { -year } else { year };
+        LocalDate::try_from_ymd(year, month, day).map_err(|_| ParseError)
+    }
+}
+
+/// Parses `YYYY-MM-DDTHH:MM:SS[.ffffff]` (a space is also allowed instead
+/// of the `T`)
+impl FromStr for LocalDatetime {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<LocalDatetime, ParseError> {
+        let (date, time) = s.split_once(['T', ' '])
+            .ok_or(ParseError)?;
+        let date: LocalDate = date.parse()?;
+        let time: LocalTime = time.parse()?;
+        LocalDatetime::try_from_micros(
+            date.to_days() as i64 * MICROS_PER_DAY as i64
+            + time.to_micros() as i64
+        ).map_err(|_| ParseError)
+    }
+}
+
+/// Parses RFC 3339 timestamps, e.g. `2021-03-15T10:00:00.5+02:00`
+///
+/// Suffix ` UTC` as written by `Display` is also accepted.
+impl FromStr for Datetime {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Datetime, ParseError> {
+        let (local, offset) = if let Some(local) = s.strip_suffix('Z') {
+            (local, 0)
+        } else if let Some(local) = s.strip_suffix(" UTC") {
+            (local, 0)
+        } else {
+            let split = s.len().checked_sub(6)
+                .filter(|&idx| s.is_char_boundary(idx))
+                .ok_or(ParseError)?;
+            let (local, offset) = s.split_at(split);
+            let sign = match offset.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return Err(ParseError),
+            };
+            // `HH:MM` is parsed as `MM:SS`, so scale it by a minute
+            let offset = parse_minutes(&offset[1..]).ok_or(ParseError)? * 60;
+            (local, sign * offset as i64)
+        };
+        let local: LocalDatetime = local.parse()?;
+        Datetime::try_from_micros(local.to_micros() - offset)
+            .map_err(|_| ParseError)
+    }
+}
+
+/// Parses `[-]H:MM:SS[.ffffff]` format written by `Display`
+impl FromStr for Duration {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Duration, ParseError> {
+        let (negative, abs) = match s.strip_prefix('-') {
+            Some(abs) => (true, abs),
+            None => (false, s),
+        };
+        let (hours, rest) = abs.split_once(':').ok_or(ParseError)?;
+        if hours.is_empty() || !hours.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseError);
+        }
+        let hours: i64 = hours.parse().map_err(|_| ParseError)?;
+        let micros = parse_minutes(rest).ok_or(ParseError)? as i64;
+        let abs = hours.checked_mul(3_600_000_000)
+            .and_then(|h| h.checked_add(micros))
+            .ok_or(ParseError)?;
+        Ok(Duration { micros: if negative { -abs } else { abs } })
+    }
+}
+
 impl Display for Duration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let abs = if self.micros < 0 {
@@ -541,7 +675,7 @@ mod test {
         assert_eq!(365, total_days);
     }
 
-    pub const CHRONO_MAX_YEAR : i32 = 262_143;
+    pub const CHRONO_MAX_YEAR : i32 = 262_142;
 
     fn extended_test_dates() -> impl Iterator<Item=(i32, u8, u8)> {
         const YEARS :[i32; 41]= [
@@ -689,6 +823,53 @@ mod test {
         assert_eq!("+294276-12-31T23:59:59.999999Z", to_debug(Datetime::MAX));
     }
 
+    #[test]
+    fn parse_datetime() {
+        for value in [Datetime::MIN, Datetime::MAX, Datetime::UNIX_EPOCH,
+                      Datetime::from_micros(1_234_567_890_123_456)].iter()
+        {
+            assert_eq!(to_debug(value).parse::<Datetime>(), Ok(*value));
+            assert_eq!(value.to_string().parse::<Datetime>(), Ok(*value));
+        }
+        assert_eq!("2039-02-14T01:31:30.5+02:00".parse::<Datetime>(),
+                   Ok(Datetime::from_micros(1_234_567_890_500_000)));
+        assert_eq!("2039-02-13T20:01:30-03:30".parse::<Datetime>(),
+                   Ok(Datetime::from_micros(1_234_567_890_000_000)));
+        for bad in ["", "2039-02-13", "2039-02-13T23:31:30",
+                    "2039-02-13T24:00:00Z", "2039-02-30T00:00:00Z",
+                    "39-02-13T00:00:00Z", "2039-02-13T00:00:00.1234567Z",
+                    "2039-02-13T00:00:00*02:00"].iter()
+        {
+            assert_eq!(bad.parse::<Datetime>(), Err(ParseError), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn parse_local() {
+        assert_eq!("2000-01-01".parse(), Ok(LocalDate::from_days(0)));
+        assert_eq!("-4713-11-24".parse(), Ok(LocalDate::MIN));
+        assert_eq!("+294276-12-31".parse(), Ok(LocalDate::MAX));
+        assert_eq!("23:59:59.999999".parse(), Ok(LocalTime::MAX));
+        assert_eq!("00:00:00".parse(), Ok(LocalTime::MIDNIGHT));
+        assert_eq!("2000-01-01 00:00:01".parse(),
+                   Ok(LocalDatetime::from_micros(1_000_000)));
+        assert_eq!("2000-01-01T00:00:00.001".parse(),
+                   Ok(LocalDatetime::from_micros(1000)));
+    }
+
+    #[test]
+    fn parse_duration() {
+        for micros in [0, 1, -1, 7_015_000, 10_000_000__015_000,
+                       -12_345_678__000_000].iter()
+        {
+            let value = Duration::from_micros(*micros);
+            assert_eq!(value.to_string().parse(), Ok(value));
+        }
+        assert_eq!("1:00".parse::<Duration>(), Err(ParseError));
+        assert_eq!("1:60:00".parse::<Duration>(), Err(ParseError));
+        assert_eq!(":00:00".parse::<Duration>(), Err(ParseError));
+    }
+
     #[test]
     fn format_duration() {
         fn dur_str(msec: i64) -> String {
@@ -732,10 +913,9 @@ mod chrono_interop {
             let timestamp = secs.checked_mul(1_000_000)
                 .and_then(|x| x.checked_add(micros as i64))
                 .ok_or(OutOfRangeError)?;
-            Ok(LocalDatetime {
-                micros: timestamp.checked_add(Datetime::UNIX_EPOCH.micros)
-                    .ok_or(OutOfRangeError)?,
-            })
+            LocalDatetime::try_from_micros(
+                timestamp.checked_add(Datetime::UNIX_EPOCH.micros)
+                    .ok_or(OutOfRangeError)?)
         }
     }
 
@@ -758,11 +938,14 @@ mod chrono_interop {
         type Error = OutOfRangeError;
 
         fn try_from(value:&ChronoDatetime) -> Result<Datetime, Self::Error> {
-           let min = ChronoDatetime::try_from(Datetime::from_micros(i64::MIN)).unwrap();
-           let duration = value.signed_duration_since(min).to_std().map_err(|_| OutOfRangeError)?;
-           let micros = u64::try_from(duration.as_micros()).map_err(|_| OutOfRangeError)?;
-           let micros = i64::MIN.wrapping_add(micros as i64);
-           Ok(Datetime::from_micros(micros))
+            let secs = value.timestamp();
+            let micros = value.timestamp_subsec_micros();
+            let timestamp = secs.checked_mul(1_000_000)
+                .and_then(|x| x.checked_add(micros as i64))
+                .ok_or(OutOfRangeError)?;
+            Datetime::try_from_micros(
+                timestamp.checked_add(Datetime::UNIX_EPOCH.micros)
+                    .ok_or(OutOfRangeError)?)
         }
     }
 
@@ -771,10 +954,9 @@ mod chrono_interop {
         fn try_from(d: &NaiveDate) -> Result<LocalDate, Self::Error>
         {
             let days = chrono::Datelike::num_days_from_ce(d);
-            Ok(LocalDate {
-                days: days.checked_sub(DAYS_IN_2000_YEARS - 365)
-                    .ok_or(OutOfRangeError)?,
-            })
+            LocalDate::try_from_days(
+                days.checked_sub(DAYS_IN_2000_YEARS - 365)
+                    .ok_or(OutOfRangeError)?)
         }
     }
 
@@ -805,6 +987,36 @@ mod chrono_interop {
         }
     }
 
+    impl From<&Duration> for chrono::Duration {
+        fn from(value: &Duration) -> chrono::Duration {
+            chrono::Duration::microseconds(value.micros)
+        }
+    }
+
+    impl TryFrom<&chrono::Duration> for Duration {
+        type Error = OutOfRangeError;
+        fn try_from(value: &chrono::Duration) -> Result<Duration, Self::Error>
+        {
+            // nanoseconds are truncated, like in `From<&NaiveTime>`
+            let micros = value.num_microseconds().ok_or(OutOfRangeError)?;
+            Ok(Duration { micros })
+        }
+    }
+
+    impl From<Duration> for chrono::Duration {
+        fn from(value: Duration) -> chrono::Duration {
+            (&value).into()
+        }
+    }
+
+    impl TryFrom<chrono::Duration> for Duration {
+        type Error = OutOfRangeError;
+        fn try_from(value: chrono::Duration) -> Result<Duration, Self::Error>
+        {
+            std::convert::TryFrom::try_from(&value)
+        }
+    }
+
     impl TryFrom<LocalDatetime> for NaiveDateTime {
         type Error = OutOfRangeError;
         fn try_from(value: LocalDatetime) -> Result<NaiveDateTime, Self::Error> {
@@ -886,9 +1098,32 @@ mod chrono_interop {
             let naive = NaiveTime::from_str("01:02:03.123456")?;
             assert_eq!(naive,
                 TryInto::<NaiveTime>::try_into(LocalTime::try_from(naive)?)?);
+            let dur = chrono::Duration::microseconds(-7_015_000);
+            assert_eq!(dur,
+                chrono::Duration::from(Duration::try_from(dur)?));
+            let utc = ChronoDatetime::from_str("2019-12-27T01:02:03.123456Z")?;
+            assert_eq!(utc,
+                ChronoDatetime::try_from(Datetime::try_from(utc)?)?);
             Ok(())
         }
 
+        #[test]
+        fn chrono_range() {
+            use chrono::TimeZone;
+
+            let date = NaiveDate::from_ymd_opt(-5000, 1, 1).unwrap();
+            let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+            assert!(LocalDate::try_from(date).is_err());
+            assert!(LocalDatetime::try_from(midnight).is_err());
+            let utc = chrono::Utc.from_utc_datetime(&midnight);
+            assert!(Datetime::try_from(utc).is_err());
+            let days = chrono::Duration::days(i64::MAX / 86_400_000);
+            assert!(Duration::try_from(days).is_err());
+
+            let date = NaiveDate::from_ymd_opt(-4713, 11, 24).unwrap();
+            assert_eq!(LocalDate::try_from(date), Ok(LocalDate::MIN));
+        }
+
         fn check_display<E:Display, A:Display>(expected_value:E, actual_value:A) {
             let expected_display = expected_value.to_string();
             let actual_display = actual_value.to_string();
@@ -958,3 +1193,234 @@ mod chrono_interop {
         }
     }
 }
+
+#[cfg(feature = "time")]
+mod time_interop {
+    use super::*;
+    use std::convert::{From, Into, TryFrom, TryInto};
+    use time::{Date, OffsetDateTime, PrimitiveDateTime, Time};
+
+    // julian day of 2000-01-01, epoch of `LocalDate`
+    const JULIAN_DAY_2000: i32 = 2_451_545;
+
+    impl TryFrom<&LocalDate> for Date {
+        type Error = OutOfRangeError;
+        fn try_from(value: &LocalDate) -> Result<Date, Self::Error> {
+            value.days.checked_add(JULIAN_DAY_2000)
+                .and_then(|day| Date::from_julian_day(day).ok())
+                .ok_or(OutOfRangeError)
+        }
+    }
+
+    impl TryFrom<&Date> for LocalDate {
+        type Error = OutOfRangeError;
+        fn try_from(d: &Date) -> Result<LocalDate, Self::Error> {
+            LocalDate::try_from_days(d.to_julian_day() - JULIAN_DAY_2000)
+        }
+    }
+
+    impl From<&LocalTime> for Time {
+        fn from(value: &LocalTime) -> Time {
+            let (hour, minute, second, micro) = value.to_hmsu();
+            Time::from_hms_micro(hour, minute, second, micro)
+                .expect("LocalTime is always a valid time")
+        }
+    }
+
+    impl From<&Time> for LocalTime {
+        fn from(time: &Time) -> LocalTime {
+            // nanoseconds are truncated, like in `From<&NaiveTime>`
+            let (hour, minute, second, micro) = time.as_hms_micro();
+            let seconds = (hour as u64 * 60 + minute as u64) * 60
+                + second as u64;
+            LocalTime {
+                micros: seconds * 1_000_000 + micro as u64,
+            }
+        }
+    }
+
+    impl TryFrom<&LocalDatetime> for PrimitiveDateTime {
+        type Error = OutOfRangeError;
+        fn try_from(value: &LocalDatetime)
+            -> Result<PrimitiveDateTime, Self::Error>
+        {
+            let date = Date::try_from(&value.date())?;
+            Ok(PrimitiveDateTime::new(date, Time::from(&value.time())))
+        }
+    }
+
+    impl TryFrom<&PrimitiveDateTime> for LocalDatetime {
+        type Error = OutOfRangeError;
+        fn try_from(d: &PrimitiveDateTime)
+            -> Result<LocalDatetime, Self::Error>
+        {
+            let date = LocalDate::try_from(&d.date())?;
+            Ok(LocalDatetime::new(date, LocalTime::from(&d.time())))
+        }
+    }
+
+    impl TryFrom<&Datetime> for OffsetDateTime {
+        type Error = OutOfRangeError;
+        fn try_from(value: &Datetime) -> Result<OffsetDateTime, Self::Error> {
+            value.micros.checked_sub(Datetime::UNIX_EPOCH.micros)
+                .and_then(|unix_micros| OffsetDateTime::UNIX_EPOCH
+                    .checked_add(time::Duration::microseconds(unix_micros)))
+                .ok_or(OutOfRangeError)
+        }
+    }
+
+    impl TryFrom<&OffsetDateTime> for Datetime {
+        type Error = OutOfRangeError;
+        fn try_from(value: &OffsetDateTime) -> Result<Datetime, Self::Error> {
+            let unix_micros = (*value - OffsetDateTime::UNIX_EPOCH)
+                .whole_microseconds();
+            i64::try_from(unix_micros).ok()
+                .and_then(|x| x.checked_add(Datetime::UNIX_EPOCH.micros))
+                .ok_or(OutOfRangeError)
+                .and_then(Datetime::try_from_micros)
+        }
+    }
+
+    impl From<&Duration> for time::Duration {
+        fn from(value: &Duration) -> time::Duration {
+            time::Duration::microseconds(value.micros)
+        }
+    }
+
+    impl TryFrom<&time::Duration> for Duration {
+        type Error = OutOfRangeError;
+        fn try_from(value: &time::Duration) -> Result<Duration, Self::Error> {
+            // nanoseconds are truncated, like in `From<&Time>`
+            let micros = i64::try_from(value.whole_microseconds())
+                .map_err(|_| OutOfRangeError)?;
+            Ok(Duration { micros })
+        }
+    }
+
+    impl TryFrom<LocalDate> for Date {
+        type Error = OutOfRangeError;
+        fn try_from(value: LocalDate) -> Result<Date, Self::Error> {
+            (&value).try_into()
+        }
+    }
+
+    impl TryFrom<Date> for LocalDate {
+        type Error = OutOfRangeError;
+        fn try_from(d: Date) -> Result<LocalDate, Self::Error> {
+            std::convert::TryFrom::try_from(&d)
+        }
+    }
+
+    impl From<LocalTime> for Time {
+        fn from(value: LocalTime) -> Time {
+            (&value).into()
+        }
+    }
+
+    impl From<Time> for LocalTime {
+        fn from(time: Time) -> LocalTime {
+            From::from(&time)
+        }
+    }
+
+    impl TryFrom<LocalDatetime> for PrimitiveDateTime {
+        type Error = OutOfRangeError;
+        fn try_from(value: LocalDatetime)
+            -> Result<PrimitiveDateTime, Self::Error>
+        {
+            (&value).try_into()
+        }
+    }
+
+    impl TryFrom<PrimitiveDateTime> for LocalDatetime {
+        type Error = OutOfRangeError;
+        fn try_from(d: PrimitiveDateTime)
+            -> Result<LocalDatetime, Self::Error>
+        {
+            std::convert::TryFrom::try_from(&d)
+        }
+    }
+
+    impl TryFrom<Datetime> for OffsetDateTime {
+        type Error = OutOfRangeError;
+        fn try_from(value: Datetime) -> Result<OffsetDateTime, Self::Error> {
+            (&value).try_into()
+        }
+    }
+
+    impl TryFrom<OffsetDateTime> for Datetime {
+        type Error = OutOfRangeError;
+        fn try_from(d: OffsetDateTime) -> Result<Datetime, Self::Error> {
+            std::convert::TryFrom::try_from(&d)
+        }
+    }
+
+    impl From<Duration> for time::Duration {
+        fn from(value: Duration) -> time::Duration {
+            (&value).into()
+        }
+    }
+
+    impl TryFrom<time::Duration> for Duration {
+        type Error = OutOfRangeError;
+        fn try_from(value: time::Duration) -> Result<Duration, Self::Error> {
+            std::convert::TryFrom::try_from(&value)
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+        use crate::model::time::test::test_times;
+        use std::convert::{TryFrom, TryInto};
+        use time::Month;
+
+        #[test]
+        fn time_roundtrips() -> Result<(), Box<dyn std::error::Error>> {
+            let date = Date::from_calendar_date(2019, Month::December, 27)?;
+            let local = LocalDate::try_from(date)?;
+            assert_eq!(local, LocalDate::from_ymd(2019, 12, 27));
+            assert_eq!(date, TryInto::<Date>::try_into(local)?);
+            let time = Time::from_hms_micro(1, 2, 3, 123_456)?;
+            assert_eq!(time, Time::from(LocalTime::from(time)));
+            let naive = PrimitiveDateTime::new(date, time);
+            assert_eq!(naive,
+                TryInto::<PrimitiveDateTime>::try_into(
+                    LocalDatetime::try_from(naive)?)?);
+            assert_eq!(LocalDatetime::try_from(naive)?.to_string(),
+                       "2019-12-27 01:02:03.123456");
+            let utc = naive.assume_utc();
+            let datetime = Datetime::try_from(utc)?;
+            assert_eq!(datetime.to_string(), "2019-12-27 01:02:03.123456 UTC");
+            assert_eq!(utc, OffsetDateTime::try_from(datetime)?);
+            let dur = time::Duration::microseconds(-7_015_000);
+            assert_eq!(dur, time::Duration::from(Duration::try_from(dur)?));
+            for micros in test_times() {
+                let local = LocalTime::from_micros(micros);
+                assert_eq!(local, LocalTime::from(Time::from(local)));
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn time_range() {
+            let date = Date::from_calendar_date(-5000, Month::January, 1)
+                .unwrap();
+            let midnight = date.midnight();
+            assert!(LocalDate::try_from(date).is_err());
+            assert!(LocalDatetime::try_from(midnight).is_err());
+            assert!(Datetime::try_from(midnight.assume_utc()).is_err());
+            assert!(Duration::try_from(time::Duration::MAX).is_err());
+
+            // `time` supports years up to 9999 only
+            assert!(Date::try_from(LocalDate::MAX).is_err());
+            assert!(PrimitiveDateTime::try_from(LocalDatetime::MAX).is_err());
+            assert!(OffsetDateTime::try_from(Datetime::MAX).is_err());
+
+            let date = Date::from_calendar_date(-4713, Month::November, 24)
+                .unwrap();
+            assert_eq!(LocalDate::try_from(date), Ok(LocalDate::MIN));
+            assert_eq!(Date::try_from(LocalDate::MIN), Ok(date));
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::ops::Deref;
+
+
+/// A value of the `ext::pgvector::vector` type
+///
+/// Use `Vec::from(vector)` (or `.into()`) to get the elements, and
+/// `Value::Vector` to pass a vector as a query argument.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Vector(pub Vec<f32>);
+
+impl Deref for Vector {
+    type Target = [f32];
+    fn deref(&self) -> &[f32] {
+        &self.0
+    }
+}
+
+impl AsRef<[f32]> for Vector {
+    fn as_ref(&self) -> &[f32] {
+        &self.0
+    }
+}
+
+impl From<Vec<f32>> for Vector {
+    fn from(value: Vec<f32>) -> Vector {
+        Vector(value)
+    }
+}
+
+impl From<&[f32]> for Vector {
+    fn from(value: &[f32]) -> Vector {
+        Vector(value.to_vec())
+    }
+}
+
+impl From<Vector> for Vec<f32> {
+    fn from(value: Vector) -> Vec<f32> {
+        value.0
+    }
+}
+
+#[cfg(feature="with-ndarray")]
+impl From<Vector> for ndarray::Array1<f32> {
+    fn from(value: Vector) -> ndarray::Array1<f32> {
+        ndarray::Array1::from(value.0)
+    }
+}
+
+#[cfg(feature="with-ndarray")]
+impl From<ndarray::ArrayView1<'_, f32>> for Vector {
+    fn from(value: ndarray::ArrayView1<'_, f32>) -> Vector {
+        Vector(value.to_vec())
+    }
+}
+
+#[cfg(feature="with-ndarray")]
+impl From<ndarray::Array1<f32>> for Vector {
+    fn from(value: ndarray::Array1<f32>) -> Vector {
+        Vector(value.to_vec())
+    }
+}
+
+/// A value of the `ext::pgvector::halfvec` type
+///
+/// Elements are stored as raw IEEE 754 half-precision bits, so they are
+/// sent to the server without per-element conversion. Enable the
+/// `with-half` feature to convert from and to slices of `half::f16`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HalfVector(Vec<u16>);
+
+impl HalfVector {
+    /// Create vector from the raw bits of half-precision floats
+    ///
+    /// Bits can be taken from any `bytemuck`-compatible buffer of half
+    /// floats (e.g. with `bytemuck::cast_slice`) without conversion.
+    pub fn from_bits(bits: Vec<u16>) -> HalfVector {
+        HalfVector(bits)
+    }
+    /// Raw bits of half-precision elements
+    pub fn as_bits(&self) -> &[u16] {
+        &self.0
+    }
+    pub fn into_bits(self) -> Vec<u16> {
+        self.0
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[cfg(feature="with-half")]
+impl From<&[half::f16]> for HalfVector {
+    fn from(value: &[half::f16]) -> HalfVector {
+        HalfVector(value.iter().map(|x| x.to_bits()).collect())
+    }
+}
+
+#[cfg(feature="with-half")]
+impl From<Vec<half::f16>> for HalfVector {
+    fn from(value: Vec<half::f16>) -> HalfVector {
+        HalfVector::from(&value[..])
+    }
+}
+
+#[cfg(feature="with-half")]
+impl From<HalfVector> for Vec<half::f16> {
+    fn from(value: HalfVector) -> Vec<half::f16> {
+        value.0.into_iter().map(half::f16::from_bits).collect()
+    }
+}
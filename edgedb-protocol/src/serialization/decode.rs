@@ -5,3 +5,7 @@ pub(crate) mod queryable;
 pub use self::raw_composite::DecodeTupleLike;
 pub(crate) use self::raw_scalar::RawCodec;
 pub(crate) use self::raw_composite::DecodeArrayLike;
+pub(crate) use self::raw_composite::decode_range;
+pub(crate) use self::raw_composite::{RANGE_EMPTY, RANGE_NO_LOWER};
+pub(crate) use self::raw_composite::{RANGE_INC_LOWER, RANGE_INC_UPPER};
+pub(crate) use self::raw_composite::RANGE_NO_UPPER;
@@ -2,7 +2,8 @@ use crate::queryable::{Queryable, Decoder, DescriptorContext};
 use crate::queryable::{DescriptorMismatch};
 use crate::errors::DecodeError;
 use crate::descriptors::{Descriptor, TypePos};
-use crate::serialization::decode::DecodeArrayLike;
+use crate::serialization::decode::{DecodeArrayLike, decode_range};
+use crate::model::Range;
 use std::iter::FromIterator;
 
 
@@ -76,3 +77,27 @@ impl<T:Queryable> Queryable for Vec<T> {
         Collection::<Vec<T>>::check_descriptor(ctx, type_pos)
     }
 }
+
+impl<T:Queryable> Queryable for Range<T> {
+    fn decode(decoder: &Decoder, buf: &[u8]) -> Result<Self, DecodeError> {
+        let range = decode_range(buf)?;
+        Ok(Range {
+            lower: range.lower.map(|b| T::decode(decoder, b)).transpose()?,
+            upper: range.upper.map(|b| T::decode(decoder, b)).transpose()?,
+            inc_lower: range.inc_lower,
+            inc_upper: range.inc_upper,
+            empty: range.empty,
+        })
+    }
+
+    fn check_descriptor(ctx: &DescriptorContext, type_pos: TypePos)
+        -> Result<(), DescriptorMismatch>
+    {
+        let desc = ctx.get(type_pos)?;
+        let element_type_pos = match desc {
+            Descriptor::Range(desc) => desc.type_pos,
+            _ => return Err(ctx.wrong_type(desc, "range"))
+        };
+        T::check_descriptor(ctx, element_type_pos)
+    }
+}
@@ -5,7 +5,7 @@ use crate::errors::DecodeError;
 use crate::codec;
 use crate::descriptors::TypePos;
 use crate::model::{Duration, LocalDate, LocalTime, LocalDatetime, Datetime};
-use crate::model::{Json, Uuid, BigInt, Decimal};
+use crate::model::{Json, Uuid, BigInt, Decimal, Vector, HalfVector};
 use crate::serialization::decode::RawCodec;
 use std::time::SystemTime;
 
@@ -51,6 +51,16 @@ impl DecodeScalar for Json {
     fn typename() -> &'static str { "std::json" }
 }
 
+impl DecodeScalar for Vector {
+    fn uuid() -> Uuid { codec::PGVECTOR_VECTOR }
+    fn typename() -> &'static str { "ext::pgvector::vector" }
+}
+
+impl DecodeScalar for HalfVector {
+    fn uuid() -> Uuid { codec::PGVECTOR_HALFVEC }
+    fn typename() -> &'static str { "ext::pgvector::halfvec" }
+}
+
 /*
 impl DecodeScalar for Vec<u8> {
     fn uuid() -> Uuid { codec::STD_BYTES }
@@ -103,6 +113,18 @@ impl DecodeScalar for Decimal {
     fn typename() -> &'static str { "std::decimal" }
 }
 
+#[cfg(feature="num-bigint")]
+impl DecodeScalar for num_bigint::BigInt {
+    fn uuid() -> Uuid { codec::STD_BIGINT }
+    fn typename() -> &'static str { "std::bigint" }
+}
+
+#[cfg(feature="bigdecimal")]
+impl DecodeScalar for bigdecimal::BigDecimal {
+    fn uuid() -> Uuid { codec::STD_DECIMAL }
+    fn typename() -> &'static str { "std::decimal" }
+}
+
 impl DecodeScalar for LocalDatetime {
     fn uuid() -> Uuid { codec::CAL_LOCAL_DATETIME }
     fn typename() -> &'static str { "cal::local_datetime" }
@@ -123,6 +145,66 @@ impl DecodeScalar for Duration {
     fn typename() -> &'static str { "std::duration" }
 }
 
+#[cfg(feature="chrono")]
+impl DecodeScalar for chrono::NaiveDateTime {
+    fn uuid() -> Uuid { codec::CAL_LOCAL_DATETIME }
+    fn typename() -> &'static str { "cal::local_datetime" }
+}
+
+#[cfg(feature="chrono")]
+impl DecodeScalar for chrono::NaiveDate {
+    fn uuid() -> Uuid { codec::CAL_LOCAL_DATE }
+    fn typename() -> &'static str { "cal::local_date" }
+}
+
+#[cfg(feature="chrono")]
+impl DecodeScalar for chrono::NaiveTime {
+    fn uuid() -> Uuid { codec::CAL_LOCAL_TIME }
+    fn typename() -> &'static str { "cal::local_time" }
+}
+
+#[cfg(feature="chrono")]
+impl DecodeScalar for chrono::DateTime<chrono::Utc> {
+    fn uuid() -> Uuid { codec::STD_DATETIME }
+    fn typename() -> &'static str { "std::datetime" }
+}
+
+#[cfg(feature="chrono")]
+impl DecodeScalar for chrono::Duration {
+    fn uuid() -> Uuid { codec::STD_DURATION }
+    fn typename() -> &'static str { "std::duration" }
+}
+
+#[cfg(feature="time")]
+impl DecodeScalar for time::PrimitiveDateTime {
+    fn uuid() -> Uuid { codec::CAL_LOCAL_DATETIME }
+    fn typename() -> &'static str { "cal::local_datetime" }
+}
+
+#[cfg(feature="time")]
+impl DecodeScalar for time::Date {
+    fn uuid() -> Uuid { codec::CAL_LOCAL_DATE }
+    fn typename() -> &'static str { "cal::local_date" }
+}
+
+#[cfg(feature="time")]
+impl DecodeScalar for time::Time {
+    fn uuid() -> Uuid { codec::CAL_LOCAL_TIME }
+    fn typename() -> &'static str { "cal::local_time" }
+}
+
+#[cfg(feature="time")]
+impl DecodeScalar for time::OffsetDateTime {
+    fn uuid() -> Uuid { codec::STD_DATETIME }
+    fn typename() -> &'static str { "std::datetime" }
+}
+
+#[cfg(feature="time")]
+impl DecodeScalar for time::Duration {
+    fn uuid() -> Uuid { codec::STD_DURATION }
+    fn typename() -> &'static str { "std::duration" }
+}
+
 impl DecodeScalar for SystemTime {
     fn uuid() -> Uuid { codec::STD_DATETIME }
     fn typename() -> &'static str { "std::datetime" }
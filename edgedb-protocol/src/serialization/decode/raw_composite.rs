@@ -1,7 +1,15 @@
 use crate::errors::{self, DecodeError};
+use crate::model::Range;
 use snafu::ensure;
+use bytes::Buf;
 use self::inner::DecodeCompositeInner;
 
+pub const RANGE_EMPTY: u8 = 0x01;
+pub const RANGE_INC_LOWER: u8 = 0x02;
+pub const RANGE_INC_UPPER: u8 = 0x04;
+pub const RANGE_NO_LOWER: u8 = 0x08;
+pub const RANGE_NO_UPPER: u8 = 0x10;
+
 
 pub struct DecodeTupleLike<'t> {
     inner:DecodeCompositeInner<'t>
@@ -54,6 +62,38 @@ impl<'t> DecodeArrayLike<'t> {
         let inner = DecodeCompositeInner::read_array_like_header(buf, || errors::InvalidArrayOrSetShape.build())?;
         Ok(DecodeArrayLike{inner})
     }
+
+    pub fn new_multirange(buf:&'t [u8]) -> Result<Self, DecodeError> {
+        let inner = DecodeCompositeInner::read_tuple_like_header(buf)?;
+        Ok(DecodeArrayLike{inner})
+    }
+}
+
+/// Split range into its (yet undecoded) bounds
+pub fn decode_range(mut buf: &[u8]) -> Result<Range<&[u8]>, DecodeError> {
+    ensure!(buf.remaining() >= 1, errors::Underflow);
+    let flags = buf.get_u8();
+    if flags & RANGE_EMPTY != 0 {
+        ensure!(buf.remaining() == 0, errors::ExtraData);
+        return Ok(Range::empty());
+    }
+    let mut bound = |missing: bool| -> Result<_, DecodeError> {
+        if missing {
+            return Ok(None);
+        }
+        ensure!(buf.remaining() >= 4, errors::Underflow);
+        let len = buf.get_u32() as usize;
+        ensure!(buf.remaining() >= len, errors::Underflow);
+        let (bound, rest) = buf.split_at(len);
+        buf = rest;
+        Ok(Some(bound))
+    };
+    let lower = bound(flags & RANGE_NO_LOWER != 0)?;
+    let upper = bound(flags & RANGE_NO_UPPER != 0)?;
+    ensure!(buf.remaining() == 0, errors::ExtraData);
+    Ok(Range::with_inclusive(lower, upper,
+                             flags & RANGE_INC_LOWER != 0,
+                             flags & RANGE_INC_UPPER != 0))
 }
 
 impl<'t> Iterator for DecodeArrayLike<'t> {
@@ -5,7 +5,7 @@ use std::time::SystemTime;
 use bytes::Buf;
 
 use crate::errors::{self, DecodeError};
-use crate::model::{Json, Uuid};
+use crate::model::{Json, Uuid, Vector, HalfVector};
 use snafu::{ResultExt, ensure};
 use crate::model::{BigInt, Decimal};
 use crate::model::{Duration, LocalDate, LocalTime, LocalDatetime, Datetime};
@@ -51,6 +51,36 @@ impl<'t> RawCodec<'t> for Json {
     }
 }
 
+impl<'t> RawCodec<'t> for Vector {
+    fn decode(mut buf: &[u8]) -> Result<Self, DecodeError> {
+        ensure!(buf.remaining() >= 4, errors::Underflow);
+        let dim = buf.get_u16() as usize;
+        let reserved = buf.get_u16();
+        ensure!(reserved == 0, errors::NonZeroReservedBytes);
+        ensure_exact_size(buf, dim * size_of::<f32>())?;
+        let mut items = Vec::with_capacity(dim);
+        for _ in 0..dim {
+            items.push(buf.get_f32());
+        }
+        Ok(Vector(items))
+    }
+}
+
+impl<'t> RawCodec<'t> for HalfVector {
+    fn decode(mut buf: &[u8]) -> Result<Self, DecodeError> {
+        ensure!(buf.remaining() >= 4, errors::Underflow);
+        let dim = buf.get_u16() as usize;
+        let reserved = buf.get_u16();
+        ensure!(reserved == 0, errors::NonZeroReservedBytes);
+        ensure_exact_size(buf, dim * size_of::<u16>())?;
+        let mut items = Vec::with_capacity(dim);
+        for _ in 0..dim {
+            items.push(buf.get_u16());
+        }
+        Ok(HalfVector::from_bits(items))
+    }
+}
+
 impl<'t> RawCodec<'t> for Uuid {
     fn decode(buf: &[u8]) -> Result<Self, DecodeError> {
         ensure_exact_size(buf, 16)?;
@@ -163,6 +193,20 @@ impl<'t> RawCodec<'t> for BigInt {
     }
 }
 
+#[cfg(feature="num-bigint")]
+impl<'t> RawCodec<'t> for num_bigint::BigInt {
+    fn decode(buf: &[u8]) -> Result<Self, DecodeError> {
+        Ok(<BigInt as RawCodec>::decode(buf)?.into())
+    }
+}
+
+#[cfg(feature="bigdecimal")]
+impl<'t> RawCodec<'t> for bigdecimal::BigDecimal {
+    fn decode(buf: &[u8]) -> Result<Self, DecodeError> {
+        Ok(<Decimal as RawCodec>::decode(buf)?.into())
+    }
+}
+
 impl<'t> RawCodec<'t> for Duration {
     fn decode(mut buf: &[u8]) -> Result<Self, DecodeError> {
         ensure_exact_size(buf, 16)?;
@@ -174,6 +218,98 @@ impl<'t> RawCodec<'t> for Duration {
     }
 }
 
+#[cfg(feature="chrono")]
+mod chrono_interop {
+    use std::convert::TryInto;
+
+    use chrono::naive::{NaiveDate, NaiveDateTime, NaiveTime};
+
+    use crate::errors::{DecodeError, decode_error};
+    use crate::model::{Duration, LocalDate, LocalTime, LocalDatetime};
+    use crate::model::Datetime;
+    use crate::serialization::decode::RawCodec;
+
+    type ChronoDatetime = chrono::DateTime<chrono::Utc>;
+
+    impl<'t> RawCodec<'t> for NaiveDateTime {
+        fn decode(buf: &[u8]) -> Result<Self, DecodeError> {
+            <LocalDatetime as RawCodec>::decode(buf)?
+                .try_into().map_err(decode_error)
+        }
+    }
+
+    impl<'t> RawCodec<'t> for NaiveDate {
+        fn decode(buf: &[u8]) -> Result<Self, DecodeError> {
+            <LocalDate as RawCodec>::decode(buf)?
+                .try_into().map_err(decode_error)
+        }
+    }
+
+    impl<'t> RawCodec<'t> for NaiveTime {
+        fn decode(buf: &[u8]) -> Result<Self, DecodeError> {
+            Ok(<LocalTime as RawCodec>::decode(buf)?.into())
+        }
+    }
+
+    impl<'t> RawCodec<'t> for ChronoDatetime {
+        fn decode(buf: &[u8]) -> Result<Self, DecodeError> {
+            <Datetime as RawCodec>::decode(buf)?
+                .try_into().map_err(decode_error)
+        }
+    }
+
+    impl<'t> RawCodec<'t> for chrono::Duration {
+        fn decode(buf: &[u8]) -> Result<Self, DecodeError> {
+            Ok(<Duration as RawCodec>::decode(buf)?.into())
+        }
+    }
+}
+
+#[cfg(feature="time")]
+mod time_interop {
+    use std::convert::TryInto;
+
+    use time::{Date, OffsetDateTime, PrimitiveDateTime, Time};
+
+    use crate::errors::{DecodeError, decode_error};
+    use crate::model::{Duration, LocalDate, LocalTime, LocalDatetime};
+    use crate::model::Datetime;
+    use crate::serialization::decode::RawCodec;
+
+    impl<'t> RawCodec<'t> for PrimitiveDateTime {
+        fn decode(buf: &[u8]) -> Result<Self, DecodeError> {
+            <LocalDatetime as RawCodec>::decode(buf)?
+                .try_into().map_err(decode_error)
+        }
+    }
+
+    impl<'t> RawCodec<'t> for Date {
+        fn decode(buf: &[u8]) -> Result<Self, DecodeError> {
+            <LocalDate as RawCodec>::decode(buf)?
+                .try_into().map_err(decode_error)
+        }
+    }
+
+    impl<'t> RawCodec<'t> for Time {
+        fn decode(buf: &[u8]) -> Result<Self, DecodeError> {
+            Ok(<LocalTime as RawCodec>::decode(buf)?.into())
+        }
+    }
+
+    impl<'t> RawCodec<'t> for OffsetDateTime {
+        fn decode(buf: &[u8]) -> Result<Self, DecodeError> {
+            <Datetime as RawCodec>::decode(buf)?
+                .try_into().map_err(decode_error)
+        }
+    }
+
+    impl<'t> RawCodec<'t> for time::Duration {
+        fn decode(buf: &[u8]) -> Result<Self, DecodeError> {
+            Ok(<Duration as RawCodec>::decode(buf)?.into())
+        }
+    }
+}
+
 impl<'t> RawCodec<'t> for SystemTime {
     fn decode(buf: &[u8]) -> Result<Self, DecodeError> {
         let micros = i64::decode(buf)?;
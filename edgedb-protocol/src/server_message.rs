@@ -11,7 +11,7 @@ use snafu::{OptionExt, ensure};
 use crate::errors::{self, EncodeError, DecodeError};
 use crate::encoding::{Headers, Decode, Encode};
 use crate::descriptors::{OutputTypedesc, InputTypedesc, Descriptor, TypePos};
-pub use crate::common::Cardinality;
+pub use crate::common::{Cardinality, Capabilities};
 
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -171,6 +171,24 @@ fn encode<T: Encode>(buf: &mut BytesMut, code: u8, msg: &T)
 }
 
 impl CommandDataDescription {
+    /// Serialize description, e.g. to store it for offline use
+    ///
+    /// The format is the payload of the protocol message, so it's as
+    /// stable as the protocol itself.
+    pub fn to_bytes(&self) -> Result<Bytes, EncodeError> {
+        let mut buf = BytesMut::new();
+        self.encode(&mut buf)?;
+        Ok(buf.freeze())
+    }
+    /// Deserialize description stored with [`to_bytes`](Self::to_bytes)
+    pub fn from_bytes(data: Bytes)
+        -> Result<CommandDataDescription, DecodeError>
+    {
+        let mut cur = Cursor::new(data);
+        let result = CommandDataDescription::decode(&mut cur)?;
+        ensure!(cur.remaining() == 0, errors::ExtraData);
+        Ok(result)
+    }
     pub fn output(&self) -> Result<OutputTypedesc, DecodeError> {
         let mut cur = Cursor::new(self.output_typedesc.clone());
         let mut descriptors = Vec::new();
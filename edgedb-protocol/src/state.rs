@@ -0,0 +1,226 @@
+//! Session state: global variables and configuration
+use std::time::Duration;
+
+use crate::value::Value;
+
+
+/// Default module used for global names that are not qualified
+pub const DEFAULT_MODULE: &str = "default";
+
+/// A set of global variable values that can be applied to a session
+///
+/// Usually implemented using `#[derive(GlobalsDelta)]` from the
+/// `edgedb-derive` crate:
+///
+/// ```rust,ignore
+/// #[derive(GlobalsDelta)]
+/// #[edgedb(module="app")]
+/// struct Globals {
+///     current_user_id: Uuid,            // app::current_user_id
+///     #[edgedb(rename="default::locale")]
+///     locale: Option<String>,           // None resets the global
+/// }
+/// ```
+pub trait GlobalsDelta {
+    fn apply(&self, globals: &mut GlobalsModifier);
+}
+
+/// Collects values of globals set by [`GlobalsDelta`]
+#[derive(Debug, Default)]
+pub struct GlobalsModifier {
+    values: Vec<(String, Value)>,
+}
+
+impl GlobalsModifier {
+    pub fn new() -> GlobalsModifier {
+        GlobalsModifier::default()
+    }
+    /// Set global `name` to `value`
+    ///
+    /// Name is qualified with [`DEFAULT_MODULE`] unless it contains a
+    /// module already. [`Value::Nothing`] means that global is reset to
+    /// its default.
+    pub fn set(&mut self, name: &str, value: impl Into<Value>) {
+        let name = if name.contains("::") {
+            name.to_owned()
+        } else {
+            format!("{}::{}", DEFAULT_MODULE, name)
+        };
+        let value = value.into();
+        match self.values.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, old)) => *old = value,
+            None => self.values.push((name, value)),
+        }
+    }
+    /// Returns qualified names and values of globals in order of setting
+    pub fn values(&self) -> &[(String, Value)] {
+        &self.values
+    }
+    pub fn into_values(self) -> Vec<(String, Value)> {
+        self.values
+    }
+}
+
+/// A set of session configuration overrides
+///
+/// [`SessionConfig`] implements this for the settings known to the client.
+/// Custom implementations can set any other settings.
+pub trait ConfigDelta {
+    fn apply(&self, config: &mut ConfigModifier);
+}
+
+/// Value of a configuration setting
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigValue {
+    Bool(bool),
+    Int(i64),
+    Str(String),
+    Duration(Duration),
+    /// Enum value, e.g. `cfg::AllowBareDDL.NeverAllow`
+    Enum { type_name: &'static str, value: &'static str },
+}
+
+/// Collects configuration settings changed by [`ConfigDelta`]
+#[derive(Debug, Default)]
+pub struct ConfigModifier {
+    values: Vec<(String, Option<ConfigValue>)>,
+}
+
+/// Value of `allow_bare_ddl` setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllowBareDdl {
+    AlwaysAllow,
+    NeverAllow,
+}
+
+/// Typed session configuration
+///
+/// Only settings changed using `with_*` methods are applied, others are
+/// left intact. Use `reset_*` methods to return a setting to its default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionConfig {
+    values: Vec<(&'static str, Option<ConfigValue>)>,
+}
+
+impl ConfigValue {
+    /// EdgeQL expression for the value
+    pub fn to_edgeql(&self) -> String {
+        match self {
+            ConfigValue::Bool(value) => value.to_string(),
+            ConfigValue::Int(value) => value.to_string(),
+            ConfigValue::Str(value) => quote_string(value),
+            ConfigValue::Duration(value) => {
+                format!("<std::duration>'{} microseconds'",
+                        value.as_micros())
+            }
+            ConfigValue::Enum { type_name, value } => {
+                format!("{}.{}", type_name, value)
+            }
+        }
+    }
+}
+
+fn quote_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('\'');
+    for c in value.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\'' => result.push_str("\\'"),
+            c => result.push(c),
+        }
+    }
+    result.push('\'');
+    result
+}
+
+impl ConfigModifier {
+    pub fn new() -> ConfigModifier {
+        ConfigModifier::default()
+    }
+    /// Set configuration setting `name` to `value`
+    pub fn set(&mut self, name: &str, value: ConfigValue) {
+        self.put(name, Some(value));
+    }
+    /// Reset configuration setting `name` to its default
+    pub fn reset(&mut self, name: &str) {
+        self.put(name, None);
+    }
+    fn put(&mut self, name: &str, value: Option<ConfigValue>) {
+        match self.values.iter_mut().find(|(n, _)| n == name) {
+            Some((_, old)) => *old = value,
+            None => self.values.push((name.to_owned(), value)),
+        }
+    }
+    /// Returns names and values of settings, `None` means reset
+    pub fn values(&self) -> &[(String, Option<ConfigValue>)] {
+        &self.values
+    }
+    pub fn into_values(self) -> Vec<(String, Option<ConfigValue>)> {
+        self.values
+    }
+}
+
+macro_rules! config_setting {
+    ($name:ident, $with:ident, $reset:ident, $typ:ty, $doc:expr,
+     |$v:ident| $value:expr) => {
+        #[doc = $doc]
+        pub fn $with(mut self, $v: $typ) -> Self {
+            self.put(stringify!($name), Some($value));
+            self
+        }
+        #[doc = concat!("Reset `", stringify!($name), "` to default")]
+        pub fn $reset(mut self) -> Self {
+            self.put(stringify!($name), None);
+            self
+        }
+    };
+}
+
+impl SessionConfig {
+    pub fn new() -> SessionConfig {
+        SessionConfig::default()
+    }
+    fn put(&mut self, name: &'static str, value: Option<ConfigValue>) {
+        match self.values.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, old)) => *old = value,
+            None => self.values.push((name, value)),
+        }
+    }
+    config_setting!(allow_bare_ddl,
+        with_allow_bare_ddl, reset_allow_bare_ddl, AllowBareDdl,
+        "Whether DDL is allowed outside of migrations",
+        |value| ConfigValue::Enum {
+            type_name: "cfg::AllowBareDDL",
+            value: match value {
+                AllowBareDdl::AlwaysAllow => "AlwaysAllow",
+                AllowBareDdl::NeverAllow => "NeverAllow",
+            },
+        });
+    config_setting!(allow_user_specified_id,
+        with_allow_user_specified_id, reset_allow_user_specified_id, bool,
+        "Whether `id` can be set explicitly in `INSERT`",
+        |value| ConfigValue::Bool(value));
+    config_setting!(apply_access_policies,
+        with_apply_access_policies, reset_apply_access_policies, bool,
+        "Whether access policies are applied to queries",
+        |value| ConfigValue::Bool(value));
+    config_setting!(query_execution_timeout,
+        with_query_execution_timeout, reset_query_execution_timeout,
+        Duration,
+        "Maximum time a single query can run",
+        |value| ConfigValue::Duration(value));
+    config_setting!(session_idle_transaction_timeout,
+        with_idle_transaction_timeout, reset_idle_transaction_timeout,
+        Duration,
+        "Maximum time a transaction can stay idle",
+        |value| ConfigValue::Duration(value));
+}
+
+impl ConfigDelta for SessionConfig {
+    fn apply(&self, config: &mut ConfigModifier) {
+        for (name, value) in &self.values {
+            config.put(name, value.clone());
+        }
+    }
+}
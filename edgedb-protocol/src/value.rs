@@ -1,6 +1,10 @@
+mod normalize;
+pub mod diff;
+
 use crate::codec::{NamedTupleShape, ObjectShape, EnumValue};
 use crate::model::{ LocalDatetime, LocalDate, LocalTime, Duration, Datetime};
-use crate::model::{ BigInt, Decimal, Uuid };
+use crate::model::{ BigInt, Decimal, Uuid, Json, Vector, HalfVector };
+use crate::model::Range;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Value {
@@ -28,6 +32,10 @@ pub enum Value {
     NamedTuple { shape: NamedTupleShape, fields: Vec<Value> },
     Array(Vec<Value>),
     Enum(EnumValue),
+    Vector(Vector),
+    HalfVector(HalfVector),
+    Range(Range<Box<Value>>),
+    MultiRange(Vec<Range<Box<Value>>>),
 }
 
 impl Value {
@@ -58,9 +66,123 @@ impl Value {
             NamedTuple { .. } => "named_tuple",
             Array(..) => "array",
             Enum(..) => "enum",
+            Vector(..) => "ext::pgvector::vector",
+            HalfVector(..) => "ext::pgvector::halfvec",
+            Range(..) => "range",
+            MultiRange(..) => "multirange",
         }
     }
     pub fn empty_tuple() -> Value {
         Value::Tuple(Vec::new())
     }
 }
+
+macro_rules! impl_from {
+    ($($typ:ty => $variant:ident,)*) => {
+        $(
+            impl From<$typ> for Value {
+                fn from(value: $typ) -> Value {
+                    Value::$variant(value.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_from! {
+    Uuid => Uuid,
+    String => Str,
+    &str => Str,
+    Vec<u8> => Bytes,
+    i16 => Int16,
+    i32 => Int32,
+    i64 => Int64,
+    f32 => Float32,
+    f64 => Float64,
+    BigInt => BigInt,
+    Decimal => Decimal,
+    bool => Bool,
+    Datetime => Datetime,
+    LocalDatetime => LocalDatetime,
+    LocalDate => LocalDate,
+    LocalTime => LocalTime,
+    Duration => Duration,
+    Json => Json,
+    Vector => Vector,
+    &[f32] => Vector,
+    HalfVector => HalfVector,
+}
+
+#[cfg(feature="num-bigint")]
+impl std::convert::TryFrom<num_bigint::BigInt> for Value {
+    type Error = crate::model::OutOfRangeError;
+    fn try_from(value: num_bigint::BigInt) -> Result<Value, Self::Error> {
+        Ok(Value::BigInt(std::convert::TryFrom::try_from(value)?))
+    }
+}
+
+#[cfg(feature="bigdecimal")]
+impl std::convert::TryFrom<bigdecimal::BigDecimal> for Value {
+    type Error = crate::model::OutOfRangeError;
+    fn try_from(value: bigdecimal::BigDecimal) -> Result<Value, Self::Error> {
+        Ok(Value::Decimal(std::convert::TryFrom::try_from(value)?))
+    }
+}
+
+#[cfg(any(feature="chrono", feature="time"))]
+macro_rules! impl_try_from_datetime {
+    ($($typ:ty => $variant:ident,)*) => {
+        $(
+            impl std::convert::TryFrom<$typ> for Value {
+                type Error = crate::model::OutOfRangeError;
+                fn try_from(value: $typ) -> Result<Value, Self::Error> {
+                    Ok(Value::$variant(
+                        std::convert::TryFrom::try_from(value)?))
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature="chrono")]
+impl_try_from_datetime! {
+    chrono::NaiveDateTime => LocalDatetime,
+    chrono::NaiveDate => LocalDate,
+    chrono::DateTime<chrono::Utc> => Datetime,
+    chrono::Duration => Duration,
+}
+
+#[cfg(feature="chrono")]
+impl From<chrono::NaiveTime> for Value {
+    fn from(value: chrono::NaiveTime) -> Value {
+        Value::LocalTime(value.into())
+    }
+}
+
+#[cfg(feature="time")]
+impl_try_from_datetime! {
+    time::PrimitiveDateTime => LocalDatetime,
+    time::Date => LocalDate,
+    time::OffsetDateTime => Datetime,
+    time::Duration => Duration,
+}
+
+#[cfg(feature="time")]
+impl From<time::Time> for Value {
+    fn from(value: time::Time) -> Value {
+        Value::LocalTime(value.into())
+    }
+}
+
+impl<T: Into<Value>> From<Range<T>> for Value {
+    fn from(value: Range<T>) -> Value {
+        Value::Range(value.map(|bound| Box::new(bound.into())))
+    }
+}
+
+/// `None` is converted to [`Value::Nothing`]
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Value {
+        value.map(Into::into).unwrap_or(Value::Nothing)
+    }
+}
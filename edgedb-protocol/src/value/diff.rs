@@ -0,0 +1,149 @@
+//! Structural comparison of values
+//!
+//! This is mostly useful in tests to get a readable description of what
+//! differs between expected and actual query results. Values are compared
+//! as is, so use [`Value::normalize`] on both sides first if set element
+//! order or named tuple element order should be ignored.
+use std::fmt;
+
+use crate::value::Value;
+
+
+/// Single difference found by [`diff`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    /// Path to the differing element, e.g. `.friends[2].name`
+    ///
+    /// Empty string means the root value itself.
+    pub path: String,
+    /// Value on the left side, `None` if element is absent there
+    pub left: Option<Value>,
+    /// Value on the right side, `None` if element is absent there
+    pub right: Option<Value>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = if self.path.is_empty() { "<root>" } else { &self.path };
+        write!(f, "{}: ", path)?;
+        match &self.left {
+            Some(v) => write!(f, "{:?}", v)?,
+            None => write!(f, "<missing>")?,
+        }
+        write!(f, " != ")?;
+        match &self.right {
+            Some(v) => write!(f, "{:?}", v)?,
+            None => write!(f, "<missing>")?,
+        }
+        Ok(())
+    }
+}
+
+/// Compute differences between two values
+///
+/// Returns an empty vector if values are equal.
+pub fn diff(left: &Value, right: &Value) -> Vec<Difference> {
+    let mut result = Vec::new();
+    diff_into(&mut result, &mut String::new(), left, right);
+    result
+}
+
+fn push(out: &mut Vec<Difference>, path: &str,
+        left: Option<&Value>, right: Option<&Value>)
+{
+    out.push(Difference {
+        path: path.into(),
+        left: left.cloned(),
+        right: right.cloned(),
+    });
+}
+
+fn diff_seq(out: &mut Vec<Difference>, path: &mut String,
+            left: &[Value], right: &[Value], tuple: bool)
+{
+    let len = path.len();
+    for idx in 0..left.len().max(right.len()) {
+        if tuple {
+            path.push_str(&format!(".{}", idx));
+        } else {
+            path.push_str(&format!("[{}]", idx));
+        }
+        match (left.get(idx), right.get(idx)) {
+            (Some(l), Some(r)) => diff_into(out, path, l, r),
+            (l, r) => push(out, path, l, r),
+        }
+        path.truncate(len);
+    }
+}
+
+fn diff_named<'a, L, R>(out: &mut Vec<Difference>, path: &mut String,
+                        left: L, right: R)
+    where L: Iterator<Item=(&'a str, Option<&'a Value>)>,
+          R: Iterator<Item=(&'a str, Option<&'a Value>)>,
+{
+    let right = right.collect::<Vec<_>>();
+    let mut seen = Vec::with_capacity(right.len());
+    let len = path.len();
+    for (name, l) in left {
+        path.push('.');
+        path.push_str(name);
+        match right.iter().position(|(n, _)| *n == name) {
+            Some(idx) => {
+                seen.push(idx);
+                match (l, right[idx].1) {
+                    (Some(l), Some(r)) => diff_into(out, path, l, r),
+                    (None, None) => {}
+                    (l, r) => push(out, path, l, r),
+                }
+            }
+            None => {
+                if l.is_some() {
+                    push(out, path, l, None);
+                }
+            }
+        }
+        path.truncate(len);
+    }
+    for (idx, (name, r)) in right.iter().enumerate() {
+        if r.is_none() || seen.contains(&idx) {
+            continue;
+        }
+        path.push('.');
+        path.push_str(name);
+        push(out, path, None, *r);
+        path.truncate(len);
+    }
+}
+
+fn diff_into(out: &mut Vec<Difference>, path: &mut String,
+             left: &Value, right: &Value)
+{
+    use Value::*;
+    match (left, right) {
+        (Set(l), Set(r)) | (Array(l), Array(r)) => {
+            diff_seq(out, path, l, r, false);
+        }
+        (Tuple(l), Tuple(r)) => {
+            diff_seq(out, path, l, r, true);
+        }
+        (Object { shape: ls, fields: lf }, Object { shape: rs, fields: rf })
+        => {
+            diff_named(out, path,
+                ls.elements.iter().map(|e| &e.name[..])
+                    .zip(lf.iter().map(|v| v.as_ref())),
+                rs.elements.iter().map(|e| &e.name[..])
+                    .zip(rf.iter().map(|v| v.as_ref())));
+        }
+        (NamedTuple { shape: ls, fields: lf },
+         NamedTuple { shape: rs, fields: rf })
+        => {
+            diff_named(out, path,
+                ls.elements.iter().map(|e| &e.name[..])
+                    .zip(lf.iter().map(Some)),
+                rs.elements.iter().map(|e| &e.name[..])
+                    .zip(rf.iter().map(Some)));
+        }
+        (l, r) if l == r => {}
+        (l, r) => push(out, path, Some(l), Some(r)),
+    }
+}
@@ -0,0 +1,188 @@
+use std::cmp::Ordering;
+
+use crate::codec::{NamedTupleShape, TupleElement};
+use crate::model::Range;
+use crate::value::Value;
+
+
+impl Value {
+    /// Bring value to a canonical form suitable for comparison
+    ///
+    /// Elements of sets are sorted (set order is not guaranteed by the
+    /// server) and elements of named tuples are ordered by name. Nested
+    /// values are normalized recursively. Arrays and objects keep their
+    /// order.
+    pub fn normalize(&mut self) {
+        use Value::*;
+        match self {
+            Set(items) => {
+                items.iter_mut().for_each(Value::normalize);
+                items.sort_by(compare);
+            }
+            Array(items) | Tuple(items) => {
+                items.iter_mut().for_each(Value::normalize);
+            }
+            Object { fields, .. } => {
+                fields.iter_mut().flatten().for_each(Value::normalize);
+            }
+            NamedTuple { shape, fields } => {
+                fields.iter_mut().for_each(Value::normalize);
+                let mut pairs = shape.elements.iter()
+                    .map(|el| el.name.clone())
+                    .zip(fields.drain(..))
+                    .collect::<Vec<_>>();
+                pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+                let mut elements = Vec::with_capacity(pairs.len());
+                for (name, value) in pairs {
+                    elements.push(TupleElement { name });
+                    fields.push(value);
+                }
+                *shape = NamedTupleShape::new(elements);
+            }
+            _ => {}
+        }
+    }
+    /// Returns normalized copy of the value, see [`Value::normalize`]
+    pub fn normalized(mut self) -> Value {
+        self.normalize();
+        self
+    }
+}
+
+fn kind_order(value: &Value) -> u8 {
+    use Value::*;
+    match value {
+        Nothing => 0,
+        Uuid(..) => 1,
+        Str(..) => 2,
+        Bytes(..) => 3,
+        Int16(..) => 4,
+        Int32(..) => 5,
+        Int64(..) => 6,
+        Float32(..) => 7,
+        Float64(..) => 8,
+        BigInt(..) => 9,
+        Decimal(..) => 10,
+        Bool(..) => 11,
+        Datetime(..) => 12,
+        LocalDatetime(..) => 13,
+        LocalDate(..) => 14,
+        LocalTime(..) => 15,
+        Duration(..) => 16,
+        Json(..) => 17,
+        Set(..) => 18,
+        Object { .. } => 19,
+        Tuple(..) => 20,
+        NamedTuple { .. } => 21,
+        Array(..) => 22,
+        Enum(..) => 23,
+        Vector(..) => 24,
+        HalfVector(..) => 25,
+        Range(..) => 26,
+        MultiRange(..) => 27,
+    }
+}
+
+fn compare_seq<'a, I>(a: I, b: I) -> Ordering
+    where I: ExactSizeIterator<Item=&'a Value>,
+{
+    a.len().cmp(&b.len())
+        .then_with(|| {
+            a.zip(b)
+                .map(|(a, b)| compare(a, b))
+                .find(|o| *o != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        })
+}
+
+fn compare_range(a: &Range<Box<Value>>, b: &Range<Box<Value>>) -> Ordering {
+    let bound = |a: Option<&Box<Value>>, b: Option<&Box<Value>>| {
+        match (a, b) {
+            (Some(a), Some(b)) => compare(a, b),
+            (a, b) => a.is_some().cmp(&b.is_some()),
+        }
+    };
+    a.is_empty().cmp(&b.is_empty())
+        .then_with(|| bound(a.lower(), b.lower()))
+        .then_with(|| a.inc_lower().cmp(&b.inc_lower()))
+        .then_with(|| bound(a.upper(), b.upper()))
+        .then_with(|| a.inc_upper().cmp(&b.inc_upper()))
+}
+
+/// Arbitrary but stable total order of values used to sort sets
+fn compare(a: &Value, b: &Value) -> Ordering {
+    use Value::*;
+    match (a, b) {
+        (Uuid(a), Uuid(b)) => a.cmp(b),
+        (Str(a), Str(b)) => a.cmp(b),
+        (Bytes(a), Bytes(b)) => a.cmp(b),
+        (Int16(a), Int16(b)) => a.cmp(b),
+        (Int32(a), Int32(b)) => a.cmp(b),
+        (Int64(a), Int64(b)) => a.cmp(b),
+        (Float32(a), Float32(b)) => a.partial_cmp(b)
+            .unwrap_or_else(|| a.to_bits().cmp(&b.to_bits())),
+        (Float64(a), Float64(b)) => a.partial_cmp(b)
+            .unwrap_or_else(|| a.to_bits().cmp(&b.to_bits())),
+        (BigInt(a), BigInt(b)) => {
+            (a.negative, a.weight, &a.digits)
+                .cmp(&(b.negative, b.weight, &b.digits))
+        }
+        (Decimal(a), Decimal(b)) => {
+            (a.negative, a.weight, a.decimal_digits, &a.digits)
+                .cmp(&(b.negative, b.weight, b.decimal_digits, &b.digits))
+        }
+        (Bool(a), Bool(b)) => a.cmp(b),
+        (Datetime(a), Datetime(b)) => a.cmp(b),
+        (LocalDatetime(a), LocalDatetime(b)) => a.cmp(b),
+        (LocalDate(a), LocalDate(b)) => a.cmp(b),
+        (LocalTime(a), LocalTime(b)) => a.cmp(b),
+        (Duration(a), Duration(b)) => a.cmp(b),
+        (Json(a), Json(b)) => a.cmp(b),
+        (Enum(a), Enum(b)) => (**a).cmp(&**b),
+        (Vector(a), Vector(b)) => {
+            a.len().cmp(&b.len()).then_with(|| {
+                a.iter().zip(b.iter())
+                    .map(|(a, b)| a.to_bits().cmp(&b.to_bits()))
+                    .find(|o| *o != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        (HalfVector(a), HalfVector(b)) => a.as_bits().cmp(b.as_bits()),
+        (Set(a), Set(b)) | (Array(a), Array(b)) | (Tuple(a), Tuple(b))
+        => compare_seq(a.iter(), b.iter()),
+        (Range(a), Range(b)) => compare_range(a, b),
+        (MultiRange(a), MultiRange(b)) => {
+            a.len().cmp(&b.len()).then_with(|| {
+                a.iter().zip(b)
+                    .map(|(a, b)| compare_range(a, b))
+                    .find(|o| *o != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        (NamedTuple { shape: sa, fields: a },
+         NamedTuple { shape: sb, fields: b })
+        => {
+            let names_a = sa.elements.iter().map(|e| &e.name);
+            let names_b = sb.elements.iter().map(|e| &e.name);
+            names_a.cmp(names_b)
+                .then_with(|| compare_seq(a.iter(), b.iter()))
+        }
+        (Object { shape: sa, fields: a },
+         Object { shape: sb, fields: b })
+        => {
+            let names_a = sa.elements.iter().map(|e| &e.name);
+            let names_b = sb.elements.iter().map(|e| &e.name);
+            names_a.cmp(names_b)
+                .then_with(|| {
+                    a.iter().zip(b)
+                        .map(|(a, b)| match (a, b) {
+                            (Some(a), Some(b)) => compare(a, b),
+                            (a, b) => a.is_some().cmp(&b.is_some()),
+                        })
+                        .find(|o| *o != Ordering::Equal)
+                        .unwrap_or(Ordering::Equal)
+                })
+        }
+        _ => kind_order(a).cmp(&kind_order(b)),
+    }
+}
@@ -9,7 +9,9 @@ use edgedb_protocol::client_message::{Prepare, IoFormat, Cardinality};
 use edgedb_protocol::client_message::{DescribeStatement, DescribeAspect};
 use edgedb_protocol::client_message::{SaslInitialResponse};
 use edgedb_protocol::client_message::{SaslResponse};
-use edgedb_protocol::client_message::Restore;
+use edgedb_protocol::client_message::{Dump, Restore};
+use edgedb_protocol::client_message::OptimisticExecute;
+use edgedb_protocol::model::Uuid;
 
 mod base;
 
@@ -65,6 +67,11 @@ fn describe_statement() -> Result<(), Box<dyn Error>> {
         aspect: DescribeAspect::DataDescription,
         statement_name: Bytes::from_static(b"example"),
     }), b"D\0\0\0\x12\0\0T\0\0\0\x07example");
+    encoding_eq!(ClientMessage::DescribeStatement(DescribeStatement {
+        headers: HashMap::new(),
+        aspect: DescribeAspect::DataDescription,
+        statement_name: Bytes::from_static(b""),
+    }), b"D\0\0\0\x0b\0\0T\0\0\0\0");
     Ok(())
 }
 
@@ -78,6 +85,23 @@ fn execute() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn optimistic_execute() -> Result<(), Box<dyn Error>> {
+    encoding_eq!(ClientMessage::OptimisticExecute(OptimisticExecute {
+        headers: HashMap::new(),
+        io_format: IoFormat::Binary,
+        expected_cardinality: Cardinality::Many,
+        command_text: String::from("SELECT 1;"),
+        input_typedesc_id: Uuid::from_u128(0xFF),
+        output_typedesc_id: Uuid::from_u128(0x105),
+        arguments: Bytes::new(),
+    }), b"O\0\0\0\x39\0\0bm\0\0\0\tSELECT 1;\
+          \0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\xff\
+          \0\0\0\0\0\0\0\0\0\0\0\0\0\0\x01\x05\
+          \0\0\0\0");
+    Ok(())
+}
+
 #[test]
 fn sync() -> Result<(), Box<dyn Error>> {
     encoding_eq!(ClientMessage::Sync, b"S\0\0\0\x04");
@@ -124,6 +148,14 @@ fn authentication() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn dump() -> Result<(), Box<dyn Error>> {
+    encoding_eq!(ClientMessage::Dump(Dump {
+        headers: HashMap::new(),
+    }), b">\x00\x00\x00\x06\x00\x00");
+    Ok(())
+}
+
 #[test]
 fn restore() -> Result<(), Box<dyn Error>> {
     encoding_eq!(ClientMessage::Restore(Restore {
@@ -16,6 +16,8 @@ use edgedb_protocol::descriptors::{TupleTypeDescriptor};
 use edgedb_protocol::descriptors::{NamedTupleTypeDescriptor, TupleElement};
 use edgedb_protocol::descriptors::ArrayTypeDescriptor;
 use edgedb_protocol::descriptors::EnumerationTypeDescriptor;
+use edgedb_protocol::descriptors::{RangeTypeDescriptor};
+use edgedb_protocol::descriptors::{MultiRangeTypeDescriptor};
 
 mod base;
 
@@ -446,7 +448,8 @@ fn set_codec() -> Result<(), Box<dyn Error>> {
 #[cfg(feature="num-bigint")]
 fn bigint() -> Result<(), Box<dyn Error>> {
     use num_bigint::BigInt;
-    use std::convert::TryInto;
+    use edgedb_protocol::queryable::{Queryable, Decoder};
+    use std::convert::{TryFrom, TryInto};
     use std::str::FromStr;
 
     let codec = build_codec(Some(TypePos(0)),
@@ -468,6 +471,12 @@ fn bigint() -> Result<(), Box<dyn Error>> {
     encoding_eq!(&codec, b"\0\x01\0\x05\0\0\0\0\0\n",
         Value::BigInt(
             BigInt::from_str("1000000000000000000000")?.try_into()?));
+
+    let decoded = BigInt::decode(&Decoder::default(),
+                                 b"\0\x01\0\x05\0\0\0\0\0\n")?;
+    assert_eq!(decoded, BigInt::from_str("1000000000000000000000")?);
+    let value = Value::try_from(BigInt::from(-15000))?;
+    encoding_eq!(&codec, b"\0\x02\0\x01@\0\0\0\0\x01\x13\x88", value);
     Ok(())
 }
 
@@ -475,7 +484,8 @@ fn bigint() -> Result<(), Box<dyn Error>> {
 #[cfg(feature="bigdecimal")]
 fn decimal() -> Result<(), Box<dyn Error>> {
     use bigdecimal::BigDecimal;
-    use std::convert::TryInto;
+    use edgedb_protocol::queryable::{Queryable, Decoder};
+    use std::convert::{TryFrom, TryInto};
     use std::str::FromStr;
 
     let codec = build_codec(Some(TypePos(0)),
@@ -502,6 +512,12 @@ fn decimal() -> Result<(), Box<dyn Error>> {
         b"\0\x06\0\x0b@\0\0\0\0\x07\x01P\x1cB\x08\x9e$!\0\xc8",
         Value::Decimal(BigDecimal::from_str(
             "-7033672342206924902e26")?.try_into()?));
+
+    let decoded = BigDecimal::decode(&Decoder::default(),
+                                     b"\0\x01\0\0\0\0\0\x02\0*")?;
+    assert_eq!(decoded, BigDecimal::from_str("42.00")?);
+    let value = Value::try_from(BigDecimal::from_str("1e100")?)?;
+    encoding_eq!(&codec, b"\0\x01\0\x19\0\0\0\0\0\x01", value);
     Ok(())
 }
 
@@ -583,6 +599,85 @@ fn local_time() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+#[cfg(feature="chrono")]
+fn chrono_scalars() -> Result<(), Box<dyn Error>> {
+    use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+    use edgedb_protocol::queryable::{Queryable, Decoder};
+    use std::convert::TryFrom;
+
+    let codec = build_codec(Some(TypePos(0)),
+        &[
+            Descriptor::BaseScalar(BaseScalarTypeDescriptor {
+                id: "00000000-0000-0000-0000-00000000010a".parse()?,
+            })
+        ]
+    )?;
+    let bytes = b"\0\x02=^\x1bTc\xe7";
+    let datetime = Utc.timestamp_opt(1577109148, 156903000).unwrap();
+    assert_eq!(DateTime::<Utc>::decode(&Decoder::default(), bytes)?,
+               datetime);
+    encoding_eq!(&codec, bytes, Value::try_from(datetime)?);
+
+    let codec = build_codec(Some(TypePos(0)),
+        &[
+            Descriptor::BaseScalar(BaseScalarTypeDescriptor {
+                id: "00000000-0000-0000-0000-00000000010c".parse()?,
+            })
+        ]
+    )?;
+    let bytes = b"\0\0\x1c\x80";
+    let date = NaiveDate::from_ymd_opt(2019, 12, 23).unwrap();
+    assert_eq!(NaiveDate::decode(&Decoder::default(), bytes)?, date);
+    encoding_eq!(&codec, bytes, Value::try_from(date)?);
+
+    let duration = chrono::Duration::decode(&Decoder::default(),
+        b"\0\0\0\0\0\x6b\x0a\x58\0\0\0\0\0\0\0\0")?;
+    assert_eq!(duration, chrono::Duration::microseconds(7015_000));
+    Ok(())
+}
+
+#[test]
+#[cfg(feature="time")]
+fn time_scalars() -> Result<(), Box<dyn Error>> {
+    use edgedb_protocol::queryable::{Queryable, Decoder};
+    use std::convert::TryFrom;
+    use time::{Date, Month, OffsetDateTime};
+
+    let codec = build_codec(Some(TypePos(0)),
+        &[
+            Descriptor::BaseScalar(BaseScalarTypeDescriptor {
+                id: "00000000-0000-0000-0000-00000000010a".parse()?,
+            })
+        ]
+    )?;
+    let bytes = b"\0\x02=^\x1bTc\xe7";
+    let datetime = OffsetDateTime::from_unix_timestamp_nanos(
+        1_577_109_148_156_903_000)?;
+    assert_eq!(OffsetDateTime::decode(&Decoder::default(), bytes)?,
+               datetime);
+    encoding_eq!(&codec, bytes, Value::try_from(datetime)?);
+
+    let codec = build_codec(Some(TypePos(0)),
+        &[
+            Descriptor::BaseScalar(BaseScalarTypeDescriptor {
+                id: "00000000-0000-0000-0000-00000000010c".parse()?,
+            })
+        ]
+    )?;
+    let bytes = b"\0\0\x1c\x80";
+    let date = Date::from_calendar_date(2019, Month::December, 23)?;
+    assert_eq!(Date::decode(&Decoder::default(), bytes)?, date);
+    encoding_eq!(&codec, bytes, Value::try_from(date)?);
+    // year 19988 is valid in EdgeDB but out of range for `time`
+    assert!(Date::decode(&Decoder::default(), b"\0\x64\x40\x10").is_err());
+
+    let duration = time::Duration::decode(&Decoder::default(),
+        b"\0\0\0\0\0\x6b\x0a\x58\0\0\0\0\0\0\0\0")?;
+    assert_eq!(duration, time::Duration::microseconds(7_015_000));
+    Ok(())
+}
+
 #[test]
 fn json() -> Result<(), Box<dyn Error>> {
     let codec = build_codec(Some(TypePos(0)),
@@ -598,6 +693,56 @@ fn json() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn pgvector() -> Result<(), Box<dyn Error>> {
+    use edgedb_protocol::model::Vector;
+    use edgedb_protocol::queryable::{Queryable, Decoder};
+
+    let codec = build_codec(Some(TypePos(0)),
+        &[
+            Descriptor::BaseScalar(BaseScalarTypeDescriptor {
+                id: "9565dd88-04f5-11ee-a691-0b6ebe179825".parse()?,
+            })
+        ]
+    )?;
+
+    let bytes = b"\0\x02\0\0\x3f\x80\0\0\xc0\0\0\0";
+    encoding_eq!(&codec, bytes, Value::from(&[1.0f32, -2.0][..]));
+    encoding_eq!(&codec, b"\0\0\0\0", Value::Vector(Vector(vec![])));
+    assert!(decode(&codec, b"\0\x02\0\0\x3f\x80\0\0").is_err());
+
+    let vector = Vector::decode(&Decoder::default(), bytes)?;
+    assert_eq!(Vec::from(vector), vec![1.0, -2.0]);
+    Ok(())
+}
+
+#[test]
+fn pgvector_halfvec() -> Result<(), Box<dyn Error>> {
+    use edgedb_protocol::model::HalfVector;
+
+    let codec = build_codec(Some(TypePos(0)),
+        &[
+            Descriptor::BaseScalar(BaseScalarTypeDescriptor {
+                id: "4ba84534-188e-43b4-a7ce-cea2af0f405b".parse()?,
+            })
+        ]
+    )?;
+
+    // 1.0 and -2.0 in half precision
+    let value = HalfVector::from_bits(vec![0x3c00, 0xc000]);
+    encoding_eq!(&codec, b"\0\x02\0\0\x3c\0\xc0\0",
+        Value::HalfVector(value.clone()));
+    assert!(decode(&codec, b"\0\x02\0\0\x3c\0").is_err());
+
+    #[cfg(feature="with-half")] {
+        use half::f16;
+        let halves = [f16::from_f32(1.0), f16::from_f32(-2.0)];
+        assert_eq!(HalfVector::from(&halves[..]), value);
+        assert_eq!(Vec::<f16>::from(value), halves.to_vec());
+    }
+    Ok(())
+}
+
 #[test]
 fn custom_scalar() -> Result<(), Box<dyn Error>> {
     let codec = build_codec(Some(TypePos(0)),
@@ -735,6 +880,57 @@ fn array() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn range() -> Result<(), Box<dyn Error>> {
+    use edgedb_protocol::model::Range;
+    use edgedb_protocol::queryable::{Queryable, Decoder};
+
+    let descriptors = [
+        Descriptor::BaseScalar(
+            BaseScalarTypeDescriptor {
+                id: "00000000-0000-0000-0000-000000000105".parse()?,
+            },
+        ),
+        Descriptor::Range(
+            RangeTypeDescriptor {
+                id: "cb3c6f9a-2d53-5a5e-9d7b-8a3b05e1cd08".parse()?,
+                type_pos: TypePos(0),
+            },
+        ),
+        Descriptor::MultiRange(
+            MultiRangeTypeDescriptor {
+                id: "4c4cd0a5-e7d3-5c69-a3f5-1b8c9f6e2e2a".parse()?,
+                type_pos: TypePos(0),
+            },
+        ),
+    ];
+    let codec = build_codec(Some(TypePos(1)), &descriptors)?;
+
+    let bytes = bconcat!(b"\x02"
+        b"\0\0\0\x08\0\0\0\0\0\0\0\x01"
+        b"\0\0\0\x08\0\0\0\0\0\0\0\x0a");
+    encoding_eq!(&codec, bytes, Value::from(Range::from(1i64..10)));
+    encoding_eq!(&codec, bconcat!(b"\x0c\0\0\0\x08\0\0\0\0\0\0\0\x05"),
+        Value::from(Range::with_inclusive(None, Some(5i64), true, true)));
+    encoding_eq!(&codec, b"\x01", Value::from(Range::<i64>::empty()));
+    assert!(decode(&codec, b"\x02\0\0\0\x08\0\0\0\0").is_err());
+
+    let range = Range::<i64>::decode(&Decoder::default(), bytes)?;
+    assert_eq!(range, Range::from(1..10));
+
+    let codec = build_codec(Some(TypePos(2)), &descriptors)?;
+    encoding_eq!(&codec, bconcat!(b"\0\0\0\x02"
+            b"\0\0\0\x19" b"\x02"
+            b"\0\0\0\x08\0\0\0\0\0\0\0\x01"
+            b"\0\0\0\x08\0\0\0\0\0\0\0\x0a"
+            b"\0\0\0\x01" b"\x01"),
+        Value::MultiRange(vec![
+            Range::from(1i64..10).map(|x| Box::new(Value::Int64(x))),
+            Range::empty(),
+        ]));
+    Ok(())
+}
+
 #[test]
 fn enums() -> Result<(), Box<dyn Error>> {
     let codec = build_codec(Some(TypePos(0)),
@@ -158,54 +158,21 @@ fn command_data_description() -> Result<(), Box<dyn Error>> {
 }
 
 #[test]
-fn data() -> Result<(), Box<dyn Error>> {
-    encoding_eq!(ServerMessage::Data(Data {
-        data: vec![Bytes::from_static(b"\0\0\0\0\0\0\0\x01")],
-    }), b"D\0\0\0\x12\0\x01\0\0\0\x08\0\0\0\0\0\0\0\x01");
-    Ok(())
-}
-
-#[test]
-fn restore_ready() -> Result<(), Box<dyn Error>> {
-    encoding_eq!(ServerMessage::RestoreReady(RestoreReady {
-        jobs: 1,
+fn command_data_description_bytes() -> Result<(), Box<dyn Error>> {
+    let desc = CommandDataDescription {
         headers: HashMap::new(),
-    }), b"+\0\0\0\x08\0\0\0\x01");
-    Ok(())
-}
-
-#[test]
-fn authentication() -> Result<(), Box<dyn Error>> {
-    encoding_eq!(
-        ServerMessage::Authentication(Authentication::Ok),
-        b"\x52\0\0\0\x08\x00\x00\x00\x00");
-    encoding_eq!(
-        ServerMessage::Authentication(Authentication::Sasl {
-            methods: vec![String::from("SCRAM-SHA-256")],
-        }),
-        b"R\0\0\0\x1d\0\0\0\n\0\0\0\x01\0\0\0\rSCRAM-SHA-256");
-    encoding_eq!(
-        ServerMessage::Authentication(Authentication::SaslContinue {
-            data: Bytes::from_static(b"sasl_interim_data"),
-        }),
-        b"R\0\0\0\x1d\x00\x00\x00\x0b\0\0\0\x11sasl_interim_data");
-    encoding_eq!(
-        ServerMessage::Authentication(Authentication::SaslFinal {
-            data: Bytes::from_static(b"sasl_final_data"),
-        }),
-        b"R\0\0\0\x1b\x00\x00\x00\x0c\0\0\0\x0fsasl_final_data");
-    Ok(())
-}
-
-#[test]
-fn log_message() -> Result<(), Box<dyn Error>> {
-    encoding_eq!(
-        ServerMessage::LogMessage(LogMessage {
-            severity: MessageSeverity::Notice,
-            code: 0xF0_00_00_00,
-            text: "changing system config".into(),
-            attributes: map!{},
-        }),
-        b"L\0\0\0%<\xf0\0\0\0\0\0\0\x16changing system config\0\0");
+        result_cardinality: Cardinality::One,
+        input_typedesc_id: Uuid::from_u128(0xFF),
+        input_typedesc: Bytes::from_static(
+            b"\x04\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\xff\0\0"),
+        output_typedesc_id: Uuid::from_u128(0x105),
+        output_typedesc: Bytes::from_static(
+            b"\x02\0\0\0\0\0\0\0\0\0\0\0\0\0\0\x01\x05"),
+    };
+    let data = desc.to_bytes()?;
+    assert_eq!(CommandDataDescription::from_bytes(data.clone())?, desc);
+    let mut extra = BytesMut::from(&data[..]);
+    extra.extend_from_slice(b"\0");
+    assert!(CommandDataDescription::from_bytes(extra.freeze()).is_err());
     Ok(())
 }
@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use edgedb_protocol::state::{ConfigDelta, ConfigModifier, ConfigValue};
+use edgedb_protocol::state::{SessionConfig, AllowBareDdl, GlobalsModifier};
+use edgedb_protocol::value::Value;
+
+
+#[test]
+fn session_config() {
+    let config = SessionConfig::new()
+        .with_allow_bare_ddl(AllowBareDdl::NeverAllow)
+        .with_idle_transaction_timeout(Duration::from_secs(1))
+        .with_allow_bare_ddl(AllowBareDdl::AlwaysAllow)
+        .reset_apply_access_policies();
+    let mut modifier = ConfigModifier::new();
+    config.apply(&mut modifier);
+    assert_eq!(modifier.into_values(), vec![
+        ("allow_bare_ddl".into(), Some(ConfigValue::Enum {
+            type_name: "cfg::AllowBareDDL",
+            value: "AlwaysAllow",
+        })),
+        ("session_idle_transaction_timeout".into(),
+         Some(ConfigValue::Duration(Duration::from_secs(1)))),
+        ("apply_access_policies".into(), None),
+    ]);
+}
+
+#[test]
+fn config_value() {
+    assert_eq!(ConfigValue::Bool(true).to_edgeql(), "true");
+    assert_eq!(ConfigValue::Int(-5).to_edgeql(), "-5");
+    assert_eq!(ConfigValue::Str(r"it's a \ test".into()).to_edgeql(),
+               r"'it\'s a \\ test'");
+    assert_eq!(ConfigValue::Duration(Duration::from_millis(2)).to_edgeql(),
+               "<std::duration>'2000 microseconds'");
+}
+
+#[test]
+fn globals() {
+    let mut modifier = GlobalsModifier::new();
+    modifier.set("user_id", 1i64);
+    modifier.set("app::locale", "en");
+    modifier.set("default::user_id", None::<i64>);
+    assert_eq!(modifier.values(), &[
+        ("default::user_id".into(), Value::Nothing),
+        ("app::locale".into(), Value::Str("en".into())),
+    ]);
+}
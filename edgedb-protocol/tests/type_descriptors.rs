@@ -7,6 +7,7 @@ use edgedb_protocol::descriptors::{Descriptor, TypePos};
 use edgedb_protocol::descriptors::TupleTypeDescriptor;
 use edgedb_protocol::descriptors::{ObjectShapeDescriptor, ShapeElement};
 use edgedb_protocol::descriptors::BaseScalarTypeDescriptor;
+use edgedb_protocol::descriptors::RangeTypeDescriptor;
 
 mod base;
 
@@ -119,3 +120,22 @@ fn object() -> Result<(), Box<dyn Error>> {
         ]);
     Ok(())
 }
+
+#[test]
+fn range() -> Result<(), Box<dyn Error>> {
+    // `SELECT range(1, 10)`
+    assert_eq!(decode(bconcat!(
+            b"\x02\0\0\0\0\0\0\0\0\0\0\0\0\0\0\x01\x05"
+            b"\x09\x01\x02\x03\x04\x05\x06\x07\x08"
+            b"\x09\x0a\x0b\x0c\x0d\x0e\x0f\x10\0\0"))?,
+        vec![
+            Descriptor::BaseScalar(BaseScalarTypeDescriptor {
+                id: "00000000-0000-0000-0000-000000000105".parse()?,
+            }),
+            Descriptor::Range(RangeTypeDescriptor {
+                id: "01020304-0506-0708-090a-0b0c0d0e0f10".parse()?,
+                type_pos: TypePos(0),
+            }),
+        ]);
+    Ok(())
+}
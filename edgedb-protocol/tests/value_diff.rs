@@ -0,0 +1,156 @@
+use edgedb_protocol::codec::{ObjectShape, ShapeElement};
+use edgedb_protocol::codec::{NamedTupleShape, TupleElement};
+use edgedb_protocol::value::Value;
+use edgedb_protocol::value::diff::{diff, Difference};
+
+
+fn object(names: &[&str], fields: Vec<Option<Value>>) -> Value {
+    Value::Object {
+        shape: ObjectShape::new(names.iter().map(|name| ShapeElement {
+            flag_implicit: false,
+            flag_link_property: false,
+            flag_link: false,
+            name: name.to_string(),
+        }).collect()),
+        fields,
+    }
+}
+
+fn named_tuple(names: &[&str], fields: Vec<Value>) -> Value {
+    Value::NamedTuple {
+        shape: NamedTupleShape::new(names.iter().map(|name| TupleElement {
+            name: name.to_string(),
+        }).collect()),
+        fields,
+    }
+}
+
+#[test]
+fn normalize_set() {
+    let value = Value::Set(vec![
+        Value::Int64(3),
+        Value::Int64(1),
+        Value::Int64(2),
+    ]);
+    assert_eq!(value.normalized(), Value::Set(vec![
+        Value::Int64(1),
+        Value::Int64(2),
+        Value::Int64(3),
+    ]));
+}
+
+#[test]
+fn normalize_array_keeps_order() {
+    let value = Value::Array(vec![
+        Value::Set(vec![Value::Str("b".into()), Value::Str("a".into())]),
+        Value::Str("z".into()),
+        Value::Str("y".into()),
+    ]);
+    assert_eq!(value.normalized(), Value::Array(vec![
+        Value::Set(vec![Value::Str("a".into()), Value::Str("b".into())]),
+        Value::Str("z".into()),
+        Value::Str("y".into()),
+    ]));
+}
+
+#[test]
+fn normalize_named_tuple() {
+    let value = named_tuple(&["b", "a"], vec![
+        Value::Int32(2),
+        Value::Int32(1),
+    ]);
+    assert_eq!(value.normalized(), named_tuple(&["a", "b"], vec![
+        Value::Int32(1),
+        Value::Int32(2),
+    ]));
+}
+
+#[test]
+fn equal() {
+    let value = object(&["id", "name"], vec![
+        Some(Value::Int64(1)),
+        Some(Value::Str("John".into())),
+    ]);
+    assert_eq!(diff(&value, &value.clone()), vec![]);
+}
+
+#[test]
+fn nested() {
+    let left = object(&["name", "friends"], vec![
+        Some(Value::Str("John".into())),
+        Some(Value::Set(vec![
+            object(&["name"], vec![Some(Value::Str("Mary".into()))]),
+            object(&["name"], vec![Some(Value::Str("Bob".into()))]),
+        ])),
+    ]);
+    let right = object(&["name", "friends"], vec![
+        Some(Value::Str("John".into())),
+        Some(Value::Set(vec![
+            object(&["name"], vec![Some(Value::Str("Mary".into()))]),
+            object(&["name"], vec![Some(Value::Str("Alice".into()))]),
+            object(&["name"], vec![None]),
+        ])),
+    ]);
+    let result = diff(&left, &right);
+    assert_eq!(result, vec![
+        Difference {
+            path: ".friends[1].name".into(),
+            left: Some(Value::Str("Bob".into())),
+            right: Some(Value::Str("Alice".into())),
+        },
+        Difference {
+            path: ".friends[2]".into(),
+            left: None,
+            right: Some(object(&["name"], vec![None])),
+        },
+    ]);
+    assert_eq!(result[0].to_string(),
+        r#".friends[1].name: Str("Bob") != Str("Alice")"#);
+}
+
+#[test]
+fn missing_fields() {
+    let left = object(&["a", "b"], vec![
+        Some(Value::Int16(1)),
+        Some(Value::Int16(2)),
+    ]);
+    let right = object(&["a", "c"], vec![
+        Some(Value::Int16(1)),
+        None,
+    ]);
+    assert_eq!(diff(&left, &right), vec![
+        Difference {
+            path: ".b".into(),
+            left: Some(Value::Int16(2)),
+            right: None,
+        },
+    ]);
+}
+
+#[test]
+fn root_and_tuple() {
+    assert_eq!(diff(&Value::Int64(1), &Value::Str("1".into())), vec![
+        Difference {
+            path: "".into(),
+            left: Some(Value::Int64(1)),
+            right: Some(Value::Str("1".into())),
+        },
+    ]);
+    let left = Value::Tuple(vec![Value::Bool(true), Value::Bool(false)]);
+    let right = Value::Tuple(vec![Value::Bool(true), Value::Bool(true)]);
+    assert_eq!(diff(&left, &right)[0].path, ".1");
+}
+
+#[test]
+fn normalized_diff() {
+    let left = named_tuple(&["x", "y"], vec![
+        Value::Set(vec![Value::Int64(1), Value::Int64(2)]),
+        Value::Nothing,
+    ]);
+    let right = named_tuple(&["y", "x"], vec![
+        Value::Nothing,
+        Value::Set(vec![Value::Int64(2), Value::Int64(1)]),
+    ]);
+    assert!(!diff(&left, &right).is_empty());
+    assert_eq!(diff(&left.normalized(), &right.normalized()), vec![]);
+}